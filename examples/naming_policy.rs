@@ -0,0 +1,74 @@
+//! Checks a Filament program with an out-of-tree pass that rejects invocation
+//! names that are not in snake case.
+//!
+//! Usage: cargo run --example naming_policy -- <file.fil> [library]
+use fil_ir::{self as ir, Ctx};
+use filament::{
+    ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options::Options,
+    pipeline::Pipeline,
+};
+
+/// Reports invocations whose names contain uppercase letters
+struct NamingPolicy {
+    violations: u64,
+}
+
+impl Construct for NamingPolicy {
+    fn from(_: &Options, _: &mut ir::Context) -> Self {
+        Self { violations: 0 }
+    }
+
+    // Violations are counted across all components
+    fn clear_data(&mut self) {}
+}
+
+impl Visitor for NamingPolicy {
+    fn name() -> &'static str {
+        "naming-policy"
+    }
+
+    fn invoke(&mut self, idx: ir::InvIdx, data: &mut VisitorData) -> Action {
+        let comp = &data.comp;
+        if let Some(info) = comp.get(comp.get(idx).info).as_invoke() {
+            if info.name.as_ref().chars().any(|c| c.is_ascii_uppercase()) {
+                eprintln!(
+                    "error: invocation `{}' is not in snake case",
+                    info.name
+                );
+                self.violations += 1;
+            }
+        }
+        Action::Continue
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        (self.violations > 0).then_some(self.violations)
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(input) = args.next() else {
+        eprintln!("usage: naming_policy <file.fil> [library]");
+        std::process::exit(1)
+    };
+    let opts = Options {
+        input: Some(input.into()),
+        library: args.next().unwrap_or_else(|| ".".to_string()).into(),
+        check: true,
+        ..Options::default()
+    };
+    let pipeline = Pipeline::new(opts)
+        .register::<NamingPolicy>(
+            "reject invocation names that are not in snake case",
+            &[],
+            &[],
+            &[],
+        )
+        .insert_after("type-check", NamingPolicy::name());
+    if let Err(failure) = pipeline.lower(None) {
+        eprintln!("Checking failed with {} errors.", failure.errors);
+        std::process::exit(1)
+    }
+}
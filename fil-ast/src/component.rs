@@ -1,17 +1,28 @@
 use super::{Command, Id, Signature};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
+#[derive(Default, Clone, Serialize, Deserialize)]
+/// Attributes attached to a component definition
+pub struct Attributes {
+    /// Lint codes or groups allowed within the component using `#[allow(..)]`
+    pub allow: Vec<Id>,
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
 /// A component in Filament
 pub struct Component {
     // Signature of this component
     pub sig: Signature,
     /// Model for this component
     pub body: Vec<Command>,
+    /// Attributes attached to the component
+    pub attrs: Attributes,
 }
 
 impl Component {
-    pub fn new(sig: Signature, body: Vec<Command>) -> Self {
-        Self { sig, body }
+    pub fn new(sig: Signature, body: Vec<Command>, attrs: Attributes) -> Self {
+        Self { sig, body, attrs }
     }
 }
 
@@ -23,8 +34,14 @@ pub struct Namespace {
     pub externs: Vec<(String, Vec<Signature>)>,
     /// Components defined in this file
     pub components: Vec<Component>,
-    /// Top-level component id
-    pub toplevel: String,
+    /// Names of the top-level components
+    pub toplevels: Vec<String>,
+    /// Values of the parameters of the top-level components given on the
+    /// command line
+    pub bindings: Vec<(String, u64)>,
+    /// Components defined by precompiled libraries. Their proof obligations
+    /// have already been discharged.
+    pub precompiled: Vec<Id>,
 }
 
 impl Namespace {
@@ -35,11 +52,26 @@ impl Namespace {
             .flat_map(|(_, comps)| comps.iter().map(|s| (*s.name.inner(), s)))
     }
 
-    /// Get the index to the top-level component.
-    /// Currently, this is the distinguished "main" component
-    pub fn main_idx(&self) -> Option<usize> {
-        self.components.iter().position(|c| {
-            c.sig.name.inner() == &Id::from(self.toplevel.clone())
-        })
+    /// Get the indices of the top-level components that are defined.
+    /// By default, this is the distinguished "main" component
+    pub fn toplevel_idxs(&self) -> Vec<usize> {
+        self.toplevels
+            .iter()
+            .filter_map(|name| {
+                self.components
+                    .iter()
+                    .position(|c| c.sig.name.inner() == &Id::from(name.clone()))
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Names of the components without signature parameters
+    pub fn concrete_components(&self) -> Vec<String> {
+        self.components
+            .iter()
+            .filter(|c| c.sig.params.is_empty())
+            .map(|c| c.sig.name.inner().to_string())
+            .collect()
     }
 }
@@ -1,7 +1,8 @@
 use super::{Binding, Expr, Time, TimeSub};
+use serde::{Deserialize, Serialize};
 
 /// Ordering operator for constraints
-#[derive(Hash, Eq, PartialEq, Clone)]
+#[derive(Hash, Eq, PartialEq, Clone, Serialize, Deserialize)]
 pub enum OrderOp {
     Gt,
     Gte,
@@ -19,7 +20,7 @@ impl std::fmt::Display for OrderOp {
 }
 
 // An ordering constraint
-#[derive(Clone, Eq, PartialEq, Hash)]
+#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct OrderConstraint<T> {
     pub left: T,
     pub right: T,
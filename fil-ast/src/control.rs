@@ -1,9 +1,10 @@
 use super::{
     Binding, Expr, Id, Implication, Loc, OrderConstraint, Range, Time,
 };
+use serde::{Deserialize, Serialize};
 use struct_variant::struct_variant;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// Access into a bundle
 pub struct Access {
     pub start: Expr,
@@ -35,7 +36,7 @@ impl From<Expr> for Access {
 /// A port mentioned in the program
 // XXX(rachit): the bundle and non-bundle variants can be unified because
 // astconv treats them the same anyways.
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum Port {
     /// A port on this component
     This(Loc<Id>),
@@ -106,7 +107,7 @@ impl Port {
 }
 
 #[struct_variant]
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// Command in a component
 pub enum Command {
     Invoke,
@@ -118,9 +119,10 @@ pub enum Command {
     If,
     Bundle,
     Exists,
+    ValueAssert,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A new component instance
 pub struct Instance {
     /// Name of the instance.
@@ -148,7 +150,7 @@ impl Instance {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// An Invocation
 pub struct Invoke {
     /// Name of the variable being assigned
@@ -188,7 +190,7 @@ impl Invoke {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// An `assert` or `assume` statement.
 /// Contains a guard
 /// If `checked` is true, the statement is checked to be statically true.
@@ -227,7 +229,36 @@ impl Fact {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
+/// A side of an assertion on the values of ports
+pub enum ValueTerm {
+    /// The value carried by a port
+    Port(Loc<Port>),
+    /// A parameter expression
+    Expr(Loc<Expr>),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+/// An assertion on the values carried by ports during an interval:
+/// ```text
+/// assert value(out) < 256 in ['G+2, 'G+3];
+/// ```
+/// Unlike facts, these are checked by bounded model checking of the design.
+pub struct ValueAssert {
+    pub cons: Loc<OrderConstraint<ValueTerm>>,
+    pub range: Loc<Range>,
+}
+
+impl ValueAssert {
+    pub fn new(
+        cons: Loc<OrderConstraint<ValueTerm>>,
+        range: Loc<Range>,
+    ) -> Self {
+        Self { cons, range }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 /// A Connection between ports
 pub struct Connect {
     /// Destination port
@@ -242,7 +273,7 @@ impl Connect {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A generative loop:
 /// ```
 /// for i in 0..W { ... }
@@ -274,7 +305,7 @@ impl ForLoop {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A conditional statement:
 /// The `then` branch is checked assuming that the condition is true and the `else` branch is checked
 /// assuming that the condition is false.
@@ -294,7 +325,7 @@ impl If {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// The type of the bundle:
 /// ```
 /// for<i> ['G+i, 'G+i+1] W
@@ -355,7 +386,7 @@ impl BundleType {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// Represents a bundle of wires with timing guarantees
 /// ```
 /// bundle f[10]: for<i> ['G+i, 'G+i+1] W;
@@ -382,14 +413,14 @@ impl Bundle {
 }
 
 /// A let-bound parameter
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ParamLet {
     pub name: Loc<Id>,
     /// The expression for the parameter binding
     pub expr: Expr,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// Binding for an existentially quantified parameter
 pub struct Exists {
     /// The existentially quantified parameter
@@ -1,9 +1,12 @@
 use super::{Binding, Id, Loc};
 use fil_utils::Error;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 /// Binary operation over expressions
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Serialize, Deserialize,
+)]
 pub enum Op {
     Add,
     Sub,
@@ -24,7 +27,9 @@ impl std::fmt::Display for Op {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Serialize, Deserialize,
+)]
 /// A unary uninterpreted function over integers.
 pub enum Fn {
     /// The `pow2` function
@@ -50,29 +55,44 @@ impl std::fmt::Display for Fn {
 }
 
 impl Fn {
+    /// Evaluate the function. Panics if the result is not representable.
     pub fn eval(self, args: Vec<u64>) -> u64 {
+        let n = args.len();
+        self.checked_eval(args).unwrap_or_else(|| {
+            panic!("Function {self} is undefined for the {n} given arguments")
+        })
+    }
+
+    /// Evaluate the function or return `None` if the result is undefined or
+    /// does not fit in a `u64`.
+    pub fn checked_eval(self, args: Vec<u64>) -> Option<u64> {
         match (self, &*args) {
-            (Fn::Pow2, &[n]) => 2u64.pow(n as u32),
-            (Fn::Log2, &[n]) => (n as f64).log2().ceil() as u64,
-            (Fn::SinB, &[num, den]) => {
+            (Fn::Pow2, &[n]) => 2u64.checked_pow(u32::try_from(n).ok()?),
+            // Ceiling of the base 2 logarithm
+            (Fn::Log2, &[0]) => None,
+            (Fn::Log2, &[n]) => {
+                Some((u64::BITS - (n - 1).leading_zeros()) as u64)
+            }
+            (Fn::SinB, &[_, 0]) | (Fn::CosB, &[_, 0]) => None,
+            (Fn::SinB, &[num, den]) => Some(
                 ((2. * std::f64::consts::PI * (num as f64) / (den as f64)).sin()
                     as f32)
-                    .to_bits() as u64
-            }
-            (Fn::CosB, &[num, den]) => {
+                    .to_bits() as u64,
+            ),
+            (Fn::CosB, &[num, den]) => Some(
                 ((2. * std::f64::consts::PI * (num as f64) / (den as f64)).cos()
                     as f32)
-                    .to_bits() as u64
-            }
+                    .to_bits() as u64,
+            ),
             (Fn::BitRev, &[n, numbits]) => {
                 let mut n = n;
-                let mut rev = 0;
+                let mut rev = 0u64;
                 for _ in 0..numbits {
                     rev <<= 1;
                     rev |= n & 1;
                     n >>= 1;
                 }
-                rev
+                Some(rev)
             }
             _ => unreachable!(
                 "Function {} did not expect {} arguments.",
@@ -84,7 +104,7 @@ impl Fn {
 }
 
 /// An expression containing integers and abstract variables
-#[derive(Clone, Hash, Debug)]
+#[derive(Clone, Hash, Debug, Serialize, Deserialize)]
 pub enum Expr {
     Concrete(u64),
     Abstract(Loc<Id>),
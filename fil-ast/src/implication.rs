@@ -1,8 +1,9 @@
 use super::{Binding, Expr, OrderConstraint};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 /// A type representing the expression a => b
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Implication<T> {
     pub guard: Option<OrderConstraint<T>>,
     pub cons: OrderConstraint<T>,
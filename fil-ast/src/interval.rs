@@ -1,8 +1,9 @@
 use super::{Binding, Constraint, Expr, OrderConstraint, Time, TimeSub};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 /// A range over time representation
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Range {
     pub start: Time,
     pub end: Time,
@@ -12,11 +12,11 @@ mod signature;
 mod time;
 
 pub use bind_map::Binding;
-pub use component::{Component, Namespace};
+pub use component::{Attributes, Component, Namespace};
 pub use constraint::{Constraint, OrderConstraint, OrderOp};
 pub use control::{
     Access, Bundle, BundleType, Command, Connect, Exists, Fact, ForLoop, If,
-    Instance, Invoke, ParamLet, Port,
+    Instance, Invoke, ParamLet, Port, ValueAssert, ValueTerm,
 };
 pub use expr::{Expr, Fn, Op};
 pub use fil_utils::Id;
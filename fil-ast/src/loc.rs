@@ -1,8 +1,9 @@
 use fil_utils::GPosIdx;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::hash::Hash;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// A type that contains several position objects and contains and inner value.
 pub struct Loc<T: Clone> {
     inner: T,
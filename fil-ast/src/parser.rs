@@ -4,7 +4,7 @@
 //! Parser for Filament programs.
 use crate::{self as ast, Loc, TimeSub};
 use fil_utils::{self as utils, FilamentResult};
-use fil_utils::{FileIdx, GPosIdx, GlobalPositionTable};
+use fil_utils::{FileIdx, GPosIdx, GlobalPositionTable, Lint};
 use itertools::Itertools;
 use pest::pratt_parser::{Assoc, Op, PrattParser};
 use pest_consume::{match_nodes, Error, Parser};
@@ -39,7 +39,7 @@ lazy_static::lazy_static! {
 
 pub enum ExtOrComp {
     Ext((String, Vec<ast::Signature>)),
-    Comp(ast::Component),
+    Comp(Box<ast::Component>),
 }
 
 #[derive(Clone)]
@@ -776,6 +776,32 @@ impl FilamentParser {
         ))
     }
 
+    fn value_term(input: Node) -> ParseResult<ast::ValueTerm> {
+        Ok(match_nodes!(
+            input.into_children();
+            [port(port)] => ast::ValueTerm::Port(port),
+            [expr(e)] => ast::ValueTerm::Expr(e),
+        ))
+    }
+
+    fn value_assert(input: Node) -> ParseResult<ast::ValueAssert> {
+        let sp = Self::get_span(&input);
+        match_nodes!(
+            input.clone().into_children();
+            [assert_w(_), value_term(l), order_op((op, rev)), value_term(r), interval_range(range)] => {
+                if !matches!(l, ast::ValueTerm::Port(_)) && !matches!(r, ast::ValueTerm::Port(_)) {
+                    return Err(input.error("assertion does not mention the value of a port. Use `value(port)' or remove the interval to assert a fact"));
+                }
+                let cons = if !rev {
+                    ast::OrderConstraint::new(l, r, op)
+                } else {
+                    ast::OrderConstraint::new(r, l, op)
+                };
+                Ok(ast::ValueAssert::new(Loc::new(cons, sp), range))
+            }
+        )
+    }
+
     fn param_let(input: Node) -> ParseResult<ast::ParamLet> {
         Ok(match_nodes!(
             input.into_children();
@@ -801,6 +827,7 @@ impl FilamentParser {
             [if_stmt(if_)] => vec![if_.into()],
             [param_let(l)] => vec![l.into()],
             [exists(e)] => vec![e.into()],
+            [value_assert(a)] => vec![a.into()],
             [fact(a)] => vec![a.into()],
         ))
     }
@@ -812,14 +839,44 @@ impl FilamentParser {
         ))
     }
 
+    fn lint_name(input: Node) -> ParseResult<ast::Id> {
+        match_nodes!(
+            input.clone().into_children();
+            [identifier(id)] => {
+                if Lint::is_known(id.as_ref()) {
+                    Ok(id.take())
+                } else {
+                    Err(input.error(format!("unknown lint `{id}'")))
+                }
+            }
+        )
+    }
+
+    fn attr_allow(input: Node) -> ParseResult<Vec<ast::Id>> {
+        Ok(match_nodes!(
+            input.into_children();
+            [lint_name(names)..] => names.collect(),
+        ))
+    }
+
+    fn attributes(input: Node) -> ParseResult<ast::Attributes> {
+        Ok(match_nodes!(
+            input.into_children();
+            [attr_allow(allows)..] => ast::Attributes {
+                allow: allows.flatten().collect(),
+            },
+        ))
+    }
+
     fn component(input: Node) -> ParseResult<ast::Component> {
         match_nodes!(
             input.into_children();
             [
+                attributes(attrs),
                 signature(sig),
                 command(body)..
             ] => {
-                Ok(ast::Component::new(sig, body.into_iter().flatten().collect()))
+                Ok(ast::Component::new(sig, body.into_iter().flatten().collect(), attrs))
             }
         )
     }
@@ -835,7 +892,7 @@ impl FilamentParser {
         Ok(match_nodes!(
             input.into_children();
             [external(sig)] => ExtOrComp::Ext(sig),
-            [component(comp)] => ExtOrComp::Comp(comp),
+            [component(comp)] => ExtOrComp::Comp(Box::new(comp)),
         ))
     }
 
@@ -854,12 +911,14 @@ impl FilamentParser {
                     imports: imps,
                     externs: vec![],
                     components: vec![],
-                    toplevel: "main".to_string(),
+                    toplevels: vec!["main".to_string()],
+                    bindings: vec![],
+                    precompiled: vec![],
                 };
                 for m in mixed {
                     match m {
                         ExtOrComp::Ext(sig) => namespace.externs.push(sig),
-                        ExtOrComp::Comp(comp) => namespace.components.push(comp),
+                        ExtOrComp::Comp(comp) => namespace.components.push(*comp),
                     }
                 }
                 namespace
@@ -1,7 +1,8 @@
 use super::{Binding, Bundle, Expr, Id, Loc, Range, Time};
+use serde::{Deserialize, Serialize};
 
 /// A port definition in a [super::Signature].
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PortDef {
     Port {
         name: Loc<Id>,
@@ -84,7 +85,7 @@ impl PortDef {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct InterfaceDef {
     /// Name of the port
     pub name: Loc<Id>,
@@ -3,8 +3,9 @@ use super::{
     TimeSub,
 };
 use fil_utils::GPosIdx;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// An event variable bound in the signature
 pub struct EventBind {
     pub event: Loc<Id>,
@@ -44,7 +45,7 @@ impl EventBind {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A parameter bound in the signature
 pub struct ParamBind {
     pub param: Loc<Id>,
@@ -77,7 +78,7 @@ impl From<Id> for ParamBind {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// A binding in the `with` section of a signature.
 pub enum SigBind {
     /// A let binding
@@ -107,7 +108,7 @@ impl SigBind {
 }
 
 /// The signature of a component definition
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct Signature {
     /// Name of the component
     pub name: Loc<Id>,
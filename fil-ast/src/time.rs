@@ -1,7 +1,8 @@
 use super::{Binding, Expr, Id};
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(Clone, Hash)]
+#[derive(Clone, Hash, Serialize, Deserialize)]
 /// Represents expression of the form `G+1+k`
 pub struct Time {
     /// The event for the time expression
@@ -75,7 +76,7 @@ impl std::ops::Sub for Time {
 }
 
 /// Represents the absolute difference between two time events
-#[derive(Clone, Hash)]
+#[derive(Clone, Hash, Serialize, Deserialize)]
 pub enum TimeSub {
     /// Concrete difference between two time expressions
     Unit(Expr),
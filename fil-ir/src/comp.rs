@@ -2,14 +2,15 @@ use super::{
     AddCtx, Cmp, CmpOp, Command, Ctx, Event, EventIdx, Expr, ExprIdx, Fact,
     IndexStore, Info, InfoIdx, InstIdx, Instance, InterfaceSrc, Interned,
     InvIdx, Invoke, MutCtx, Param, ParamIdx, Port, PortIdx, Prop, PropIdx,
-    Time, TimeSub,
+    Specialization, Time, TimeSub,
 };
 use crate::{utils::Idx, ParamOwner};
 use fil_ast as ast;
 use fil_derive::Ctx;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Ctx, Clone)]
+#[derive(Default, Ctx, Clone, Serialize, Deserialize)]
 /// A IR component. If `is_ext` is true then this is an external component.
 pub struct Component {
     // ================ Interned data ====================
@@ -59,11 +60,24 @@ pub struct Component {
     info: IndexStore<Info>,
     /// Is this an external component
     pub is_ext: bool,
+    /// Name of the component in the source program. `None` for components
+    /// generated by the compiler.
+    pub name: Option<ast::Id>,
     /// Externally facing interface information, used to preserve interface in compilation.
     /// Must be `Some` for toplevel components and externals.
     pub src_info: Option<InterfaceSrc>,
+    /// The components and parameters this component was monomorphized from.
+    /// Deduplication merges the specializations of identical components into
+    /// the one that is kept. Empty for components that have not been
+    /// monomorphized.
+    pub specializations: Vec<Specialization>,
     /// unannotated ports associated with this component
     pub unannotated_ports: Box<Vec<(ast::Id, u64)>>,
+    /// Lint codes or groups allowed within this component
+    pub allowed_lints: Box<Vec<ast::Id>>,
+    /// The component is defined by a precompiled library and its proof
+    /// obligations have already been discharged
+    pub precompiled: bool,
 
     // ============== Component structure ===============
     /// Commands in the component
@@ -570,7 +584,7 @@ impl AddCtx<Expr> for Component {
                 args.iter()
                     .map(|arg| arg.as_concrete(self))
                     .collect::<Option<Vec<_>>>()
-                    .map(|args| op.eval(args))
+                    .and_then(|args| op.checked_eval(args))
                     .map_or(val, Expr::Concrete),
             ),
         }
@@ -2,20 +2,24 @@ use super::{
     AddCtx, CompIdx, Component, Ctx, Foreign, Idx, IndexStore, MutCtx,
 };
 use fil_derive::Ctx;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Default, Ctx)]
+#[derive(Default, Ctx, Serialize, Deserialize)]
 pub struct Context {
     #[ctx(Component: Get, Add, Mut)]
     pub comps: IndexStore<Component>,
     // Contains external components grouped by file name.
-    pub externals: HashMap<String, Vec<CompIdx>>,
-    pub entrypoint: Option<CompIdx>,
+    pub externals: BTreeMap<String, Vec<CompIdx>>,
+    /// The top-level components of the program
+    pub entrypoints: Vec<CompIdx>,
+    /// Values of the signature parameters of the top-level components
+    pub entry_args: BTreeMap<CompIdx, Vec<u64>>,
 }
 
 impl Context {
     pub fn is_main(&self, idx: CompIdx) -> bool {
-        Some(idx) == self.entrypoint
+        self.entrypoints.contains(&idx)
     }
 
     /// Is this component external?
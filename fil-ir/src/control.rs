@@ -1,9 +1,11 @@
 use super::{
-    Access, CompIdx, Component, Ctx, Event, ExprIdx, Fact, Foreign, InfoIdx,
-    InstIdx, InvIdx, ParamIdx, PortIdx, PropIdx, Range, TimeIdx, TimeSub,
+    Access, CmpOp, CompIdx, Component, Ctx, Event, ExprIdx, Fact, Foreign,
+    InfoIdx, InstIdx, InvIdx, ParamIdx, PortIdx, PropIdx, Range, TimeIdx,
+    TimeSub,
 };
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// A flattened and minimized representation of the control flow graph.
 /// Bundle definitions and facts are removed during the process of compilation to the IR.
 pub enum Command {
@@ -23,6 +25,8 @@ pub enum Command {
     Fact(Fact),
     /// An `exists` binding
     Exists(Exists),
+    /// An assertion on the values of ports
+    ValueAssert(ValueAssert),
 }
 impl Command {
     pub fn is_loop(&self) -> bool {
@@ -74,8 +78,13 @@ impl From<Exists> for Command {
         Command::Exists(exists)
     }
 }
+impl From<ValueAssert> for Command {
+    fn from(assert: ValueAssert) -> Self {
+        Command::ValueAssert(assert)
+    }
+}
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// An instantiated component
 pub struct Instance {
     /// The component being instantiated
@@ -98,7 +107,7 @@ impl InstIdx {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// A connection between two ports
 pub struct Connect {
     pub src: Access,
@@ -106,7 +115,7 @@ pub struct Connect {
     pub info: InfoIdx,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// An invocation of a component
 /// Unlike in the AST, invocations are completely desuarged and do not have any
 /// ports.
@@ -148,7 +157,7 @@ impl InvIdx {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// A loop over a range of numbers
 pub struct Loop {
     pub index: ParamIdx,
@@ -157,7 +166,7 @@ pub struct Loop {
     pub body: Vec<Command>,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// A conditional statement
 pub struct If {
     pub cond: PropIdx,
@@ -165,7 +174,7 @@ pub struct If {
     pub alt: Vec<Command>,
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Binding for an event argument of an invocation
 pub struct EventBind {
     /// The delay of the event being provided for the binding
@@ -194,10 +203,51 @@ impl EventBind {
     }
 }
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Exists {
     /// The existentially quantified parameter
     pub param: ParamIdx,
     /// The binding for the parameter
     pub expr: ExprIdx,
 }
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A side of an assertion on the values of ports
+pub enum ValueTerm {
+    /// The value carried by a port
+    Port(Access),
+    /// A parameter expression
+    Expr(ExprIdx),
+}
+
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An assertion that the values of ports satisfy a comparison during every
+/// cycle of an interval. Unlike facts, these are not discharged by the type
+/// checker but by bounded model checking of the design.
+pub struct ValueAssert {
+    pub cons: CmpOp<ValueTerm>,
+    pub range: Range,
+    pub info: InfoIdx,
+}
+
+impl ValueAssert {
+    /// The ports read by the assertion
+    pub fn ports(&self) -> impl Iterator<Item = &Access> {
+        [&self.cons.lhs, &self.cons.rhs]
+            .into_iter()
+            .filter_map(|t| match t {
+                ValueTerm::Port(a) => Some(a),
+                ValueTerm::Expr(_) => None,
+            })
+    }
+
+    /// Mutable references to the ports read by the assertion
+    pub fn ports_mut(&mut self) -> impl Iterator<Item = &mut Access> {
+        [&mut self.cons.lhs, &mut self.cons.rhs]
+            .into_iter()
+            .filter_map(|t| match t {
+                ValueTerm::Port(a) => Some(a),
+                ValueTerm::Expr(_) => None,
+            })
+    }
+}
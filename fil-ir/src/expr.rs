@@ -1,9 +1,10 @@
 use super::{AddCtx, Component, Ctx, ExprIdx, ParamIdx};
 use crate::construct_binop;
 use fil_ast as ast;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Param(ParamIdx),
     Concrete(u64),
@@ -96,6 +97,13 @@ impl ExprIdx {
         })
     }
 
+    pub fn bit_rev(self, bits: Self, ctx: &mut impl AddCtx<Expr>) -> Self {
+        ctx.add(Expr::Fn {
+            op: ast::Fn::BitRev,
+            args: vec![self, bits],
+        })
+    }
+
     /// creates an [Expr::Bin] given two [ExprIdx]s and an [ast::Op].
     fn bin(self, rhs: Self, op: ast::Op) -> Expr {
         Expr::Bin { op, lhs: self, rhs }
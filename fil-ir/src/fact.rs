@@ -1,8 +1,9 @@
 use super::{idxs::PropIdx, AddCtx, Ctx, ExprIdx, InfoIdx, TimeIdx, TimeSub};
 use crate::{construct_binop, EventIdx, Expr, ParamIdx, Time};
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Comparison operators
 pub enum Cmp {
     Gt,
@@ -21,7 +22,7 @@ impl fmt::Display for Cmp {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Comparison between two expressions of type T
 pub struct CmpOp<T> {
     pub op: Cmp,
@@ -77,7 +78,7 @@ impl<T: Display> fmt::Display for CmpOp<T> {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A proposition
 pub enum Prop {
     True,
@@ -290,7 +291,7 @@ impl PropIdx {
     }
 }
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// A fact in the program.
 /// If `checked` is true, then this represents an assertion that needs to be
 /// checked. Otherwise, it is an assumption.
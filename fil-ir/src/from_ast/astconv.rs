@@ -8,9 +8,9 @@ use crate::{
     ParamIdx, PortIdx, PropIdx, TimeIdx,
 };
 use fil_ast as ast;
-use fil_utils::{Diagnostics, Error, GPosIdx};
+use fil_utils::{codes, Diagnostics, Error, GPosIdx};
 use itertools::Itertools;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::{iter, rc::Rc};
 
 pub type BuildRes<T> = Result<T, Diagnostics>;
@@ -217,7 +217,8 @@ impl<'prog> BuildCtx<'prog> {
             | ast::Command::If(_)
             | ast::Command::Fact(_)
             | ast::Command::Connect(_)
-            | ast::Command::Bundle(_) => Ok(()),
+            | ast::Command::Bundle(_)
+            | ast::Command::ValueAssert(_) => Ok(()),
         }
     }
 
@@ -575,6 +576,46 @@ impl<'prog> BuildCtx<'prog> {
         Ok(acc)
     }
 
+    /// Access to a port read by an assertion. Unlike connections, assertions
+    /// may read the outputs as well as the inputs of the signature and of
+    /// invocations.
+    fn value_access(&mut self, port: ast::Port) -> BuildRes<ir::Access> {
+        let out = match &port {
+            ast::Port::This(name) | ast::Port::Bundle { name, .. } => {
+                OwnedPort::Sig(ir::Direction::Out, name.clone())
+            }
+            ast::Port::InvPort { invoke, name }
+            | ast::Port::InvBundle {
+                invoke, port: name, ..
+            } => {
+                let inv = self.get_inv(invoke)?;
+                OwnedPort::Inv(inv, ir::Direction::Out, name.clone())
+            }
+        };
+        let dir = if self.find_port(&out).is_some() {
+            ir::Direction::Out
+        } else {
+            ir::Direction::In
+        };
+        self.get_access(port, dir)
+    }
+
+    fn value_term(
+        &mut self,
+        term: ast::ValueTerm,
+    ) -> BuildRes<(ir::ValueTerm, GPosIdx)> {
+        Ok(match term {
+            ast::ValueTerm::Port(p) => {
+                let pos = p.pos();
+                (ir::ValueTerm::Port(self.value_access(p.take())?), pos)
+            }
+            ast::ValueTerm::Expr(e) => {
+                let pos = e.pos();
+                (ir::ValueTerm::Expr(self.expr(e.take())?), pos)
+            }
+        })
+    }
+
     fn sig(&mut self, idx: ir::CompIdx, sig: &ast::Signature) -> BuildRes<Sig> {
         let mut conv_sig = Sig::new(idx, sig);
 
@@ -766,7 +807,7 @@ impl<'prog> BuildCtx<'prog> {
                 ports.len()
             );
             let info = self.diag().add_info(msg.clone(), instance.pos());
-            let err = Error::malformed(msg);
+            let err = Error::malformed(msg).with_code(codes::ARGUMENT_COUNT);
 
             return self.fail(err, [info]);
         }
@@ -911,7 +952,7 @@ impl<'prog> BuildCtx<'prog> {
                     ir::ParamOwner::Exists { .. }
                 ) {
                     let diag = self.diag();
-                    let param_typ = Error::malformed("parameter in exists binding is not existentially quantified").add_note(
+                    let param_typ = Error::malformed("parameter in exists binding is not existentially quantified").with_code(codes::NOT_EXISTENTIAL).add_note(
                         diag.add_info("parameter is not existentially quantified", param.pos()),
                     );
                     diag.add_error(param_typ);
@@ -936,6 +977,28 @@ impl<'prog> BuildCtx<'prog> {
                 };
                 fact.into_iter().collect()
             }
+            ast::Command::ValueAssert(ast::ValueAssert { cons, range }) => {
+                let cons_loc = cons.pos();
+                let range_loc = range.pos();
+                let ast::OrderConstraint { left, right, op } = cons.take();
+                let (lhs, lhs_loc) = self.value_term(left)?;
+                let (rhs, rhs_loc) = self.value_term(right)?;
+                let op = match op {
+                    ast::OrderOp::Gt => Cmp::Gt,
+                    ast::OrderOp::Gte => Cmp::Gte,
+                    ast::OrderOp::Eq => Cmp::Eq,
+                };
+                let range = self.range(range.take())?;
+                let info = self.comp().add(ir::Info::value_assert(
+                    cons_loc, lhs_loc, rhs_loc, range_loc,
+                ));
+                vec![ir::ValueAssert {
+                    cons: ir::CmpOp { op, lhs, rhs },
+                    range,
+                    info,
+                }
+                .into()]
+            }
             ast::Command::Connect(ast::Connect { src, dst }) => {
                 let info =
                     self.comp().add(ir::Info::connect(dst.pos(), src.pos()));
@@ -1030,18 +1093,51 @@ impl<'prog> BuildCtx<'prog> {
         }
         cmds
     }
+
+    /// Assume the values of the signature parameters of a top-level
+    /// component so that it is verified for the configuration it is compiled
+    /// with.
+    fn entry_assumptions(&mut self, args: &[u64]) -> Vec<ir::Command> {
+        let reason = self.comp().add(
+            ir::info::Reason::misc(
+                "parameter is bound on the command line",
+                GPosIdx::UNKNOWN,
+            )
+            .into(),
+        );
+        let params = self.comp().sig_params().collect_vec();
+        let mut cmds = Vec::with_capacity(params.len());
+        for (param, val) in params.into_iter().zip(args) {
+            let param = param.expr(self.comp());
+            let val = self.comp().num(*val);
+            let eq = param.equal(val, self.comp());
+            cmds.extend(self.comp().assume(eq, reason))
+        }
+        cmds
+    }
 }
 
-fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
+/// Transform the namespace into the IR and return the diagnostics without
+/// reporting them if the transformation fails.
+pub fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
+    // index main components after all externals
+    let ext_count = ns.externals().count();
     // creates an empty context with the main index.
     let mut ctx = ir::Context {
-        entrypoint: ns
-            .main_idx()
-            // index main component after all externals
-            .map(|idx| Idx::new(ns.externals().count() + idx)),
+        entrypoints: ns
+            .toplevel_idxs()
+            .into_iter()
+            .map(|idx| Idx::new(ext_count + idx))
+            .collect(),
+        entry_args: super::toplevel::entry_args(&ns)?
+            .into_iter()
+            .map(|(idx, args)| (Idx::new(ext_count + idx), args))
+            .collect(),
         ..Default::default()
     };
 
+    let precompiled: HashSet<_> = ns.precompiled.iter().copied().collect();
+
     // Walk over signatures and compile signatures to build a SigMap
     // Contains a tuple containing three necessary bits of information:
     // 1. The (optional) name of the component (if it is an external)
@@ -1059,7 +1155,7 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
         .chain(
             ns.components
                 .into_iter()
-                .map(|comp| (None, comp.sig, Some(comp.body))),
+                .map(|comp| (None, comp.sig, Some((comp.body, comp.attrs)))),
         )
         .enumerate();
 
@@ -1070,7 +1166,7 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
     struct Builder<'a> {
         idx: ir::CompIdx,
         builder: BuildCtx<'a>,
-        body: Option<Vec<ast::Command>>,
+        body: Option<(Vec<ast::Command>, ast::Attributes)>,
     }
 
     // uses the information above to compile the signatures of components and create their builders.
@@ -1079,9 +1175,12 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
             let idx = ir::CompIdx::new(idx);
             let mut builder =
                 BuildCtx::new(ir::Component::new(body.is_none()), &sig_map);
+            builder.comp().name = Some(sig.name.copy());
+            builder.comp().precompiled =
+                body.is_some() && precompiled.contains(sig.name.inner());
 
             // enable source information saving if this is main or an external.
-            if body.is_none() || Some(idx) == ctx.entrypoint {
+            if body.is_none() || ctx.is_main(idx) {
                 builder.comp().src_info =
                     Some(InterfaceSrc::new(sig.name.copy()))
             }
@@ -1113,10 +1212,16 @@ fn try_transform(ns: ast::Namespace) -> BuildRes<ir::Context> {
     } in builders
     {
         let body_cmds = match body {
-            Some(cmds) => builder.commands(cmds)?,
+            Some((cmds, attrs)) => {
+                *builder.comp().allowed_lints = attrs.allow;
+                builder.commands(cmds)?
+            }
             None => vec![],
         };
         let mut cmds = builder.port_assumptions();
+        if let Some(args) = ctx.entry_args.get(&idx) {
+            cmds.extend(builder.entry_assumptions(args));
+        }
         cmds.extend(body_cmds);
         builder.comp().cmds.extend(cmds);
         log::debug!("Adding component: {}", idx);
@@ -1,6 +1,7 @@
 mod build_ctx;
 mod scope_map;
 mod sig_map;
+mod toplevel;
 
 use build_ctx::BuildCtx;
 use scope_map::ScopeMap;
@@ -1,7 +1,7 @@
 use super::BuildRes;
 use crate as ir;
 use fil_ast as ast;
-use fil_utils::{self as utils, Error, Id};
+use fil_utils::{self as utils, codes, Error, Id};
 use itertools::Itertools;
 use std::collections::HashMap;
 
@@ -81,6 +81,7 @@ impl Sig {
                 arg_len
             );
             let err = Error::malformed(msg.clone())
+                .with_code(codes::ARGUMENT_COUNT)
                 .add_note(diag.add_info(msg, comp.pos()));
             diag.add_error(err);
             return Err(std::mem::take(diag));
@@ -99,6 +100,7 @@ impl Sig {
                 comp.inner(),
             );
             let err = Error::malformed(msg.clone())
+                .with_code(codes::ARGUMENT_COUNT)
                 .add_note(diag.add_info(msg, comp.pos()));
             diag.add_error(err);
             return Err(std::mem::take(diag));
@@ -148,7 +150,8 @@ impl Sig {
                 self.raw_events.len(),
                 args.len()
             );
-            let err = Error::malformed(msg.clone());
+            let err =
+                Error::malformed(msg.clone()).with_code(codes::ARGUMENT_COUNT);
             let err = err.add_note(diag.add_info(msg, inst.pos()));
             diag.add_error(err);
             return Err(std::mem::take(diag));
@@ -168,7 +171,8 @@ impl Sig {
                 min_args,
                 args.len()
             );
-            let err = Error::malformed(msg.clone());
+            let err =
+                Error::malformed(msg.clone()).with_code(codes::ARGUMENT_COUNT);
             let err = err.add_note(diag.add_info(msg, inst.pos()));
             diag.add_error(err);
             return Err(std::mem::take(diag));
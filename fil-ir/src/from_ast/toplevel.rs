@@ -0,0 +1,217 @@
+//! Compute the values of the signature parameters of the top-level components.
+//!
+//! Parameters of a top-level component are bound by the values given on the
+//! command line or by their default values. The values are checked against
+//! the `where` constraints of the signature so that invalid configurations
+//! are reported before the program is monomorphized.
+use super::BuildRes;
+use fil_ast as ast;
+use fil_utils::{codes, Diagnostics, Error, Id};
+use std::collections::HashMap;
+
+/// Reasons an expression cannot be evaluated
+enum EvalErr {
+    /// The expression mentions a name without a value
+    Unbound,
+    /// The value of the expression is not a natural number
+    Invalid(String),
+}
+
+/// Evaluate the expression using the values of the parameters
+fn eval(e: &ast::Expr, env: &HashMap<Id, u64>) -> Result<u64, EvalErr> {
+    match e {
+        ast::Expr::Concrete(n) => Ok(*n),
+        ast::Expr::Abstract(id) => {
+            env.get(id.inner()).copied().ok_or(EvalErr::Unbound)
+        }
+        ast::Expr::ParamAccess { .. } => Err(EvalErr::Unbound),
+        ast::Expr::App { func, args } => {
+            let args = args
+                .iter()
+                .map(|a| eval(a, env))
+                .collect::<Result<Vec<_>, _>>()?;
+            let app = format!(
+                "{func}({})",
+                args.iter()
+                    .map(u64::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            func.checked_eval(args).ok_or_else(|| {
+                EvalErr::Invalid(format!("`{app}' is out of range"))
+            })
+        }
+        ast::Expr::Op { op, left, right } => {
+            let (l, r) = (eval(left, env)?, eval(right, env)?);
+            let v = match op {
+                ast::Op::Add => l.checked_add(r),
+                ast::Op::Sub => l.checked_sub(r),
+                ast::Op::Mul => l.checked_mul(r),
+                ast::Op::Div => l.checked_div(r),
+                ast::Op::Mod => l.checked_rem(r),
+            };
+            v.ok_or_else(|| {
+                EvalErr::Invalid(format!(
+                    "`{l} {op} {r}' is not a natural number"
+                ))
+            })
+        }
+    }
+}
+
+/// Whether the expression mentions the parameter
+fn mentions(e: &ast::Expr, param: &Id) -> bool {
+    match e {
+        ast::Expr::Concrete(_) | ast::Expr::ParamAccess { .. } => false,
+        ast::Expr::Abstract(id) => id.inner() == param,
+        ast::Expr::App { args, .. } => args.iter().any(|a| mentions(a, param)),
+        ast::Expr::Op { left, right, .. } => {
+            mentions(left, param) || mentions(right, param)
+        }
+    }
+}
+
+/// Values of the parameters of the top-level components indexed by their
+/// position in the namespace. Parameters without a value on the command line
+/// use their default value. Components with parameters that have no value
+/// are omitted.
+pub fn entry_args(ns: &ast::Namespace) -> BuildRes<HashMap<usize, Vec<u64>>> {
+    let mut diag = Diagnostics::default();
+    let bindings: HashMap<Id, u64> = ns
+        .bindings
+        .iter()
+        .map(|(name, v)| (Id::from(name.clone()), *v))
+        .collect();
+
+    let mut failed = false;
+    let mut args = HashMap::new();
+    let mut used = vec![];
+    for idx in ns.toplevel_idxs() {
+        let sig = &ns.components[idx].sig;
+        let name = sig.name.inner();
+        let mut env = HashMap::new();
+        let mut vals = Vec::with_capacity(sig.params.len());
+        let mut missing = false;
+        for pb in &sig.params {
+            let param = pb.name();
+            let val = if let Some(v) = bindings.get(&param) {
+                used.push(param);
+                *v
+            } else if let Some(default) = &pb.default {
+                match eval(default, &env) {
+                    Ok(v) => v,
+                    Err(EvalErr::Unbound) => {
+                        missing = true;
+                        continue;
+                    }
+                    Err(EvalErr::Invalid(reason)) => {
+                        let mut err = Error::malformed(format!(
+                            "default value of parameter `{param}' of `{name}' is invalid: {reason}"
+                        ))
+                        .with_code(codes::PARAM_CONSTRAINT)
+                        .add_note(diag.add_info(
+                            format!("provide a value with `-P {param}=<value>'"),
+                            pb.pos(),
+                        ));
+                        // Point at the command line flags the default uses
+                        let flags = sig
+                            .params
+                            .iter()
+                            .map(|pb| pb.name())
+                            .filter(|p| {
+                                mentions(default, p) && bindings.contains_key(p)
+                            })
+                            .map(|p| format!("-P {p}={}", bindings[&p]))
+                            .collect::<Vec<_>>();
+                        if !flags.is_empty() {
+                            err = err.add_note(diag.add_message(format!(
+                                "The default value uses `{}'",
+                                flags.join("', `")
+                            )));
+                        }
+                        diag.add_error(err);
+                        failed = true;
+                        missing = true;
+                        continue;
+                    }
+                }
+            } else {
+                missing = true;
+                continue;
+            };
+            env.insert(param, val);
+            vals.push(val);
+        }
+        // Components with parameters without values are only verified and
+        // cannot be monomorphized
+        if missing {
+            continue;
+        }
+
+        // Constraints that mention bindings other than the signature
+        // parameters are checked after monomorphization.
+        for cons in &sig.param_constraints {
+            let ast::OrderConstraint { left, right, op } = cons.inner();
+            let (l, r) = match (eval(left, &env), eval(right, &env)) {
+                (Ok(l), Ok(r)) => (l, r),
+                (Err(EvalErr::Invalid(reason)), _)
+                | (_, Err(EvalErr::Invalid(reason))) => {
+                    let err = Error::malformed(format!(
+                        "constraint of `{name}' cannot be evaluated: {reason}"
+                    ))
+                    .with_code(codes::PARAM_CONSTRAINT)
+                    .add_note(diag.add_info("invalid constraint", cons.pos()));
+                    diag.add_error(err);
+                    failed = true;
+                    continue;
+                }
+                _ => continue,
+            };
+            let holds = match op {
+                ast::OrderOp::Gt => l > r,
+                ast::OrderOp::Gte => l >= r,
+                ast::OrderOp::Eq => l == r,
+            };
+            if !holds {
+                let values = sig
+                    .params
+                    .iter()
+                    .zip(&vals)
+                    .map(|(pb, v)| format!("{}={v}", pb.name()))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let err = Error::malformed(format!(
+                    "parameters of top-level component `{name}' violate its constraints"
+                ))
+                .with_code(codes::PARAM_CONSTRAINT)
+                .add_note(diag.add_info(
+                    format!("`{l} {op} {r}' does not hold"),
+                    cons.pos(),
+                ))
+                .add_note(diag.add_message(format!(
+                    "Parameters are bound to {values}"
+                )));
+                diag.add_error(err);
+                failed = true;
+            }
+        }
+        args.insert(idx, vals);
+    }
+
+    for (name, _) in &ns.bindings {
+        if !used.contains(&Id::from(name.clone())) {
+            let err = Error::malformed(format!(
+                "`{name}' is not a parameter of a top-level component"
+            ))
+            .with_code(codes::UNDEFINED);
+            diag.add_error(err);
+            failed = true;
+        }
+    }
+
+    if failed {
+        Err(diag)
+    } else {
+        Ok(args)
+    }
+}
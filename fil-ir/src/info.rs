@@ -3,18 +3,19 @@ use super::{
 };
 use codespan_reporting::diagnostic::Diagnostic;
 use fil_ast as ast;
-use fil_utils::GPosIdx;
+use fil_utils::{codes, GPosIdx};
+use serde::{Deserialize, Serialize};
 use struct_variant::struct_variant;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// An absence of information is still information
 pub struct Empty;
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// Assertion information
 pub struct Assert(pub Reason);
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Param]
 pub struct Param {
     /// Surface-level name of the parameter
@@ -22,7 +23,7 @@ pub struct Param {
     pub bind_loc: GPosIdx,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Event]
 pub struct Event {
     /// Surface-level name of the event
@@ -34,7 +35,7 @@ pub struct Event {
     pub interface_bind_loc: Option<GPosIdx>,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::EventBind]
 pub struct EventBind {
     /// Location for the delay of the event
@@ -43,7 +44,7 @@ pub struct EventBind {
     pub bind_loc: GPosIdx,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Instance]
 pub struct Instance {
     pub name: ast::Id,
@@ -53,7 +54,7 @@ pub struct Instance {
     pub event_lives: Vec<GPosIdx>,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Invoke]
 pub struct Invoke {
     /// Name of the invocation
@@ -66,14 +67,25 @@ pub struct Invoke {
     pub event_bind_locs: Vec<GPosIdx>,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Connect]
 pub struct Connect {
     pub dst_loc: GPosIdx,
     pub src_loc: GPosIdx,
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+/// For [super::ValueAssert]
+pub struct ValueAssert {
+    /// Location of the comparison
+    pub cons_loc: GPosIdx,
+    pub lhs_loc: GPosIdx,
+    pub rhs_loc: GPosIdx,
+    /// Location of the interval during which the comparison is checked
+    pub range_loc: GPosIdx,
+}
+
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
 /// For [super::Port]
 pub struct Port {
     /// Surface-level name
@@ -86,6 +98,7 @@ pub struct Port {
 
 /// Information associated with the IR.
 #[struct_variant]
+#[derive(Serialize, Deserialize)]
 pub enum Info {
     Empty,
     Assert,
@@ -96,6 +109,7 @@ pub enum Info {
     Invoke,
     Connect,
     Port,
+    ValueAssert,
 }
 
 impl Default for Info {
@@ -207,6 +221,21 @@ impl Info {
         .into()
     }
 
+    pub fn value_assert(
+        cons_loc: GPosIdx,
+        lhs_loc: GPosIdx,
+        rhs_loc: GPosIdx,
+        range_loc: GPosIdx,
+    ) -> Self {
+        ValueAssert {
+            cons_loc,
+            lhs_loc,
+            rhs_loc,
+            range_loc,
+        }
+        .into()
+    }
+
     /// Gets the name associated with this info (if it exists). Useful for compilation purposes.
     pub fn get_name(&self) -> Option<String> {
         match self {
@@ -218,7 +247,8 @@ impl Info {
             Info::Connect(_)
             | Info::EventBind(_)
             | Info::Empty(_)
-            | Info::Assert(_) => None,
+            | Info::Assert(_)
+            | Info::ValueAssert(_) => None,
         }
     }
 }
@@ -241,6 +271,7 @@ impl Clone for Info {
             Self::Invoke(x) => Self::Invoke(x.clone()),
             Self::Param(x) => Self::Param(x.clone()),
             Self::Port(x) => Self::Port(x.clone()),
+            Self::ValueAssert(x) => Self::ValueAssert(x.clone()),
         }
     }
 }
@@ -296,8 +327,9 @@ info_cast!(Instance, as_instance);
 info_cast!(Invoke, as_invoke);
 info_cast!(Connect, as_connect);
 info_cast!(Port, as_port);
+info_cast!(ValueAssert, as_value_assert);
 
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
 /// Why was an assertion created?
 pub enum Reason {
     /// Assertion representing constraint on a parameter
@@ -419,6 +451,44 @@ impl Reason {
         }
     }
 
+    /// The stable diagnostic code for this reason
+    pub fn code(&self) -> &'static str {
+        match self {
+            Reason::ParamConstraint { .. } => codes::PARAM_CONSTRAINT,
+            Reason::EventConstraint { .. } => codes::EVENT_CONSTRAINT,
+            Reason::ExistsConstraint { .. } => codes::EXISTS_CONSTRAINT,
+            Reason::BundleLenMatch { .. } => codes::BUNDLE_LEN_MATCH,
+            Reason::BundleWidthMatch { .. } => codes::BUNDLE_WIDTH_MATCH,
+            Reason::InBoundsAccess { .. } => codes::IN_BOUNDS_ACCESS,
+            Reason::Liveness { .. } => codes::LIVENESS,
+            Reason::BundleDelay { .. } => codes::BUNDLE_DELAY,
+            Reason::WellFormedInterval { .. } => codes::WELL_FORMED_INTERVAL,
+            Reason::EventLive { .. } => codes::EVENT_LIVE,
+            Reason::EventLiveDelay { .. } => codes::EVENT_LIVE_DELAY,
+            Reason::EventTrig { .. } => codes::EVENT_TRIG,
+            Reason::Misc { .. } => codes::MISC_CONSTRAINT,
+        }
+    }
+
+    /// A short name for the kind of this reason
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Reason::ParamConstraint { .. } => "param-constraint",
+            Reason::EventConstraint { .. } => "event-constraint",
+            Reason::ExistsConstraint { .. } => "exists-constraint",
+            Reason::BundleLenMatch { .. } => "bundle-len-match",
+            Reason::BundleWidthMatch { .. } => "bundle-width-match",
+            Reason::InBoundsAccess { .. } => "in-bounds-access",
+            Reason::Liveness { .. } => "liveness",
+            Reason::BundleDelay { .. } => "bundle-delay",
+            Reason::WellFormedInterval { .. } => "well-formed-interval",
+            Reason::EventLive { .. } => "event-live",
+            Reason::EventLiveDelay { .. } => "event-live-delay",
+            Reason::EventTrig { .. } => "event-trig",
+            Reason::Misc { .. } => "misc",
+        }
+    }
+
     pub fn bundle_delay(
         event_delay_loc: GPosIdx,
         bundle_range_loc: GPosIdx,
@@ -578,7 +648,7 @@ impl From<Reason> for Info {
 impl Reason {
     /// Convert this reason into a diagnostic message
     pub fn diag(&self, ctx: &Component) -> Diagnostic<usize> {
-        match self {
+        let diag = match self {
             Reason::Misc { reason, def_loc } => {
                 let err = Diagnostic::error().with_message(reason);
                 if let Some(loc) = def_loc.into_option() {
@@ -815,6 +885,7 @@ impl Reason {
                     .with_message("event's delay must be greater than the instance's borrow length")
                     .with_labels(vec![live, ev])
             }
-        }
+        };
+        diag.with_code(self.code())
     }
 }
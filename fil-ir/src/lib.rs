@@ -8,7 +8,9 @@ mod from_ast;
 mod idxs;
 pub mod info;
 mod macros;
+mod parser;
 mod printer;
+mod serialize;
 mod source_info;
 mod structure;
 mod time;
@@ -19,18 +21,20 @@ pub use comp::Component;
 pub use context::Context;
 pub use control::{
     Command, Connect, EventBind, Exists, If, Instance, Invoke, Loop,
+    ValueAssert, ValueTerm,
 };
 pub use ctx::{AddCtx, Ctx, MutCtx};
 pub use expr::Expr;
 pub use fact::{Cmp, CmpOp, Fact, Prop};
-pub use from_ast::astconv::transform;
+pub use from_ast::astconv::{transform, try_transform};
 pub use idxs::{
     CompIdx, EventIdx, ExprIdx, InfoIdx, InstIdx, InvIdx, ParamIdx, PortIdx,
     PropIdx, TimeIdx,
 };
 pub use info::Info;
+pub use parser::IrParser;
 pub use printer::{DisplayCtx, Printer};
-pub use source_info::InterfaceSrc;
+pub use source_info::{InterfaceSrc, Specialization};
 pub use structure::{
     Access, Direction, Event, Liveness, Param, ParamOwner, Port, PortOwner,
     Range,
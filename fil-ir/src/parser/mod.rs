@@ -0,0 +1,1088 @@
+#![allow(clippy::result_large_err)]
+
+//! Parser for the textual IR printed by [Printer](crate::Printer), for
+//! example, with `--dump-after`.
+//!
+//! The printed IR hoists assumptions above the definitions they refer to
+//! and may define the same name several times, for example, after loops
+//! have been unrolled. The parser therefore works in two steps: [Declare]
+//! creates every entity defined by the program and [Define] fills in their
+//! types and the control of each component. Names are resolved to the
+//! innermost enclosing definition and otherwise to the last definition of
+//! the name in the component.
+use crate::{self as ir, info::Reason, AddCtx, Ctx, MutCtx};
+use fil_ast as ast;
+use fil_utils::{FileIdx, GPosIdx, GlobalPositionTable};
+use pest::error::{Error, ErrorVariant};
+use pest::Parser;
+use std::collections::HashMap;
+use std::{fs, path::Path};
+
+#[derive(pest_derive::Parser)]
+#[grammar = "parser/syntax.pest"]
+struct IrGrammar;
+
+type Pair<'i> = pest::iterators::Pair<'i, Rule>;
+type ParseResult<T> = Result<T, Error<Rule>>;
+
+fn error(pair: &Pair, msg: impl ToString) -> Error<Rule> {
+    Error::new_from_span(
+        ErrorVariant::CustomError {
+            message: msg.to_string(),
+        },
+        pair.as_span(),
+    )
+}
+
+fn pos(file: FileIdx, pair: &Pair) -> GPosIdx {
+    let sp = pair.as_span();
+    GPosIdx(GlobalPositionTable::as_mut().add_pos(file, sp.start(), sp.end()))
+}
+
+/// Source name of an entity. Entities without one are printed using their
+/// index, which starts with `%`.
+fn src_name(pair: &Pair) -> Option<ast::Id> {
+    let name = pair.as_str();
+    (!name.starts_with('%')).then(|| ast::Id::from(name))
+}
+
+/// Names of the entities visible at some point of a component
+#[derive(Default)]
+struct Scope {
+    params: HashMap<String, ir::ParamIdx>,
+    events: HashMap<String, ir::EventIdx>,
+    ports: HashMap<String, ir::PortIdx>,
+    insts: HashMap<String, ir::InstIdx>,
+    invs: HashMap<String, ir::InvIdx>,
+}
+
+/// Names defined by a component
+#[derive(Default)]
+struct Names {
+    /// The last definition of each name in the component
+    last: Scope,
+    /// Parameters defined by instances
+    inst_params: HashMap<(ir::InstIdx, String), ir::ParamIdx>,
+    /// Ports defined by invocations
+    inv_ports: HashMap<(ir::InvIdx, String), ir::PortIdx>,
+}
+
+/// Signature of a component used to resolve the foreign keys of the
+/// components that instantiate it.
+#[derive(Default)]
+struct Sig {
+    params: HashMap<String, ir::ParamIdx>,
+    ports: HashMap<String, ir::PortIdx>,
+    /// Events in the order they are bound by invocations
+    events: Vec<ir::EventIdx>,
+}
+
+/// Entities created by [Declare] indexed by the position of their binding
+#[derive(Default)]
+struct Decls {
+    params: HashMap<usize, ir::ParamIdx>,
+    events: HashMap<usize, ir::EventIdx>,
+    ports: HashMap<usize, ir::PortIdx>,
+    insts: HashMap<usize, ir::InstIdx>,
+    invs: HashMap<usize, ir::InvIdx>,
+}
+
+pub struct IrParser;
+
+impl IrParser {
+    /// Parse a file containing the IR of a program
+    pub fn parse_file(path: &Path) -> Result<ir::Context, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read `{}': {e}", path.display()))?;
+        let name = path.to_string_lossy().to_string();
+        let file =
+            GlobalPositionTable::as_mut().add_file(name.clone(), content);
+        let (_, content) = GlobalPositionTable::as_ref().get_file_data(file);
+        Self::parse(content, file)
+            .map_err(|e| format!("Failed to parse {}", e.with_path(&name)))
+    }
+
+    fn parse(content: &str, file: FileIdx) -> ParseResult<ir::Context> {
+        let mut pairs = IrGrammar::parse(Rule::file, content)?;
+        let comps = pairs
+            .next()
+            .unwrap()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::component)
+            .collect::<Vec<_>>();
+
+        // Create the components so that instances can refer to components
+        // defined later in the file.
+        let mut ctx = ir::Context::default();
+        let mut names = HashMap::new();
+        let mut idxs = Vec::with_capacity(comps.len());
+        for pair in &comps {
+            let mut inner = pair.clone().into_inner();
+            let is_ext = inner.peek().unwrap().as_rule() == Rule::ext;
+            if is_ext {
+                inner.next();
+            }
+            let name = inner.next().unwrap();
+            let idx = ctx.comp(is_ext);
+            let key = name.as_str().trim_start_matches('%').to_string();
+            if names.insert(key, idx).is_some() {
+                return Err(error(&name, "component is defined twice"));
+            }
+            if let Some(n) = src_name(&name) {
+                let comp = ctx.get_mut(idx);
+                comp.name = Some(n);
+                comp.src_info = Some(ir::InterfaceSrc::new(n));
+            }
+            idxs.push(idx);
+        }
+
+        let mut decls = Decls::default();
+        let mut comp_names = Vec::with_capacity(comps.len());
+        let mut sigs = HashMap::new();
+        for (pair, &idx) in comps.iter().zip(&idxs) {
+            let (names, sig) = Declare {
+                comp: ctx.get_mut(idx),
+                file,
+                comps: &names,
+                decls: &mut decls,
+                names: Names::default(),
+                sig: Sig::default(),
+            }
+            .component(pair.clone())?;
+            comp_names.push(names);
+            sigs.insert(idx, sig);
+        }
+
+        for ((pair, &idx), names) in
+            comps.into_iter().zip(&idxs).zip(&comp_names)
+        {
+            Define {
+                comp: ctx.get_mut(idx),
+                file,
+                decls: &decls,
+                sigs: &sigs,
+                names,
+                scopes: vec![],
+            }
+            .component(pair)?;
+        }
+
+        // Named components that are not instantiated are the entrypoints
+        let instantiated = ctx
+            .iter()
+            .flat_map(|(_, c)| c.instances().iter().map(|(_, i)| i.comp))
+            .collect::<Vec<_>>();
+        ctx.entrypoints = idxs
+            .into_iter()
+            .filter(|idx| {
+                let comp = ctx.get(*idx);
+                !comp.is_ext
+                    && comp.src_info.is_some()
+                    && !instantiated.contains(idx)
+            })
+            .collect();
+        Ok(ctx)
+    }
+}
+
+/// Creates the entities defined by a component. The entities are defined by
+/// [Define] once every component has been declared.
+struct Declare<'a> {
+    comp: &'a mut ir::Component,
+    file: FileIdx,
+    comps: &'a HashMap<String, ir::CompIdx>,
+    decls: &'a mut Decls,
+    names: Names,
+    sig: Sig,
+}
+
+impl Declare<'_> {
+    fn component(mut self, pair: Pair) -> ParseResult<(Names, Sig)> {
+        // Interfaces are declared after the events they belong to
+        let interfaces = pair
+            .clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::interface_decl)
+            .map(|p| {
+                let mut inner = p.into_inner();
+                let name = inner.next().unwrap();
+                let ev = inner.next().unwrap().into_inner().next().unwrap();
+                let name =
+                    (ast::Id::from(name.as_str()), pos(self.file, &name));
+                (ev.as_str().to_string(), name)
+            })
+            .collect::<HashMap<_, _>>();
+
+        // The direction of signature ports is flipped because inputs are
+        // written to by the environment and read by the component.
+        let mut dir = ir::Direction::Out;
+        let mut param_args = vec![];
+        let mut event_args = vec![];
+        for node in pair.into_inner() {
+            match node.as_rule() {
+                Rule::sig_params => {
+                    for name in node.into_inner() {
+                        let p = self.param(&name, ir::ParamOwner::Sig);
+                        self.sig_param(&name, p);
+                        param_args.push(p);
+                    }
+                }
+                Rule::sig_events => {
+                    for def in node.into_inner() {
+                        event_args.push(self.event(def, &interfaces));
+                    }
+                }
+                Rule::ports => {
+                    for def in node.into_inner() {
+                        let name = def.clone().into_inner().next().unwrap();
+                        let owner = ir::PortOwner::Sig { dir: dir.clone() };
+                        let p = self.port(def, owner);
+                        self.names.last.ports.insert(name.as_str().into(), p);
+                        self.sig.ports.insert(name.as_str().into(), p);
+                        if let (Some(src), Some(n)) =
+                            (&mut self.comp.src_info, src_name(&name))
+                        {
+                            src.ports.push(p, n);
+                        }
+                    }
+                    dir = ir::Direction::In;
+                }
+                Rule::param_decl => {
+                    let name = node.into_inner().next().unwrap();
+                    let p = self.param(&name, ir::ParamOwner::Sig);
+                    self.sig_param(&name, p);
+                }
+                Rule::event_decl => {
+                    let def = node.into_inner().next().unwrap();
+                    self.event(def, &interfaces);
+                }
+                Rule::exists_decl => {
+                    let mut inner = node.into_inner().peekable();
+                    let opaque = inner
+                        .next_if(|p| p.as_rule() == Rule::opaque)
+                        .is_some();
+                    let name = inner.next().unwrap();
+                    let p =
+                        self.param(&name, ir::ParamOwner::Exists { opaque });
+                    self.names.last.params.insert(name.as_str().into(), p);
+                    self.sig.params.insert(name.as_str().into(), p);
+                }
+                Rule::unannotated_decl => {
+                    let mut inner = node.into_inner();
+                    let name = inner.next().unwrap();
+                    let width = inner.next().unwrap();
+                    let width = width
+                        .as_str()
+                        .parse()
+                        .map_err(|_| error(&width, "number is too large"))?;
+                    self.comp
+                        .unannotated_ports
+                        .push((name.as_str().into(), width));
+                }
+                Rule::block => self.commands(node)?,
+                _ => {}
+            }
+        }
+        self.comp.param_args = param_args.into_boxed_slice();
+        self.comp.event_args = event_args.into_boxed_slice();
+        Ok((self.names, self.sig))
+    }
+
+    fn param(&mut self, name: &Pair, owner: ir::ParamOwner) -> ir::ParamIdx {
+        let info = src_name(name).map_or_else(ir::Info::empty, |n| {
+            ir::Info::param(n, pos(self.file, name))
+        });
+        let info = self.comp.add(info);
+        let idx = self.comp.add(ir::Param::new(owner, info));
+        self.decls.params.insert(name.as_span().start(), idx);
+        idx
+    }
+
+    fn sig_param(&mut self, name: &Pair, p: ir::ParamIdx) {
+        self.names.last.params.insert(name.as_str().into(), p);
+        self.sig.params.insert(name.as_str().into(), p);
+        if let (Some(src), Some(n)) = (&mut self.comp.src_info, src_name(name))
+        {
+            src.params.push(p, n);
+        }
+    }
+
+    fn event(
+        &mut self,
+        def: Pair,
+        interfaces: &HashMap<String, (ast::Id, GPosIdx)>,
+    ) -> ir::EventIdx {
+        let mut inner = def.into_inner();
+        let ev = inner.next().unwrap();
+        let delay = inner.next().unwrap();
+        let name = ev.clone().into_inner().next().unwrap();
+        let interface = interfaces.get(name.as_str()).copied();
+        let info = src_name(&name).map_or_else(ir::Info::empty, |n| {
+            ir::Info::event(
+                n,
+                pos(self.file, &name),
+                pos(self.file, &delay),
+                interface,
+            )
+        });
+        let info = self.comp.add(info);
+        let zero = self.comp.num(0);
+        let idx = self.comp.add(ir::Event {
+            delay: ir::TimeSub::Unit(zero),
+            info,
+            has_interface: false,
+        });
+        self.decls.events.insert(ev.as_span().start(), idx);
+        self.names.last.events.insert(name.as_str().into(), idx);
+        self.sig.events.push(idx);
+        if let (Some(src), Some(n)) = (&mut self.comp.src_info, src_name(&name))
+        {
+            src.events.push(idx, n);
+            if let Some((port, _)) = interface {
+                src.interface_ports.push(idx, port);
+            }
+        }
+        idx
+    }
+
+    /// Declare a port defined by a signature, bundle, or invocation. Its
+    /// type is filled in by [Define::port].
+    fn port(&mut self, def: Pair, owner: ir::PortOwner) -> ir::PortIdx {
+        let inner = def.into_inner().collect::<Vec<_>>();
+        let name = &inner[0];
+        let live = inner.iter().find(|p| p.as_rule() == Rule::liveness);
+        let live = live.unwrap();
+        let width = inner.last().unwrap();
+        let info = src_name(name).map_or_else(ir::Info::empty, |n| {
+            ir::Info::port(
+                n,
+                pos(self.file, name),
+                pos(self.file, width),
+                pos(self.file, live),
+            )
+        });
+        let info = self.comp.add(info);
+        let idx = self.comp.add(ir::Port {
+            owner,
+            width: ir::ExprIdx::UNKNOWN,
+            live: ir::Liveness {
+                idxs: vec![],
+                lens: vec![],
+                range: ir::Range {
+                    start: ir::TimeIdx::UNKNOWN,
+                    end: ir::TimeIdx::UNKNOWN,
+                },
+            },
+            info,
+        });
+        self.decls.ports.insert(name.as_span().start(), idx);
+
+        let idxs = live
+            .clone()
+            .into_inner()
+            .filter(|p| p.as_rule() == Rule::bundle_idx)
+            .map(|b| {
+                let name = b.into_inner().next().unwrap();
+                let p = self.param(&name, ir::ParamOwner::bundle(idx));
+                self.names.last.params.insert(name.as_str().into(), p);
+                p
+            })
+            .collect();
+        self.comp.get_mut(idx).live.idxs = idxs;
+        idx
+    }
+
+    fn commands(&mut self, block: Pair) -> ParseResult<()> {
+        for cmd in block.into_inner() {
+            self.command(cmd)?;
+        }
+        Ok(())
+    }
+
+    fn command(&mut self, cmd: Pair) -> ParseResult<()> {
+        match cmd.as_rule() {
+            Rule::for_loop => {
+                let mut inner = cmd.into_inner();
+                let name = inner.next().unwrap();
+                let p = self.param(&name, ir::ParamOwner::Loop);
+                self.names.last.params.insert(name.as_str().into(), p);
+                self.commands(inner.last().unwrap())?;
+            }
+            Rule::if_cmd => {
+                for block in
+                    cmd.into_inner().filter(|p| p.as_rule() == Rule::block)
+                {
+                    self.commands(block)?;
+                }
+            }
+            Rule::bundle_def => {
+                let name = cmd.clone().into_inner().next().unwrap();
+                let p = self.port(cmd, ir::PortOwner::Local);
+                self.names.last.ports.insert(name.as_str().into(), p);
+            }
+            Rule::instance => self.instance(cmd)?,
+            Rule::invoke => self.invoke(cmd),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn instance(&mut self, cmd: Pair) -> ParseResult<()> {
+        let inner = cmd.into_inner().collect::<Vec<_>>();
+        let name = &inner[0];
+        let params = inner
+            .iter()
+            .filter(|p| p.as_rule() == Rule::inst_param)
+            .collect::<Vec<_>>();
+        let comp_name = &inner[params.len() + 1];
+        let Some(&comp) = self.comps.get(comp_name.as_str()) else {
+            return Err(error(comp_name, "undefined component"));
+        };
+        let lives = inner.last().unwrap().clone().into_inner();
+        let info = src_name(name).map_or_else(ir::Info::empty, |n| {
+            ir::Info::instance(
+                n,
+                pos(self.file, comp_name),
+                pos(self.file, name),
+                lives.map(|l| pos(self.file, &l)).collect(),
+            )
+        });
+        let info = self.comp.add(info);
+        let inst = self.comp.add(ir::Instance {
+            comp,
+            args: Box::new([]),
+            lives: vec![],
+            params: vec![],
+            info,
+        });
+        self.decls.insts.insert(name.as_span().start(), inst);
+        self.names.last.insts.insert(name.as_str().into(), inst);
+
+        for param in params {
+            let name = param.clone().into_inner().nth(1).unwrap();
+            let owner = ir::ParamOwner::Instance {
+                inst,
+                base: ir::Foreign::new(ir::ParamIdx::UNKNOWN, comp),
+            };
+            let p = self.param(&name, owner);
+            self.comp.get_mut(inst).params.push(p);
+            self.names
+                .inst_params
+                .insert((inst, name.as_str().into()), p);
+        }
+        Ok(())
+    }
+
+    fn invoke(&mut self, cmd: Pair) {
+        let inner = cmd.into_inner().collect::<Vec<_>>();
+        let name = &inner[0];
+        let inst_name = &inner[1];
+        let binds = inner.iter().filter(|p| p.as_rule() == Rule::event_bind);
+        let info = src_name(name).map_or_else(ir::Info::empty, |n| {
+            ir::Info::invoke(
+                n,
+                pos(self.file, inst_name),
+                pos(self.file, name),
+                binds.map(|b| pos(self.file, b)).collect(),
+            )
+        });
+        let info = self.comp.add(info);
+        let inv = self.comp.add(ir::Invoke {
+            inst: ir::InstIdx::UNKNOWN,
+            events: vec![],
+            ports: vec![],
+            info,
+        });
+        self.decls.invs.insert(name.as_span().start(), inv);
+        self.names.last.invs.insert(name.as_str().into(), inv);
+
+        for def in inner.into_iter().filter(|p| p.as_rule() == Rule::inv_port) {
+            let mut inner = def.clone().into_inner();
+            let name = inner.next().unwrap();
+            let dir = match inner.next().unwrap().as_rule() {
+                Rule::dir_in => ir::Direction::In,
+                _ => ir::Direction::Out,
+            };
+            let owner = ir::PortOwner::Inv {
+                inv,
+                dir,
+                base: ir::Foreign::new(
+                    ir::PortIdx::UNKNOWN,
+                    ir::CompIdx::UNKNOWN,
+                ),
+            };
+            let p = self.port(def, owner);
+            self.comp.get_mut(inv).ports.push(p);
+            self.names.inv_ports.insert((inv, name.as_str().into()), p);
+        }
+    }
+}
+
+/// An operand of a comparison
+enum Operand {
+    Expr(ir::ExprIdx),
+    Time(ir::TimeIdx),
+    TimeSub(ir::TimeSub),
+}
+
+/// Defines the entities created by [Declare] and builds the control of a
+/// component.
+struct Define<'a> {
+    comp: &'a mut ir::Component,
+    file: FileIdx,
+    decls: &'a Decls,
+    sigs: &'a HashMap<ir::CompIdx, Sig>,
+    names: &'a Names,
+    /// Definitions in the enclosing blocks and bundles
+    scopes: Vec<Scope>,
+}
+
+impl Define<'_> {
+    fn lookup<T: Copy>(
+        &self,
+        name: &Pair,
+        kind: &str,
+        get: impl Fn(&Scope) -> &HashMap<String, T>,
+    ) -> ParseResult<T> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain(Some(&self.names.last))
+            .find_map(|s| get(s).get(name.as_str()).copied())
+            .ok_or_else(|| error(name, format!("undefined {kind}")))
+    }
+
+    fn scope(&mut self) -> &mut Scope {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn component(mut self, pair: Pair) -> ParseResult<()> {
+        self.scopes.push(Scope::default());
+        for node in pair.into_inner() {
+            match node.as_rule() {
+                Rule::sig_events => {
+                    for def in node.into_inner() {
+                        self.event(def)?;
+                    }
+                }
+                Rule::event_decl => {
+                    self.event(node.into_inner().next().unwrap())?;
+                }
+                Rule::ports => {
+                    for def in node.into_inner() {
+                        self.port(def)?;
+                    }
+                }
+                Rule::interface_decl => {
+                    let ev = node.into_inner().nth(1).unwrap();
+                    let ev = ev.into_inner().next().unwrap();
+                    let ev = self.lookup(&ev, "event", |s| &s.events)?;
+                    self.comp.get_mut(ev).has_interface = true;
+                }
+                Rule::exists_decl => {
+                    let mut inner = node.into_inner();
+                    let mut name = inner.next().unwrap();
+                    if name.as_rule() == Rule::opaque {
+                        name = inner.next().unwrap();
+                    }
+                    let p = self.decls.params[&name.as_span().start()];
+                    if let Some(assumes) = inner.next() {
+                        let props = assumes
+                            .into_inner()
+                            .map(|p| self.prop(p))
+                            .collect::<ParseResult<Vec<_>>>()?;
+                        self.comp.add_exist_assumes(p, props);
+                    }
+                }
+                Rule::sig_asserts => {
+                    for p in node.into_inner() {
+                        let prop = self.prop(p)?;
+                        if self.is_event_prop(prop) {
+                            self.comp.add_event_assert(Some(prop));
+                        } else {
+                            self.comp.add_param_assert(Some(prop));
+                        }
+                    }
+                }
+                Rule::block => {
+                    self.comp.cmds = self.commands(node)?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Does the proposition constrain events?
+    fn is_event_prop(&self, prop: ir::PropIdx) -> bool {
+        match self.comp.get(prop) {
+            ir::Prop::TimeCmp(_) | ir::Prop::TimeSubCmp(_) => true,
+            ir::Prop::True | ir::Prop::False | ir::Prop::Cmp(_) => false,
+            ir::Prop::Not(p) => self.is_event_prop(*p),
+            ir::Prop::And(l, r)
+            | ir::Prop::Or(l, r)
+            | ir::Prop::Implies(l, r) => {
+                self.is_event_prop(*l) || self.is_event_prop(*r)
+            }
+        }
+    }
+
+    fn event(&mut self, def: Pair) -> ParseResult<()> {
+        let mut inner = def.into_inner();
+        let ev = self.decls.events[&inner.next().unwrap().as_span().start()];
+        let delay = self.timesub(inner.next().unwrap())?;
+        self.comp.get_mut(ev).delay = delay;
+        Ok(())
+    }
+
+    /// Define the type of a port declared by [Declare::port]
+    fn port(&mut self, def: Pair) -> ParseResult<ir::PortIdx> {
+        let inner = def.into_inner().collect::<Vec<_>>();
+        let idx = self.decls.ports[&inner[0].as_span().start()];
+        let live = inner.iter().find(|p| p.as_rule() == Rule::liveness);
+        let width = self.expr(inner.last().unwrap().clone())?;
+
+        let mut idxs = self.comp.get(idx).live.idxs.clone().into_iter();
+        let mut lens = vec![];
+        let mut range = None;
+        self.scopes.push(Scope::default());
+        for node in live.unwrap().clone().into_inner() {
+            if node.as_rule() == Rule::range {
+                range = Some(self.range(node)?);
+                continue;
+            }
+            let mut b = node.into_inner();
+            let name = b.next().unwrap();
+            lens.push(self.expr(b.next().unwrap())?);
+            let p = idxs.next().unwrap();
+            self.scope().params.insert(name.as_str().into(), p);
+        }
+        self.scopes.pop();
+
+        let port = self.comp.get_mut(idx);
+        port.width = width;
+        port.live.lens = lens;
+        port.live.range = range.unwrap();
+        Ok(idx)
+    }
+
+    fn commands(&mut self, block: Pair) -> ParseResult<Vec<ir::Command>> {
+        self.scopes.push(Scope::default());
+        let cmds = block
+            .into_inner()
+            .map(|c| self.command(c))
+            .collect::<ParseResult<Vec<_>>>();
+        self.scopes.pop();
+        cmds
+    }
+
+    fn command(&mut self, cmd: Pair) -> ParseResult<ir::Command> {
+        let c = match cmd.as_rule() {
+            Rule::for_loop => {
+                let mut inner = cmd.into_inner();
+                let name = inner.next().unwrap();
+                let index = self.decls.params[&name.as_span().start()];
+                let start = self.expr(inner.next().unwrap())?;
+                let end = self.expr(inner.next().unwrap())?;
+                self.scopes.push(Scope::default());
+                self.scope().params.insert(name.as_str().into(), index);
+                let body = self.commands(inner.next().unwrap());
+                self.scopes.pop();
+                ir::Loop {
+                    index,
+                    start,
+                    end,
+                    body: body?,
+                }
+                .into()
+            }
+            Rule::if_cmd => {
+                let mut inner = cmd.into_inner();
+                let cond = self.prop(inner.next().unwrap())?;
+                let then = self.commands(inner.next().unwrap())?;
+                let alt =
+                    inner.next().map_or(Ok(vec![]), |b| self.commands(b))?;
+                ir::If { cond, then, alt }.into()
+            }
+            Rule::value_assert => self.value_assert(cmd)?.into(),
+            Rule::fact => {
+                let mut inner = cmd.into_inner();
+                let kind = inner.next().unwrap();
+                let prop = inner.next().unwrap();
+                let reason = Reason::misc(
+                    format!("{} in the IR", kind.as_str()),
+                    pos(self.file, &prop),
+                );
+                let info = self.comp.add(ir::Info::assert(reason));
+                let prop = self.prop(prop)?;
+                if kind.as_rule() == Rule::assert {
+                    ir::Fact::assert(prop, info).into()
+                } else {
+                    ir::Fact::assume(prop, info).into()
+                }
+            }
+            Rule::exists_cmd => {
+                let mut inner = cmd.into_inner();
+                let name = inner.next().unwrap();
+                let param = self.lookup(&name, "parameter", |s| &s.params)?;
+                let expr = self.expr(inner.next().unwrap())?;
+                ir::Exists { param, expr }.into()
+            }
+            Rule::bundle_def => {
+                let name = cmd.clone().into_inner().next().unwrap();
+                let p = self.port(cmd)?;
+                self.scope().ports.insert(name.as_str().into(), p);
+                p.into()
+            }
+            Rule::instance => self.instance(cmd)?.into(),
+            Rule::invoke => self.invoke(cmd)?.into(),
+            Rule::connect => {
+                let mut inner = cmd.into_inner();
+                let dst = inner.next().unwrap();
+                let src = inner.next().unwrap();
+                let info = ir::Info::connect(
+                    pos(self.file, &dst),
+                    pos(self.file, &src),
+                );
+                ir::Connect {
+                    dst: self.access(dst)?,
+                    src: self.access(src)?,
+                    info: self.comp.add(info),
+                }
+                .into()
+            }
+            r => unreachable!("unexpected command: {r:?}"),
+        };
+        Ok(c)
+    }
+
+    fn instance(&mut self, cmd: Pair) -> ParseResult<ir::InstIdx> {
+        let inner = cmd.into_inner().collect::<Vec<_>>();
+        let name = &inner[0];
+        let inst = self.decls.insts[&name.as_span().start()];
+        let comp = self.comp.get(inst).comp;
+        let sig = &self.sigs[&comp];
+        for param in inner.iter().filter(|p| p.as_rule() == Rule::inst_param) {
+            let name = param.clone().into_inner().nth(1).unwrap();
+            let p = self.decls.params[&name.as_span().start()];
+            let Some(&base) = sig.params.get(name.as_str()) else {
+                return Err(error(&name, "undefined parameter of component"));
+            };
+            self.comp.get_mut(p).owner = ir::ParamOwner::Instance {
+                inst,
+                base: ir::Foreign::new(base, comp),
+            };
+        }
+
+        let args = inner[inner.len() - 2]
+            .clone()
+            .into_inner()
+            .map(|e| self.expr(e))
+            .collect::<ParseResult<Box<_>>>()?;
+        let lives = inner[inner.len() - 1]
+            .clone()
+            .into_inner()
+            .map(|r| self.range(r))
+            .collect::<ParseResult<Vec<_>>>()?;
+        let instance = self.comp.get_mut(inst);
+        instance.args = args;
+        instance.lives = lives;
+        self.scope().insts.insert(name.as_str().into(), inst);
+        Ok(inst)
+    }
+
+    fn invoke(&mut self, cmd: Pair) -> ParseResult<ir::InvIdx> {
+        let inner = cmd.into_inner().collect::<Vec<_>>();
+        let name = &inner[0];
+        let inv = self.decls.invs[&name.as_span().start()];
+        let inst = self.lookup(&inner[1], "instance", |s| &s.insts)?;
+        let comp = self.comp.get(inst).comp;
+        let sig = &self.sigs[&comp];
+
+        let mut events = vec![];
+        for (k, bind) in inner
+            .iter()
+            .filter(|p| p.as_rule() == Rule::event_bind)
+            .enumerate()
+        {
+            let Some(&base) = sig.events.get(k) else {
+                return Err(error(
+                    bind,
+                    "component does not have this many events",
+                ));
+            };
+            let mut b = bind.clone().into_inner();
+            let arg = self.time(b.next().unwrap())?;
+            let delay = b.next().unwrap();
+            let info = ir::Info::event_bind(
+                pos(self.file, &delay),
+                pos(self.file, bind),
+            );
+            let info = self.comp.add(info);
+            let delay = self.timesub(delay)?;
+            events.push(ir::EventBind::new(
+                delay,
+                arg,
+                info,
+                ir::Foreign::new(base, comp),
+            ));
+        }
+
+        for def in inner.iter().filter(|p| p.as_rule() == Rule::inv_port) {
+            let name = def.clone().into_inner().next().unwrap();
+            let Some(&base) = sig.ports.get(name.as_str()) else {
+                return Err(error(&name, "undefined port of component"));
+            };
+            let p = self.port(def.clone())?;
+            if let ir::PortOwner::Inv { base: b, .. } =
+                &mut self.comp.get_mut(p).owner
+            {
+                *b = ir::Foreign::new(base, comp);
+            }
+        }
+
+        let invoke = self.comp.get_mut(inv);
+        invoke.inst = inst;
+        invoke.events = events;
+        self.scope().invs.insert(name.as_str().into(), inv);
+        Ok(inv)
+    }
+
+    fn value_assert(&mut self, cmd: Pair) -> ParseResult<ir::ValueAssert> {
+        let cons_loc = pos(self.file, &cmd);
+        let mut inner = cmd.into_inner();
+        let lhs = inner.next().unwrap();
+        let op = inner.next().unwrap();
+        let rhs = inner.next().unwrap();
+        let range = inner.next().unwrap();
+        let info = ir::Info::value_assert(
+            cons_loc,
+            pos(self.file, &lhs),
+            pos(self.file, &rhs),
+            pos(self.file, &range),
+        );
+        let lhs = self.value_term(lhs)?;
+        let rhs = self.value_term(rhs)?;
+        Ok(ir::ValueAssert {
+            cons: Self::cmp(&op, lhs, rhs),
+            range: self.range(range)?,
+            info: self.comp.add(info),
+        })
+    }
+
+    fn value_term(&mut self, term: Pair) -> ParseResult<ir::ValueTerm> {
+        let inner = term.into_inner().next().unwrap();
+        Ok(match inner.as_rule() {
+            Rule::access => ir::ValueTerm::Port(self.access(inner)?),
+            _ => ir::ValueTerm::Expr(self.expr(inner)?),
+        })
+    }
+
+    fn access(&mut self, access: Pair) -> ParseResult<ir::Access> {
+        let mut inner = access.into_inner();
+        let mut port_ref = inner.next().unwrap().into_inner();
+        let name = port_ref.next().unwrap();
+        let port = if let Some(port) = port_ref.next() {
+            let inv = self.lookup(&name, "invocation", |s| &s.invs)?;
+            *self
+                .names
+                .inv_ports
+                .get(&(inv, port.as_str().into()))
+                .ok_or_else(|| error(&port, "undefined port of invocation"))?
+        } else {
+            self.lookup(&name, "port", |s| &s.ports)?
+        };
+        let ranges = inner
+            .map(|r| {
+                let mut r = r.into_inner();
+                let start = self.expr(r.next().unwrap())?;
+                let end = match r.next() {
+                    Some(e) => self.expr(e)?,
+                    None => {
+                        let one = self.comp.num(1);
+                        start.add(one, self.comp)
+                    }
+                };
+                Ok((start, end))
+            })
+            .collect::<ParseResult<_>>()?;
+        Ok(ir::Access { port, ranges })
+    }
+
+    fn range(&mut self, range: Pair) -> ParseResult<ir::Range> {
+        let mut inner = range.into_inner();
+        Ok(ir::Range {
+            start: self.time(inner.next().unwrap())?,
+            end: self.time(inner.next().unwrap())?,
+        })
+    }
+
+    fn time(&mut self, time: Pair) -> ParseResult<ir::TimeIdx> {
+        let mut inner = time.into_inner();
+        let ev = inner.next().unwrap().into_inner().next().unwrap();
+        let event = self.lookup(&ev, "event", |s| &s.events)?;
+        let offset = match inner.next() {
+            Some(e) => self.expr(e)?,
+            None => self.comp.num(0),
+        };
+        Ok(self.comp.add(ir::Time { event, offset }))
+    }
+
+    fn timesub(&mut self, ts: Pair) -> ParseResult<ir::TimeSub> {
+        let inner = ts.into_inner().next().unwrap();
+        match inner.as_rule() {
+            Rule::time_diff => self.time_diff(inner),
+            _ => Ok(ir::TimeSub::Unit(self.expr(inner)?)),
+        }
+    }
+
+    fn time_diff(&mut self, diff: Pair) -> ParseResult<ir::TimeSub> {
+        let mut inner = diff.into_inner();
+        let l = self.time(inner.next().unwrap())?;
+        let r = self.time(inner.next().unwrap())?;
+        Ok(ir::TimeSub::Sym { l, r })
+    }
+
+    fn expr(&mut self, expr: Pair) -> ParseResult<ir::ExprIdx> {
+        match expr.as_rule() {
+            Rule::expr | Rule::term => {
+                let mut inner = expr.into_inner();
+                let mut lhs = self.expr(inner.next().unwrap())?;
+                while let Some(op) = inner.next() {
+                    let op = match op.as_rule() {
+                        Rule::op_add => ast::Op::Add,
+                        Rule::op_sub => ast::Op::Sub,
+                        Rule::op_mul => ast::Op::Mul,
+                        Rule::op_div => ast::Op::Div,
+                        _ => ast::Op::Mod,
+                    };
+                    let rhs = self.expr(inner.next().unwrap())?;
+                    lhs = self.comp.add(ir::Expr::Bin { op, lhs, rhs });
+                }
+                Ok(lhs)
+            }
+            Rule::num => {
+                let n = expr
+                    .as_str()
+                    .parse()
+                    .map_err(|_| error(&expr, "number is too large"))?;
+                Ok(self.comp.num(n))
+            }
+            Rule::param_ref => {
+                let mut inner = expr.into_inner();
+                let name = inner.next().unwrap();
+                let p = if let Some(param) = inner.next() {
+                    let inst = self.lookup(&name, "instance", |s| &s.insts)?;
+                    *self
+                        .names
+                        .inst_params
+                        .get(&(inst, param.as_str().into()))
+                        .ok_or_else(|| {
+                            error(&param, "undefined parameter of instance")
+                        })?
+                } else {
+                    self.lookup(&name, "parameter", |s| &s.params)?
+                };
+                Ok(self.comp.add(ir::Expr::Param(p)))
+            }
+            Rule::call => {
+                let mut inner = expr.into_inner();
+                let op = match inner.next().unwrap().as_str() {
+                    "pow2" => ast::Fn::Pow2,
+                    "log2" => ast::Fn::Log2,
+                    "sin_bits" => ast::Fn::SinB,
+                    "cos_bits" => ast::Fn::CosB,
+                    _ => ast::Fn::BitRev,
+                };
+                let args =
+                    inner.map(|a| self.expr(a)).collect::<ParseResult<_>>()?;
+                Ok(self.comp.add(ir::Expr::Fn { op, args }))
+            }
+            r => unreachable!("unexpected expression: {r:?}"),
+        }
+    }
+
+    fn cmp<T>(op: &Pair, lhs: T, rhs: T) -> ir::CmpOp<T> {
+        match op.as_rule() {
+            Rule::op_gte => ir::CmpOp::gte(lhs, rhs),
+            Rule::op_gt => ir::CmpOp::gt(lhs, rhs),
+            Rule::op_lte => ir::CmpOp::lte(lhs, rhs),
+            Rule::op_lt => ir::CmpOp::lt(lhs, rhs),
+            _ => ir::CmpOp::eq(lhs, rhs),
+        }
+    }
+
+    fn operand(&mut self, op: Pair) -> ParseResult<Operand> {
+        Ok(match op.as_rule() {
+            Rule::time_diff => Operand::TimeSub(self.time_diff(op)?),
+            Rule::time => Operand::Time(self.time(op)?),
+            _ => Operand::Expr(self.expr(op)?),
+        })
+    }
+
+    fn prop(&mut self, prop: Pair) -> ParseResult<ir::PropIdx> {
+        let p = match prop.as_rule() {
+            Rule::prop => {
+                let mut inner = prop.into_inner();
+                let l = self.prop(inner.next().unwrap())?;
+                match inner.next() {
+                    Some(r) => {
+                        let r = self.prop(r)?;
+                        ir::Prop::Implies(l, r)
+                    }
+                    None => return Ok(l),
+                }
+            }
+            Rule::prop_or | Rule::prop_and => {
+                let is_or = prop.as_rule() == Rule::prop_or;
+                let mut inner = prop.into_inner();
+                let mut l = self.prop(inner.next().unwrap())?;
+                for r in inner {
+                    let r = self.prop(r)?;
+                    l = self.comp.add(if is_or {
+                        ir::Prop::Or(l, r)
+                    } else {
+                        ir::Prop::And(l, r)
+                    });
+                }
+                return Ok(l);
+            }
+            Rule::ptrue => ir::Prop::True,
+            Rule::pfalse => ir::Prop::False,
+            Rule::negation => {
+                let p = self.prop(prop.into_inner().next().unwrap())?;
+                ir::Prop::Not(p)
+            }
+            Rule::cmp => {
+                let mut inner = prop.clone().into_inner();
+                let lhs = self.operand(inner.next().unwrap())?;
+                let op = inner.next().unwrap();
+                let rhs = self.operand(inner.next().unwrap())?;
+                match (lhs, rhs) {
+                    (Operand::Expr(l), Operand::Expr(r)) => {
+                        ir::Prop::Cmp(Self::cmp(&op, l, r))
+                    }
+                    (Operand::Time(l), Operand::Time(r)) => {
+                        ir::Prop::TimeCmp(Self::cmp(&op, l, r))
+                    }
+                    (Operand::Time(_), _) | (_, Operand::Time(_)) => {
+                        return Err(error(
+                            &prop,
+                            "cannot compare a time with an expression",
+                        ))
+                    }
+                    (l, r) => {
+                        let ts = |o| match o {
+                            Operand::Expr(e) => ir::TimeSub::Unit(e),
+                            Operand::TimeSub(ts) => ts,
+                            Operand::Time(_) => unreachable!(),
+                        };
+                        ir::Prop::TimeSubCmp(Self::cmp(&op, ts(l), ts(r)))
+                    }
+                }
+            }
+            r => unreachable!("unexpected proposition: {r:?}"),
+        };
+        Ok(self.comp.add(p))
+    }
+}
@@ -119,6 +119,29 @@ impl<'a, 'b> Printer<'a, 'b> {
                     self.comp.display(*expr)
                 )
             }
+            ir::Command::ValueAssert(assert) => {
+                let ir::CmpOp { op, lhs, rhs } = &assert.cons;
+                write!(f, "{:indent$}assert ", "")?;
+                self.value_term(lhs, f)?;
+                write!(f, " {op} ")?;
+                self.value_term(rhs, f)?;
+                write!(f, " in {};", self.comp.display(&assert.range))
+            }
+        }
+    }
+
+    fn value_term(
+        &self,
+        t: &ir::ValueTerm,
+        f: &mut impl io::Write,
+    ) -> io::Result<()> {
+        match t {
+            ir::ValueTerm::Port(access) => {
+                write!(f, "value(")?;
+                self.comp.write(access, f)?;
+                write!(f, ")")
+            }
+            ir::ValueTerm::Expr(e) => write!(f, "{}", self.comp.display(*e)),
         }
     }
 
@@ -213,11 +236,68 @@ impl<'a, 'b> Printer<'a, 'b> {
             .join(",\n");
         writeln!(f, "{outs}) with {{")?;
 
+        // Signature parameters and events that are not arguments of the
+        // component, for example, after monomorphization.
+        for (idx, param) in self.comp.params().iter() {
+            if param.is_sig_owned() && !self.comp.param_args().contains(&idx) {
+                writeln!(
+                    f,
+                    "{:indent$}param {};",
+                    "",
+                    self.comp.display(idx),
+                    indent = indent + 2
+                )?;
+            }
+        }
+        for (idx, ev) in self.comp.events().iter() {
+            if !self.comp.event_args().contains(&idx) {
+                writeln!(
+                    f,
+                    "{:indent$}event {}: {};",
+                    "",
+                    self.comp.display(idx),
+                    self.comp.display(&ev.delay),
+                    indent = indent + 2
+                )?;
+            }
+        }
+        for (idx, ev) in self.comp.events().iter() {
+            if ev.has_interface {
+                let name = self
+                    .comp
+                    .get(ev.info)
+                    .as_event()
+                    .and_then(|ev| ev.interface_name)
+                    .map_or("_".to_string(), |n| n.to_string());
+                writeln!(
+                    f,
+                    "{:indent$}interface {name}: {};",
+                    "",
+                    self.comp.display(idx),
+                    indent = indent + 2
+                )?;
+            }
+        }
+
+        for (name, width) in self.comp.unannotated_ports.iter() {
+            writeln!(
+                f,
+                "{:indent$}unannotated {name}: {width};",
+                "",
+                indent = indent + 2
+            )?;
+        }
+
         for param in self.comp.exist_params() {
+            let opaque = matches!(
+                self.comp.get(param).owner,
+                ir::ParamOwner::Exists { opaque: true }
+            );
             write!(
                 f,
-                "{:indent$}exists {}",
+                "{:indent$}exists {}{}",
                 "",
+                if opaque { "opaque " } else { "" },
                 self.comp.display(param),
                 indent = indent + 2
             )?;
@@ -233,8 +313,9 @@ impl<'a, 'b> Printer<'a, 'b> {
         let p_asserts = self.comp.get_param_asserts();
         let e_asserts = self.comp.get_event_asserts();
 
+        writeln!(f, "}}")?;
         if !p_asserts.is_empty() || !e_asserts.is_empty() {
-            writeln!(f, "}} where ")?;
+            writeln!(f, "where")?;
             for idx in p_asserts.iter().chain(e_asserts.iter()) {
                 write!(f, "{:indent$}", "", indent = indent + 2)?;
                 self.comp.write(*idx, f)?;
@@ -363,21 +444,42 @@ impl<'a, 'b> Printer<'a, 'b> {
         } = self.comp.get(idx);
         write!(f, "{:indent$}", "")?;
         self.comp.write(idx, f)?;
-
-        let ports = ports.iter().map(|p| self.comp.display(*p)).join(", ");
-        if !ports.is_empty() {
-            write!(f, ", {ports}")?;
-        }
         write!(f, " = ")?;
-
         self.comp.write(*inst, f)?;
-        write!(
-            f,
-            "<{}>;",
-            events.iter().map(|e| self.comp.display(e.arg)).join(", ")
-        )?;
-
-        Ok(())
+        let events = events
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}: {}",
+                    self.comp.display(e.arg),
+                    self.comp.display(&e.delay)
+                )
+            })
+            .join(", ");
+        writeln!(f, "<{events}> with {{")?;
+        // The ports defined by the invocation
+        for port in ports {
+            let ir::Port {
+                owner, width, live, ..
+            } = self.comp.get(*port);
+            let ir::PortOwner::Inv { dir, .. } = owner else {
+                unreachable!("invocation defines a non-invocation port")
+            };
+            let name = self
+                .comp
+                .get(self.comp.get(*port).info)
+                .as_port()
+                .map_or(format!("{port}"), |p| p.name.to_string());
+            writeln!(
+                f,
+                "{:indent$}{name}: bundle({dir}) {} {};",
+                "",
+                self.comp.display(live),
+                self.comp.display(*width),
+                indent = indent + 2
+            )?;
+        }
+        write!(f, "{:indent$}}}", "")
     }
 
     pub fn comp(
@@ -161,7 +161,13 @@ impl<'a> DisplayCtx<&'a ir::Liveness> for ir::Component {
             .iter()
             .zip(lens)
             .map(|(idx, len)| {
-                format!("{}:{}", self.display(*idx), self.display(*len))
+                // Passes that split bundles delete their index parameters
+                let idx = if self.params().is_valid(*idx) {
+                    self.display(*idx)
+                } else {
+                    "_".to_string()
+                };
+                format!("{idx}:{}", self.display(*len))
             })
             .join(", ");
         write!(f, "for<{idxs}> {}", self.display(range))
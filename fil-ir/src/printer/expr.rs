@@ -62,7 +62,21 @@ fn display_expr_helper(
         ir::Expr::Bin { op, lhs, rhs } => {
             let inner = ECtx::from(*op);
             let left = display_expr_helper(*lhs, inner, comp);
-            let right = display_expr_helper(*rhs, inner, comp);
+            let mut right = display_expr_helper(*rhs, inner, comp);
+            // Operators associate to the left so a right operand with the
+            // same precedence needs parentheses unless both operators are
+            // the same associative operator.
+            if let ir::Expr::Bin { op: rop, .. } = comp.get(*rhs) {
+                let level = |op: &ast::Op| {
+                    matches!(op, ast::Op::Mul | ast::Op::Div | ast::Op::Mod)
+                };
+                let assoc =
+                    op == rop && matches!(op, ast::Op::Add | ast::Op::Mul);
+                let wrapped = inner > ECtx::from(*rop);
+                if level(op) == level(rop) && !assoc && !wrapped {
+                    right = format!("({right})");
+                }
+            }
             // If context binds more tightly than the inner operator,
             // wrap the inner expression in parens.
             if ctx > inner {
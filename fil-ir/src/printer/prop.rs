@@ -100,7 +100,8 @@ fn display_prop_helper(
         }
         ir::Prop::Implies(l, r) => {
             let inner = PCtx::Implies;
-            let l = display_prop_helper(*l, inner, comp);
+            // Implications associate to the right
+            let l = display_prop_helper(*l, PCtx::Or, comp);
             let r = display_prop_helper(*r, inner, comp);
             if inner < ctx {
                 format!("({} => {})", l, r)
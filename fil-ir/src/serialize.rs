@@ -0,0 +1,68 @@
+//! On-disk format of the IR.
+//!
+//! A [Context] is written as a JSON document together with the positions its
+//! information refers to:
+//! ```text
+//! { "version": 2, "pass": "..", "positions": { "files": [..], "positions": [..] }, "context": { .. } }
+//! ```
+//! `pass` is the last pass that ran on the context.
+//! Indices are written as integers and keep their meaning when the document
+//! is read back, so a program can be stopped after any pass and compilation
+//! resumed from the document.
+use crate::Context;
+use fil_utils::{GlobalPositionTable, PosIdx, PositionData};
+use serde::{Deserialize, Serialize};
+
+/// Version of the format. Must be bumped whenever the representation of the
+/// IR changes.
+const VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    pass: &'a str,
+    positions: PositionData,
+    context: &'a Context,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    version: u32,
+    pass: String,
+    positions: PositionData,
+    /// Read once the positions have been imported
+    context: serde_json::Value,
+}
+
+impl Context {
+    /// Serialize the context produced by the pass and the global position
+    /// table
+    pub fn to_json(&self, pass: &str) -> String {
+        let doc = DocumentRef {
+            version: VERSION,
+            pass,
+            positions: GlobalPositionTable::as_ref().export(),
+            context: self,
+        };
+        serde_json::to_string(&doc).expect("failed to serialize the IR")
+    }
+
+    /// Deserialize a context written by [Context::to_json] and the pass that
+    /// produced it. The positions of the document are added to the global
+    /// position table.
+    pub fn from_json(text: &str) -> Result<(Self, String), String> {
+        let doc: Document = serde_json::from_str(text)
+            .map_err(|e| format!("malformed IR: {e}"))?;
+        if doc.version != VERSION {
+            return Err(format!(
+                "IR was written with format version {} but version {VERSION} is expected",
+                doc.version
+            ));
+        }
+        let offset = GlobalPositionTable::as_mut().import(doc.positions)?;
+        let ctx =
+            PosIdx::relocated(offset, || serde_json::from_value(doc.context))
+                .map_err(|e| format!("malformed IR: {e}"))?;
+        Ok((ctx, doc.pass))
+    }
+}
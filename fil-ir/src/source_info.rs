@@ -1,7 +1,8 @@
 use super::{utils::SparseInfoMap, Event, Param, Port};
 use fil_ast as ast;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 /// Externally facing interface name information for components.
 pub struct InterfaceSrc {
     /// The name of the component
@@ -27,3 +28,15 @@ impl InterfaceSrc {
         }
     }
 }
+
+#[derive(Clone, Serialize, Deserialize)]
+/// The source component and the values of its signature parameters that a
+/// monomorphized component was generated from.
+pub struct Specialization {
+    /// The name of the source component
+    pub name: ast::Id,
+    /// Values of the signature parameters in the order they are defined
+    pub params: Vec<(ast::Id, u64)>,
+    /// Values of the existentially quantified parameters
+    pub exists: Vec<(ast::Id, u64)>,
+}
@@ -4,9 +4,10 @@ use super::{
 };
 use fil_ast::Op;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// An interval of time
 pub struct Range {
     pub start: TimeIdx,
@@ -27,7 +28,7 @@ impl Foldable<ParamIdx, ExprIdx> for Range {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// The context in which a port was defined.
 pub enum PortOwner {
     /// The port is defined in the signature
@@ -88,7 +89,7 @@ impl PortOwner {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum Direction {
     /// Input port
     In,
@@ -122,7 +123,7 @@ impl fmt::Display for Direction {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Duration when the port caries a meaningful value.
 /// Equivalent to the bundle type:
 /// ```
@@ -134,7 +135,7 @@ pub struct Liveness {
     pub range: Range,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// A port tracks its definition and liveness.
 /// A port in the IR generalizes both bundles and normal ports.
 pub struct Port {
@@ -199,7 +200,7 @@ impl Port {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Represents a port access in bundle syntax since the IR desugars all ports to
 /// bundles.
 pub struct Access {
@@ -291,7 +292,7 @@ impl Access {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Construct that defines the parameter
 pub enum ParamOwner {
     /// Defined by the signature (passed in when instantiated)
@@ -341,7 +342,7 @@ impl ParamOwner {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Parameters with an optional initial value
 pub struct Param {
     pub owner: ParamOwner,
@@ -369,7 +370,7 @@ impl ParamIdx {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Events must have a delay and an optional default value
 pub struct Event {
     pub delay: TimeSub,
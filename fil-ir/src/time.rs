@@ -4,9 +4,10 @@ use super::{
     AddCtx, Component, Ctx, EventIdx, Expr, ExprIdx, Foldable, ParamIdx,
     TimeIdx,
 };
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// A temporal event. Represents an offset from the start of the event.
 pub struct Time {
     pub event: EventIdx,
@@ -55,7 +56,7 @@ impl TimeIdx {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 /// Represents the difference between two events.
 pub enum TimeSub {
     /// Concrete difference between two time expressions
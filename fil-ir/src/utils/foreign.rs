@@ -1,9 +1,12 @@
 use crate::{Ctx, Idx, IdxLike};
+use serde::{Deserialize, Serialize};
 
 /// A reference to a foreign key and its owner.
 /// On its own, a foreign key is not very useful. We need provide it with a context
 /// that can resolve the owner which can then resolve the underlying type.
 /// However, we do not provide a way to extract the underyling `T`.
+#[derive(Serialize, Deserialize)]
+#[serde(bound = "")]
 pub struct Foreign<T, C>
 where
     C: Ctx<T>,
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{marker::PhantomData, num::NonZeroU32};
 
 #[macro_export]
@@ -68,6 +69,23 @@ impl<T> Ord for Idx<T> {
 /// All indexes are copy
 impl<T> Copy for Idx<T> {}
 
+/// Indices are serialized as their underlying integer which does not depend on
+/// the indexed type.
+impl<T> Serialize for Idx<T> {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        self.idx.serialize(s)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Idx<T> {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            idx: NonZeroU32::deserialize(d)?,
+            _phantom: PhantomData,
+        })
+    }
+}
+
 impl<T> Idx<T> {
     /// Representing an unknown index
     pub const UNKNOWN: Self = Self {
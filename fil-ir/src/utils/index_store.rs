@@ -1,5 +1,6 @@
 use crate::{utils, utils::Idx, AddCtx, Ctx, MutCtx};
 use bitvec::vec::BitVec;
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use std::{fmt::Display, marker::PhantomData};
 
 #[derive(Clone)]
@@ -129,6 +130,41 @@ where
     }
 }
 
+/// Serialized as the stored values together with the validity of each index.
+/// Values at invalid indices are kept so that indices remain stable.
+impl<T, I> Serialize for IndexStore<T, I>
+where
+    T: Serialize,
+    I: utils::IdxLike<T>,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let valid = self.valid.iter().map(|b| *b).collect::<Vec<_>>();
+        (&self.store, valid).serialize(s)
+    }
+}
+
+impl<'de, T, I> Deserialize<'de> for IndexStore<T, I>
+where
+    T: Deserialize<'de>,
+    I: utils::IdxLike<T>,
+{
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let (store, valid) = <(Vec<T>, Vec<bool>)>::deserialize(d)?;
+        if store.len() != valid.len() {
+            return Err(D::Error::custom(format!(
+                "store has {} values but {} validity bits",
+                store.len(),
+                valid.len()
+            )));
+        }
+        Ok(Self {
+            store,
+            valid: valid.into_iter().collect(),
+            _type: PhantomData,
+        })
+    }
+}
+
 impl<T> IntoIterator for IndexStore<T> {
     type Item = T;
 
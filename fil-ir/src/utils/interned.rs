@@ -1,4 +1,5 @@
 use crate::{utils, utils::Idx, AddCtx, Ctx};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use std::{collections::HashMap, fmt::Display, rc::Rc};
 
 #[derive(Clone)]
@@ -99,6 +100,36 @@ where
     }
 }
 
+/// Only the values are serialized. The reverse mapping is rebuilt by interning
+/// the values in order which assigns them their original indices.
+impl<T, I> Serialize for Interned<T, I>
+where
+    T: Eq + std::hash::Hash + Serialize,
+    I: utils::IdxLike<T>,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(self.store.iter().map(|v| &**v))
+    }
+}
+
+impl<'de, T, I> Deserialize<'de> for Interned<T, I>
+where
+    T: Eq + std::hash::Hash + Deserialize<'de>,
+    I: utils::IdxLike<T>,
+{
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let mut interned = Self::default();
+        for (idx, val) in Vec::<T>::deserialize(d)?.into_iter().enumerate() {
+            if interned.intern(val).get() != idx {
+                return Err(D::Error::custom(format!(
+                    "value at index {idx} is interned more than once"
+                )));
+            }
+        }
+        Ok(interned)
+    }
+}
+
 impl<T> Display for Interned<T>
 where
     T: Eq + std::hash::Hash + Display,
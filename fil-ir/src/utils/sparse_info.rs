@@ -1,4 +1,6 @@
 use crate::utils::{Idx, IdxLike};
+use itertools::Itertools;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
 /// A sparse information map to store information associated with indices.
@@ -86,6 +88,28 @@ where
     }
 }
 
+/// Serialized as a list of key-value pairs sorted by key so that the output
+/// does not depend on the iteration order of the map.
+impl<Assoc, Info, Key> Serialize for SparseInfoMap<Assoc, Info, Key>
+where
+    Info: Serialize,
+    Key: IdxLike<Assoc> + Serialize,
+{
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_seq(self.map.iter().sorted_by_key(|(k, _)| **k))
+    }
+}
+
+impl<'de, Assoc, Info, Key> Deserialize<'de> for SparseInfoMap<Assoc, Info, Key>
+where
+    Info: Deserialize<'de>,
+    Key: IdxLike<Assoc> + Deserialize<'de>,
+{
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        Ok(Vec::<(Key, Info)>::deserialize(d)?.into_iter().collect())
+    }
+}
+
 impl<T, V, Idx: IdxLike<T>> FromIterator<(Idx, V)>
     for SparseInfoMap<T, V, Idx>
 {
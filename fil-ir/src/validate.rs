@@ -205,6 +205,9 @@ impl<'a> Validate<'a> {
             ir::Command::Fact(_) => (),
             ir::Command::BundleDef(b) => self.bundle_def(*b),
             ir::Command::Exists(e) => self.exists(e),
+            ir::Command::ValueAssert(a) => {
+                a.ports().for_each(|a| self.access(a))
+            }
         }
     }
 
@@ -0,0 +1,135 @@
+//! Stable codes for the diagnostics reported by the compiler.
+//! Codes of the form `F0xxx` are errors and codes of the form `F1xxx` are lints.
+//! Each code has a long-form explanation in `explain/<code>.md` that is shown
+//! by `filament --explain <code>`.
+//! Codes must never be reused for a different diagnostic.
+
+// ================ Unprovable constraints ================
+/// Instantiation violates a parameter constraint
+pub const PARAM_CONSTRAINT: &str = "F0001";
+/// Invocation violates an event constraint
+pub const EVENT_CONSTRAINT: &str = "F0002";
+/// Body does not satisfy a constraint on an existential parameter
+pub const EXISTS_CONSTRAINT: &str = "F0003";
+/// Bundle lengths do not match
+pub const BUNDLE_LEN_MATCH: &str = "F0004";
+/// Bundle widths do not match
+pub const BUNDLE_WIDTH_MATCH: &str = "F0005";
+/// Out of bounds access of a bundle
+pub const IN_BOUNDS_ACCESS: &str = "F0006";
+/// Source is not available as long as the destination requires
+pub const LIVENESS: &str = "F0007";
+/// Bundle availability is greater than the delay of the event
+pub const BUNDLE_DELAY: &str = "F0008";
+/// Interval end is not greater than the start
+pub const WELL_FORMED_INTERVAL: &str = "F0009";
+/// Event used for longer than the instance borrow allows
+pub const EVENT_LIVE: &str = "F0010";
+/// Instance borrow is longer than the delay of the event
+pub const EVENT_LIVE_DELAY: &str = "F0011";
+/// Event provided to an invocation triggers too often
+pub const EVENT_TRIG: &str = "F0012";
+/// A source-level fact or another constraint cannot be proven
+pub const MISC_CONSTRAINT: &str = "F0013";
+
+// ================ Name resolution ================
+/// Use of an undefined name
+pub const UNDEFINED: &str = "F0020";
+/// Name is already bound
+pub const ALREADY_BOUND: &str = "F0021";
+/// Wrong number of parameters, events, or inputs
+pub const ARGUMENT_COUNT: &str = "F0022";
+/// Exists binding for a parameter that is not existentially quantified
+pub const NOT_EXISTENTIAL: &str = "F0023";
+/// Import cannot be resolved
+pub const UNRESOLVED_IMPORT: &str = "F0024";
+/// Import resolves to multiple files
+pub const AMBIGUOUS_IMPORT: &str = "F0025";
+
+// ================ Structural checks ================
+/// Instance that uses a phantom event is invoked multiple times
+pub const PHANTOM_SHARED: &str = "F0030";
+/// Invocation in a loop uses a phantom event with an instance outside it
+pub const PHANTOM_LOOP: &str = "F0031";
+/// Phantom event provided to a non-phantom event
+pub const PHANTOM_BINDING: &str = "F0032";
+/// Port is assigned to multiple times
+pub const MULTIPLE_ASSIGN: &str = "F0033";
+/// Bundle has unassigned locations
+pub const UNASSIGNED: &str = "F0034";
+/// Combinational loop
+pub const COMB_LOOP: &str = "F0035";
+
+// ================ Bitvector encoding ================
+/// Constraint has no counterexample in the bitvector encoding but cannot be
+/// proven for unbounded integers
+pub const WRAPAROUND: &str = "F0040";
+
+// ================ Bounded model checking ================
+/// Assertion on the values of ports is violated or cannot be checked
+pub const VALUE_ASSERT: &str = "F0050";
+/// Components produce different outputs or cannot be compared
+pub const NOT_EQUIVALENT: &str = "F0051";
+
+// ================ Lints ================
+/// Instance is never invoked
+pub const UNUSED_INSTANCE: &str = "F1001";
+/// Invocation output is never read
+pub const UNUSED_OUTPUT: &str = "F1002";
+/// Input port or bundle is never read
+pub const UNUSED_PORT: &str = "F1003";
+/// Bundle has locations that are never read
+pub const UNUSED_BUNDLE_LOCATION: &str = "F1004";
+/// Event with an interface port could be a phantom event
+pub const COULD_BE_PHANTOM: &str = "F1005";
+
+/// Long-form explanations for each code
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (PARAM_CONSTRAINT, include_str!("explain/F0001.md")),
+    (EVENT_CONSTRAINT, include_str!("explain/F0002.md")),
+    (EXISTS_CONSTRAINT, include_str!("explain/F0003.md")),
+    (BUNDLE_LEN_MATCH, include_str!("explain/F0004.md")),
+    (BUNDLE_WIDTH_MATCH, include_str!("explain/F0005.md")),
+    (IN_BOUNDS_ACCESS, include_str!("explain/F0006.md")),
+    (LIVENESS, include_str!("explain/F0007.md")),
+    (BUNDLE_DELAY, include_str!("explain/F0008.md")),
+    (WELL_FORMED_INTERVAL, include_str!("explain/F0009.md")),
+    (EVENT_LIVE, include_str!("explain/F0010.md")),
+    (EVENT_LIVE_DELAY, include_str!("explain/F0011.md")),
+    (EVENT_TRIG, include_str!("explain/F0012.md")),
+    (MISC_CONSTRAINT, include_str!("explain/F0013.md")),
+    (UNDEFINED, include_str!("explain/F0020.md")),
+    (ALREADY_BOUND, include_str!("explain/F0021.md")),
+    (ARGUMENT_COUNT, include_str!("explain/F0022.md")),
+    (NOT_EXISTENTIAL, include_str!("explain/F0023.md")),
+    (UNRESOLVED_IMPORT, include_str!("explain/F0024.md")),
+    (AMBIGUOUS_IMPORT, include_str!("explain/F0025.md")),
+    (PHANTOM_SHARED, include_str!("explain/F0030.md")),
+    (PHANTOM_LOOP, include_str!("explain/F0031.md")),
+    (PHANTOM_BINDING, include_str!("explain/F0032.md")),
+    (MULTIPLE_ASSIGN, include_str!("explain/F0033.md")),
+    (UNASSIGNED, include_str!("explain/F0034.md")),
+    (COMB_LOOP, include_str!("explain/F0035.md")),
+    (WRAPAROUND, include_str!("explain/F0040.md")),
+    (VALUE_ASSERT, include_str!("explain/F0050.md")),
+    (NOT_EQUIVALENT, include_str!("explain/F0051.md")),
+    (UNUSED_INSTANCE, include_str!("explain/F1001.md")),
+    (UNUSED_OUTPUT, include_str!("explain/F1002.md")),
+    (UNUSED_PORT, include_str!("explain/F1003.md")),
+    (UNUSED_BUNDLE_LOCATION, include_str!("explain/F1004.md")),
+    (COULD_BE_PHANTOM, include_str!("explain/F1005.md")),
+];
+
+/// The long-form explanation of a diagnostic code, if the code is known.
+/// Codes are matched case-insensitively.
+pub fn explain(code: &str) -> Option<&'static str> {
+    EXPLANATIONS
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, e)| *e)
+}
+
+/// All the known diagnostic codes
+pub fn all() -> impl Iterator<Item = &'static str> {
+    EXPLANATIONS.iter().map(|(c, _)| *c)
+}
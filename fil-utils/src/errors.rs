@@ -1,11 +1,23 @@
 //! Errors generated by the compiler.
-use super::{Id, InfoIdx};
+use super::{codes, Id, InfoIdx};
 use itertools::Itertools;
 
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, PartialOrd, Ord)]
+/// How severe a diagnostic is. Only errors cause compilation to fail.
+pub enum Severity {
+    Note,
+    Warning,
+    Error,
+}
+
 #[derive(PartialEq, Eq, Hash)]
 pub struct Error {
     pub kind: String,
     pub notes: Vec<InfoIdx>,
+    /// The severity of the diagnostic
+    pub severity: Severity,
+    /// Stable code identifying the diagnostic
+    pub code: Option<&'static str>,
 }
 
 impl std::fmt::Debug for Error {
@@ -29,10 +41,24 @@ impl Error {
         self
     }
 
+    /// Attach a stable code to this diagnostic.
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// Change the severity of this diagnostic.
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
     pub fn invalid_file(f: String) -> Self {
         Self {
             kind: format!("invalid file: {}", f),
             notes: vec![],
+            severity: Severity::Error,
+            code: None,
         }
     }
 
@@ -40,6 +66,8 @@ impl Error {
         Self {
             kind: format!("failed to write output: {}", e),
             notes: vec![],
+            severity: Severity::Error,
+            code: None,
         }
     }
 
@@ -47,6 +75,8 @@ impl Error {
         Self {
             kind: msg.to_string(),
             notes: vec![],
+            severity: Severity::Error,
+            code: None,
         }
     }
 
@@ -58,6 +88,8 @@ impl Error {
                 name.to_string(),
             ),
             notes: vec![],
+            severity: Severity::Error,
+            code: Some(codes::UNDEFINED),
         }
     }
 
@@ -68,6 +100,8 @@ impl Error {
                 kind.to_string()
             ),
             notes: vec![],
+            severity: Severity::Error,
+            code: Some(codes::ALREADY_BOUND),
         }
     }
 
@@ -75,6 +109,8 @@ impl Error {
         Self {
             kind: msg,
             notes: vec![],
+            severity: Severity::Error,
+            code: None,
         }
     }
 }
@@ -1,14 +1,19 @@
+pub mod codes;
 mod errors;
 mod global_sym;
 mod gsym;
 mod id;
+mod lint;
 mod math;
 mod position;
 mod reporter;
 
-pub use errors::{Error, FilamentResult};
+pub use errors::{Error, FilamentResult, Severity};
 pub use gsym::GSym;
 pub use id::Id;
+pub use lint::{Lint, LintConfig, LintLevel};
 pub use math::{all_indices, flat_idx, nd_idx};
-pub use position::{FileIdx, GPosIdx, GlobalPositionTable, PosData};
-pub use reporter::{Diagnostics, InfoIdx};
+pub use position::{
+    FileIdx, GPosIdx, GlobalPositionTable, PosData, PosIdx, PositionData,
+};
+pub use reporter::{Diagnostics, InfoIdx, Reported};
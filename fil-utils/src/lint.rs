@@ -0,0 +1,102 @@
+//! Lints are diagnostics that do not affect the correctness of a program and
+//! can be allowed, reported as warnings, or denied by the user.
+
+use crate::codes;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// How a lint is reported
+pub enum LintLevel {
+    /// Do not report the lint
+    Allow,
+    /// Report violations as warnings
+    Warn,
+    /// Report violations as errors
+    Deny,
+}
+
+/// A diagnostic that can be configured by the user.
+pub struct Lint {
+    /// Stable code identifying the lint
+    pub code: &'static str,
+    /// Name of the group of lints this lint belongs to
+    pub group: &'static str,
+    /// Level used when the user does not configure the lint
+    pub default: LintLevel,
+}
+
+impl Lint {
+    /// An instance that is never invoked
+    pub const UNUSED_INSTANCE: Lint =
+        Lint::new(codes::UNUSED_INSTANCE, "unused");
+    /// An invocation output that is never read
+    pub const UNUSED_OUTPUT: Lint = Lint::new(codes::UNUSED_OUTPUT, "unused");
+    /// An input port or bundle that is never read
+    pub const UNUSED_PORT: Lint = Lint::new(codes::UNUSED_PORT, "unused");
+    /// A bundle with some locations that are never read
+    pub const UNUSED_BUNDLE_LOCATION: Lint =
+        Lint::new(codes::UNUSED_BUNDLE_LOCATION, "unused");
+    /// An event with an interface port that could be a phantom event
+    pub const COULD_BE_PHANTOM: Lint =
+        Lint::new(codes::COULD_BE_PHANTOM, "phantom");
+
+    /// All the known lints
+    pub const ALL: &'static [Lint] = &[
+        Lint::UNUSED_INSTANCE,
+        Lint::UNUSED_OUTPUT,
+        Lint::UNUSED_PORT,
+        Lint::UNUSED_BUNDLE_LOCATION,
+        Lint::COULD_BE_PHANTOM,
+    ];
+
+    const fn new(code: &'static str, group: &'static str) -> Self {
+        Self {
+            code,
+            group,
+            default: LintLevel::Allow,
+        }
+    }
+
+    /// Returns true if `name` refers to this lint by its code or group.
+    pub fn matches(&self, name: &str) -> bool {
+        self.code == name || self.group == name
+    }
+
+    /// Returns true if `name` refers to some lint by its code or group.
+    pub fn is_known(name: &str) -> bool {
+        Lint::ALL.iter().any(|l| l.matches(name))
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+/// Lint levels configured by the user.
+/// Each entry is either a lint code or the name of a lint group.
+pub struct LintConfig {
+    pub allow: Vec<String>,
+    pub warn: Vec<String>,
+    pub deny: Vec<String>,
+}
+
+impl LintConfig {
+    /// The level at which `lint` should be reported in a component that
+    /// allows the lints in `allowed`.
+    /// Lints allowed in the source take precedence over the command line,
+    /// and denying a lint takes precedence over warning about or allowing it.
+    pub fn level<S: AsRef<str>>(
+        &self,
+        lint: &Lint,
+        allowed: &[S],
+    ) -> LintLevel {
+        let matches = |names: &[String]| names.iter().any(|n| lint.matches(n));
+        if allowed.iter().any(|n| lint.matches(n.as_ref())) {
+            LintLevel::Allow
+        } else if matches(&self.deny) {
+            LintLevel::Deny
+        } else if matches(&self.warn) {
+            LintLevel::Warn
+        } else if matches(&self.allow) {
+            LintLevel::Allow
+        } else {
+            lint.default
+        }
+    }
+}
@@ -1,12 +1,41 @@
 //! Tracking of source positions
 use codespan_reporting::{diagnostic::Label, files::SimpleFiles};
-use std::{mem, sync};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{cell::Cell, mem, sync};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize)]
 /// Handle to a position in a [PositionTable]
 /// The index refers to the index in the [PositionTable::indices] vector.
 pub struct PosIdx(u32);
 
+thread_local! {
+    /// Offset added to the positions that are deserialized
+    static RELOCATION: Cell<u32> = const { Cell::new(0) };
+}
+
+impl PosIdx {
+    /// Run `f` with every deserialized position other than the unknown one
+    /// shifted by `offset`. Used to read data whose positions have been
+    /// appended to a table by [PositionTable::import].
+    pub fn relocated<T>(offset: u32, f: impl FnOnce() -> T) -> T {
+        let prev = RELOCATION.with(|r| r.replace(offset));
+        let out = f();
+        RELOCATION.with(|r| r.set(prev));
+        out
+    }
+}
+
+impl<'de> Deserialize<'de> for PosIdx {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let idx = u32::deserialize(d)?;
+        if idx == 0 {
+            Ok(PosIdx(0))
+        } else {
+            Ok(PosIdx(idx + RELOCATION.with(Cell::get)))
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 /// Handle to a file in a [PositionTable]
 /// The index refers to the index in the [PositionTable::files] vector.
@@ -98,6 +127,55 @@ impl PositionTable {
         let file = &self.files.get(file.0).unwrap();
         (file.name(), file.source())
     }
+
+    /// The files and positions of the table other than the unknown ones
+    pub fn export(&self) -> PositionData {
+        let files = (1..)
+            .map_while(|i| self.files.get(i).ok())
+            .map(|f| (f.name().clone(), f.source().clone()))
+            .collect();
+        let positions = self.indices[1..]
+            .iter()
+            .map(|p| (p.file.0, p.start, p.end))
+            .collect();
+        PositionData { files, positions }
+    }
+
+    /// Append the files and positions exported from another table. Returns
+    /// the offset by which the imported positions are shifted, which must be
+    /// passed to [PosIdx::relocated] when reading data that refers to them.
+    pub fn import(&mut self, data: PositionData) -> Result<u32, String> {
+        // The unknown file and position are not exported
+        let file_base = (1..)
+            .find(|i| self.files.get(*i).is_err())
+            .unwrap_or_default();
+        let offset = self.indices.len() as u32 - 1;
+        let num_files = data.files.len();
+        for (name, source) in data.files {
+            self.add_file(name, source);
+        }
+        for (file, start, end) in data.positions {
+            let file = match file {
+                0 => 0,
+                f if f <= num_files => file_base + f - 1,
+                f => {
+                    return Err(format!("position refers to unknown file {f}"))
+                }
+            };
+            self.add_pos(FileIdx(file), start, end);
+        }
+        Ok(offset)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+/// Files and positions of a [PositionTable] that can be stored alongside
+/// data that refers to the positions
+pub struct PositionData {
+    /// Name and source of each file
+    files: Vec<(String, String)>,
+    /// File, start, and end of each position
+    positions: Vec<(usize, usize, usize)>,
 }
 
 /// The global position table
@@ -128,7 +206,7 @@ impl GlobalPositionTable {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 /// A position index backed by a global [PositionTable]
 pub struct GPosIdx(pub PosIdx);
 
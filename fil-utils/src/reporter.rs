@@ -1,10 +1,49 @@
-use crate::{Error, GPosIdx, GlobalPositionTable};
+use crate::{Error, GPosIdx, GlobalPositionTable, Lint, LintLevel, Severity};
 use codespan_reporting::term::termcolor::ColorChoice;
 use codespan_reporting::{
     diagnostic::{Diagnostic, Label, LabelStyle},
     term::{self, termcolor::StandardStream},
 };
-use std::collections::BTreeMap;
+use std::{cell::RefCell, collections::BTreeMap, fmt};
+
+thread_local! {
+    /// The first error emitted on this thread since it was last taken
+    static FIRST_ERROR: RefCell<Option<Reported>> = const { RefCell::new(None) };
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// Code and message of an error that has been emitted
+pub struct Reported {
+    pub code: Option<String>,
+    pub message: String,
+}
+
+impl Reported {
+    /// Remember the error if it is the first one emitted on this thread.
+    /// Used by code that emits errors without a [Diagnostics] instance.
+    pub fn record(code: Option<&str>, message: &str) {
+        FIRST_ERROR.with(|f| {
+            f.borrow_mut().get_or_insert_with(|| Reported {
+                code: code.map(str::to_string),
+                message: message.to_string(),
+            });
+        })
+    }
+
+    /// Return and forget the first error emitted on this thread
+    pub fn take_first() -> Option<Reported> {
+        FIRST_ERROR.with(|f| f.borrow_mut().take())
+    }
+}
+
+impl fmt::Display for Reported {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "error[{code}]: {}", self.message),
+            None => write!(f, "error: {}", self.message),
+        }
+    }
+}
 
 #[derive(PartialOrd, Ord, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 /// Index for information associated with a [Diagnostic] instance.
@@ -31,7 +70,7 @@ impl Information {
 pub struct Diagnostics {
     /// Information being tracked by this instance.
     infos: Vec<Information>,
-    /// Errors that have been reported.
+    /// Diagnostics that have been reported.
     errors: Vec<Error>,
 }
 
@@ -70,6 +109,7 @@ impl Diagnostics {
     }
 
     /// Add an error to the diagnostics instance.
+    /// The diagnostic is reported with the severity attached to it.
     // XXX: Make this add a new information object so that its easy to express
     // the "create error and add info" pattern.
     pub fn add_error(&mut self, error: Error) {
@@ -79,24 +119,58 @@ impl Diagnostics {
         }
     }
 
-    /// Report all errors and return the number of errors.
+    /// Add a warning to the diagnostics instance.
+    /// Warnings are reported alongside errors but do not count towards the
+    /// number of errors.
+    pub fn add_warning(&mut self, warning: Error) {
+        self.add_error(warning.with_severity(Severity::Warning))
+    }
+
+    /// Add a violation of `lint` reported at the given level.
+    pub fn add_lint(&mut self, lint: &Lint, level: LintLevel, error: Error) {
+        let error = error.with_code(lint.code);
+        match level {
+            LintLevel::Allow => (),
+            LintLevel::Warn => self.add_warning(error),
+            LintLevel::Deny => self.add_error(error),
+        }
+    }
+
+    /// The first diagnostic with error severity that has been added
+    pub fn first_error(&self) -> Option<&Error> {
+        self.errors.iter().find(|e| e.severity == Severity::Error)
+    }
+
+    /// Report all diagnostics and return the number of errors.
     /// Returns None if there are no errors.
     pub fn report_all(&mut self) -> Option<u64> {
+        let (errors, others): (Vec<_>, Vec<_>) = self
+            .errors
+            .drain(..)
+            .partition(|e| e.severity == Severity::Error);
+
+        // Notes and warnings are reported first and do not count as errors
+        self.emit(others);
+        if errors.is_empty() {
+            return None;
+        }
+        Some(self.emit(errors))
+    }
+
+    /// Emit the given diagnostics and return the number of diagnostics emitted.
+    fn emit(&self, errors: Vec<Error>) -> u64 {
         let is_tty = atty::is(atty::Stream::Stderr);
         let writer = StandardStream::stderr(if is_tty {
             ColorChoice::Always
         } else {
             ColorChoice::Never
         });
-        if self.errors.is_empty() {
-            return None;
-        }
 
         let mut total = 0;
 
         // Deduplicate errors based on the location attached to the error
         let mut error_map = BTreeMap::new();
-        for mut error in self.errors.drain(..) {
+        for mut error in errors {
             if !error.notes.is_empty() {
                 // Sort everything except the first element
                 let first = error.notes.remove(0);
@@ -105,13 +179,13 @@ impl Diagnostics {
             }
 
             error_map
-                .entry(error.notes)
+                .entry((error.severity, error.notes))
                 .or_insert_with(Vec::new)
-                .push(error.kind);
+                .push((error.kind, error.code));
         }
 
         let table = GlobalPositionTable::as_ref();
-        for (all_notes, errors) in error_map {
+        for ((severity, all_notes), errors) in error_map {
             let mut labels = vec![];
             let mut notes = vec![];
             for (idx, info) in all_notes.iter().enumerate() {
@@ -133,11 +207,34 @@ impl Diagnostics {
                 }
             }
 
-            let msg = if errors.len() > 1 {
-                notes.extend(errors.iter().map(|e| e.to_string()));
-                "Multiple errors encountered".to_string()
+            let mut diag = match severity {
+                Severity::Error => Diagnostic::error(),
+                Severity::Warning => Diagnostic::warning(),
+                Severity::Note => Diagnostic::note(),
+            };
+            if errors.len() > 1 {
+                if severity == Severity::Error {
+                    let (msg, code) = &errors[0];
+                    Reported::record(*code, &msg.to_string());
+                }
+                notes.extend(errors.iter().map(|(e, _)| e.to_string()));
+                diag = diag.with_message(format!(
+                    "Multiple {}s encountered",
+                    match severity {
+                        Severity::Error => "error",
+                        Severity::Warning => "warning",
+                        Severity::Note => "note",
+                    }
+                ));
             } else {
-                errors[0].to_string()
+                let (msg, code) = &errors[0];
+                if severity == Severity::Error {
+                    Reported::record(*code, &msg.to_string());
+                }
+                diag = diag.with_message(msg);
+                if let Some(code) = code {
+                    diag = diag.with_code(*code);
+                }
             };
 
             total += 1;
@@ -145,15 +242,12 @@ impl Diagnostics {
                 &mut writer.lock(),
                 &term::Config::default(),
                 table.files(),
-                &Diagnostic::error()
-                    .with_message(msg)
-                    .with_labels(labels)
-                    .with_notes(notes),
+                &diag.with_labels(labels).with_notes(notes),
             )
             .unwrap();
         }
 
-        Some(total)
+        total
     }
 }
 
@@ -1,32 +1,12 @@
+use crate::options::Options;
 use argh::FromArgs;
+use fil_utils::{Lint, LintConfig};
+use itertools::Itertools;
 use std::{path::PathBuf, str::FromStr};
 
-#[derive(Debug, Default, Clone, Copy)]
-/// Solver to use in the pass
-pub enum Solver {
-    #[default]
-    CVC5,
-    Z3,
-    Boolector,
-    Bitwuzla,
-}
-
-impl FromStr for Solver {
-    type Err = String;
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "z3" => Ok(Solver::Z3),
-            "cvc5" => Ok(Solver::CVC5),
-            "boolector" => Ok(Solver::Boolector),
-            "bitwuzla" => Ok(Solver::Bitwuzla),
-            _ => {
-                Err(format!("unknown solver: {s}. Known solvers are: z3, cvc5, boolector, bitwuzla"))
-            }
-        }
-    }
-}
+pub use crate::options::Solver;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub enum Backend {
     #[default]
     Verilog,
@@ -46,17 +26,79 @@ impl FromStr for Backend {
     }
 }
 
-#[derive(FromArgs, Debug)]
+/// Parse the name of a lint code or group
+fn lint_name(s: &str) -> Result<String, String> {
+    if Lint::is_known(s) {
+        Ok(s.to_string())
+    } else {
+        Err(format!(
+            "unknown lint: {s}. Known lints are: {}",
+            Lint::ALL
+                .iter()
+                .map(|l| format!("{} ({})", l.code, l.group))
+                .join(", ")
+        ))
+    }
+}
+
+/// Parse the value of a parameter given as `NAME=VALUE`
+fn param_value(s: &str) -> Result<(String, u64), String> {
+    let Some((name, val)) = s.split_once('=') else {
+        return Err(format!("expected NAME=VALUE for parameter, found: {s}"));
+    };
+    let val = val.parse::<u64>().map_err(|e| {
+        format!("invalid value for parameter `{name}': {val} ({e})")
+    })?;
+    Ok((name.trim().to_string(), val))
+}
+
+/// Parse the file the IR is written to, optionally preceded by the pass
+/// after which it is written
+fn emit_ir(s: &str) -> Result<(Option<String>, PathBuf), String> {
+    match s.split_once('=') {
+        Some((pass, path)) => {
+            Ok((Some(pass.trim().to_string()), PathBuf::from(path)))
+        }
+        None => Ok((None, PathBuf::from(s))),
+    }
+}
+
+/// Parse a comma-separated list of pass names
+fn pass_list(s: &str) -> Result<Vec<String>, String> {
+    Ok(s.split(',').map(|p| p.trim().to_string()).collect())
+}
+
+#[derive(FromArgs, Debug, Clone)]
 /// The Filament pipeline verifier
 pub struct Opts {
     /// path to the primitives library
     #[argh(positional)]
-    pub input: PathBuf,
+    pub input: Option<PathBuf>,
+
+    #[argh(subcommand)]
+    pub command: Option<Command>,
+
+    /// print a detailed explanation of the error or lint code and exit
+    #[argh(option, long = "explain")]
+    pub explain: Option<String>,
 
     /// print out the IR
     #[argh(option, long = "dump-after")]
     pub dump_after: Vec<String>,
 
+    /// comma-separated list of passes to run instead of the default pipeline
+    #[argh(option, long = "passes", from_str_fn(pass_list))]
+    pub passes: Option<Vec<String>>,
+
+    /// remove the pass from the pipeline
+    #[argh(option, long = "disable-pass")]
+    pub disable_pass: Vec<String>,
+
+    /// print the known passes and the pipeline selected by the other options
+    /// and exit
+    #[argh(switch, long = "print-passes")]
+    pub print_passes: bool,
+
     /// print out assignments that falsify the constraints
     #[argh(switch, long = "show-models")]
     pub show_models: bool,
@@ -65,6 +107,12 @@ pub struct Opts {
     #[argh(option, long = "library", short = 'l', default = "\".\".into()")]
     pub library: PathBuf,
 
+    /// precompiled library written by the `precompile` command. Unchanged
+    /// files of the library are neither parsed nor verified again and the
+    /// specializations it contains are not monomorphized again
+    #[argh(option, long = "precompiled")]
+    pub precompiled: Vec<PathBuf>,
+
     /// only check the program without compilation.
     #[argh(switch, short = 'c', long = "check")]
     pub check: bool,
@@ -77,9 +125,20 @@ pub struct Opts {
     #[argh(option, long = "log", default = "log::LevelFilter::Warn")]
     pub log_level: log::LevelFilter,
 
-    /// set toplevel
-    #[argh(option, long = "toplevel", default = "\"main\".into()")]
-    pub toplevel: String,
+    /// set toplevel. Can be given several times to compile several toplevel
+    /// components (default: main)
+    #[argh(option, long = "toplevel")]
+    pub toplevel: Vec<String>,
+
+    /// compile every component without signature parameters as a toplevel
+    /// component
+    #[argh(switch, long = "all-concrete")]
+    pub all_concrete: bool,
+
+    /// bind a signature parameter of the toplevel components as NAME=VALUE.
+    /// Parameters without a value use their default value
+    #[argh(option, short = 'P', long = "param", from_str_fn(param_value))]
+    pub params: Vec<(String, u64)>,
 
     /// skip the discharge pass (unsafe)
     #[argh(switch, long = "unsafe-skip-discharge")]
@@ -97,17 +156,232 @@ pub struct Opts {
     #[argh(switch, long = "preserve-names")]
     pub preserve_names: bool,
 
+    /// write a JSON file mapping the name of each generated module to the
+    /// source components and parameter values it implements
+    #[argh(option, long = "name-map")]
+    pub name_map: Option<PathBuf>,
+
+    /// write the IR of the program to a file after a pass (PASS=FILE) or
+    /// after the last pass (FILE). Compilation can be resumed from the file
+    /// with --from-ir
+    #[argh(option, long = "emit-ir", from_str_fn(emit_ir))]
+    pub emit_ir: Option<(Option<String>, PathBuf)>,
+
+    /// run the passes following the one after which the IR was written by
+    /// --emit-ir and compile the result instead of an input program
+    #[argh(option, long = "from-ir")]
+    pub from_ir: Option<PathBuf>,
+
+    /// share instances of external components whose invocations never overlap
+    #[argh(switch, long = "share-instances")]
+    pub share_instances: bool,
+
+    /// keep structurally identical specializations of a component as separate
+    /// modules
+    #[argh(switch, long = "no-dedup")]
+    pub no_dedup: bool,
+
+    /// do not report the lint code or group
+    #[argh(option, short = 'A', long = "allow", from_str_fn(lint_name))]
+    pub allow: Vec<String>,
+
+    /// report the lint code or group as a warning
+    #[argh(option, short = 'W', long = "warn", from_str_fn(lint_name))]
+    pub warn: Vec<String>,
+
+    /// report the lint code or group as an error
+    #[argh(option, short = 'D', long = "deny", from_str_fn(lint_name))]
+    pub deny: Vec<String>,
+
     // Solver specific configuration
-    /// solver to use (default: cvc5): cvc5, z3
+    /// external solver used for constraints the built-in solver cannot
+    /// decide (default: z3): z3, cvc5, boolector, bitwuzla, or builtin to
+    /// only use the built-in solver
     #[argh(option, long = "solver", default = "Solver::Z3")]
     pub solver: Solver,
+    /// timeout in milliseconds for each query to an external solver
+    #[argh(option, long = "solver-timeout")]
+    pub solver_timeout: Option<u64>,
+    /// external solver to run in parallel with the other `--portfolio`
+    /// solvers instead of `--solver` on constraints the built-in solver
+    /// cannot decide. The first definitive answer is used.
+    #[argh(option, long = "portfolio")]
+    pub portfolio: Vec<Solver>,
     /// solve assertions separately rather than all at once
     #[argh(switch, long = "discharge-separate")]
     pub discharge_separate: bool,
     /// dump interactions with the solver in the given file
     #[argh(option, long = "dump-solver-log")]
     pub solver_replay_file: Option<String>,
-    /// use bitvector encoding for proofs
+    /// use bitvector encoding for proofs with at most the given width
+    /// (default: 64 for boolector and bitwuzla)
     #[argh(option, long = "solver-bv")]
     pub solver_bv: Option<u8>,
+    /// emit a proof certificate for each verified component into the
+    /// directory
+    #[argh(option, long = "emit-certs")]
+    pub emit_certs: Option<PathBuf>,
+    /// write a design instantiating the toplevel component with the values
+    /// of each counterexample to its constraints and a stimulus file for it
+    /// into the directory
+    #[argh(option, long = "emit-counterexample")]
+    pub emit_counterexample: Option<PathBuf>,
+    /// record the reason, size, solve time, and outcome of each proof
+    /// obligation in the given JSON file and summarize the slowest ones.
+    /// Obligations are checked separately when profiling.
+    #[argh(option, long = "profile-discharge")]
+    pub profile_discharge: Option<PathBuf>,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand)]
+/// Commands that do not compile a program
+pub enum Command {
+    VerifyCert(VerifyCert),
+    Equiv(Equiv),
+    Replay(Replay),
+    Sweep(Sweep),
+    Precompile(Precompile),
+    RunPass(RunPass),
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "verify-cert")]
+/// Check proof certificates produced by --emit-certs
+pub struct VerifyCert {
+    /// certificates or directories containing certificates
+    #[argh(positional)]
+    pub certs: Vec<PathBuf>,
+    /// the program the certificates were produced from
+    #[argh(option, long = "source")]
+    pub source: PathBuf,
+    /// path to search for imports
+    #[argh(option, long = "library", short = 'l', default = "\".\".into()")]
+    pub library: PathBuf,
+    /// bitvector width the certificates must have been produced with
+    /// (default: integer encoding)
+    #[argh(option, long = "solver-bv")]
+    pub solver_bv: Option<u8>,
+    /// solver used to check the certificates (default: the solver that
+    /// produced each certificate)
+    #[argh(option, long = "solver")]
+    pub solver: Option<Solver>,
+    /// timeout in milliseconds for checking each certificate
+    #[argh(option, long = "solver-timeout")]
+    pub solver_timeout: Option<u64>,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "equiv")]
+/// Check that two components of the input program produce the same outputs
+/// for all inputs
+pub struct Equiv {
+    /// the original component
+    #[argh(positional)]
+    pub left: String,
+    /// the component that should behave like the original one
+    #[argh(positional)]
+    pub right: String,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "replay")]
+/// Re-run a solver log written by --dump-solver-log and report the
+/// constraints that do not hold
+pub struct Replay {
+    /// the solver log
+    #[argh(positional)]
+    pub log: PathBuf,
+    /// solver used to replay the log (default: the solver that wrote it)
+    #[argh(option, long = "solver")]
+    pub solver: Option<Solver>,
+    /// timeout in milliseconds for each query
+    #[argh(option, long = "solver-timeout")]
+    pub solver_timeout: Option<u64>,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "sweep")]
+/// Check and compile a component for every configuration of parameter
+/// values in the sweep file and write a CSV of the resulting designs
+pub struct Sweep {
+    /// TOML file describing the component and parameter values to sweep
+    #[argh(positional)]
+    pub config: PathBuf,
+    /// write the CSV to the file instead of stdout
+    #[argh(option, short = 'o', long = "output")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "precompile")]
+/// Verify the input program and its imports and write a library with their
+/// signatures and the specializations of the --toplevel components (or of
+/// every concrete component) that can be used with --precompiled
+pub struct Precompile {
+    /// file to write the library to
+    #[argh(option, short = 'o', long = "output")]
+    pub output: PathBuf,
+}
+
+#[derive(FromArgs, Debug, Clone)]
+#[argh(subcommand, name = "run-pass")]
+/// Parse the input file as the IR printed by --dump-after, run a single pass
+/// on it, and print the resulting IR
+pub struct RunPass {
+    /// name of the pass to run
+    #[argh(positional)]
+    pub pass: String,
+}
+
+impl Opts {
+    /// Names of the toplevel components given on the command line
+    pub fn toplevels(&self) -> Vec<String> {
+        if self.toplevel.is_empty() {
+            vec!["main".to_string()]
+        } else {
+            self.toplevel.clone()
+        }
+    }
+
+    /// The lint levels configured on the command line
+    pub fn lints(&self) -> LintConfig {
+        LintConfig {
+            allow: self.allow.clone(),
+            warn: self.warn.clone(),
+            deny: self.deny.clone(),
+        }
+    }
+}
+
+impl From<&Opts> for Options {
+    fn from(opts: &Opts) -> Self {
+        Options {
+            input: opts.input.clone(),
+            library: opts.library.clone(),
+            precompiled: opts.precompiled.clone(),
+            dump_after: opts.dump_after.clone(),
+            emit_ir: opts.emit_ir.clone(),
+            show_models: opts.show_models,
+            check: opts.check,
+            params: opts.params.clone(),
+            lints: opts.lints(),
+            passes: opts.passes.clone(),
+            disable_pass: opts.disable_pass.clone(),
+            unsafe_skip_discharge: opts.unsafe_skip_discharge,
+            share_instances: opts.share_instances,
+            no_dedup: opts.no_dedup,
+            disable_slow_fsms: opts.disable_slow_fsms,
+            preserve_names: opts.preserve_names,
+            solver: opts.solver,
+            solver_timeout: opts.solver_timeout,
+            portfolio: opts.portfolio.clone(),
+            discharge_separate: opts.discharge_separate,
+            solver_replay_file: opts.solver_replay_file.clone(),
+            solver_bv: opts.solver_bv,
+            emit_certs: opts.emit_certs.clone(),
+            emit_counterexample: opts.emit_counterexample.clone(),
+            profile_discharge: opts.profile_discharge.clone(),
+        }
+    }
 }
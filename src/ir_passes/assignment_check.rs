@@ -1,9 +1,9 @@
 use crate::{
-    cmdline,
     ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
 };
 use fil_ir::{self as ir, Connect, Context, Ctx, DisplayCtx, PortIdx};
-use fil_utils::{self as utils, Diagnostics, Error, GPosIdx};
+use fil_utils::{self as utils, codes, Diagnostics, Error, GPosIdx};
 use itertools::Itertools;
 use linked_hash_map::LinkedHashMap;
 
@@ -15,7 +15,7 @@ pub struct AssignCheck {
 }
 
 impl Construct for AssignCheck {
-    fn from(_: &cmdline::Opts, _: &mut Context) -> Self {
+    fn from(_: &options::Options, _: &mut Context) -> Self {
         Self {
             ports: LinkedHashMap::new(),
             diag: Diagnostics::default(),
@@ -111,7 +111,8 @@ impl Visitor for AssignCheck {
                 "port `{}{{{}}}' is assigned to {con_len} times",
                 data.comp.display(port),
                 idx,
-            ));
+            ))
+            .with_code(codes::MULTIPLE_ASSIGN);
 
             // Add all assignments with location information
             let err = connects.into_iter().flatten().fold(err, |err, pos| {
@@ -126,7 +127,8 @@ impl Visitor for AssignCheck {
                 "bundle `{}' has {} unassigned locations",
                 data.comp.display(port),
                 idxs.len(),
-            ));
+            ))
+            .with_code(codes::UNASSIGNED);
 
             let p = data.comp.get(port);
             let info = data.comp.get(p.info).as_port();
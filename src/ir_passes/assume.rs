@@ -1,6 +1,7 @@
 use crate::ir_visitor::{Action, Visitor, VisitorData};
 use fil_ast as ast;
 use fil_ir::{self as ir, AddCtx, Ctx, ExprIdx, PropIdx};
+use itertools::Itertools;
 
 /// Generates default assumptions to the Filament program for assumptions using custom functions
 #[derive(Default)]
@@ -54,12 +55,88 @@ impl Assume {
             ast::Fn::SinB | ast::Fn::CosB | ast::Fn::BitRev => vec![], // can't make any assumptions on the output value here
         }
     }
+
+    /// Axioms about the application `e = op(args)` of a function with
+    /// multiple arguments. These hold for all values of the arguments.
+    fn fn_axioms(
+        ctx: &mut ir::Component,
+        op: ast::Fn,
+        e: ExprIdx,
+        args: &[ExprIdx],
+    ) -> Vec<PropIdx> {
+        let zero = ctx.add(ir::Expr::Concrete(0));
+        match (op, args) {
+            (ast::Fn::BitRev, &[x, n]) => {
+                let max = n.pow2(ctx);
+                vec![
+                    // 0 <= bit_rev(x, n) < pow2(n)
+                    e.gte(zero, ctx),
+                    e.lt(max, ctx),
+                    // x < pow2(n) => bit_rev(bit_rev(x, n), n) = x
+                    x.lt(max, ctx)
+                        .implies(e.bit_rev(n, ctx).equal(x, ctx), ctx),
+                ]
+            }
+            // The bits of a 32-bit floating point number
+            (ast::Fn::SinB | ast::Fn::CosB, _) => {
+                let max = ctx.add(ir::Expr::Concrete(1 << 32));
+                vec![e.gte(zero, ctx), e.lt(max, ctx)]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Applications of functions with multiple arguments in the proposition
+    fn fn_apps(
+        p: PropIdx,
+        comp: &ir::Component,
+        acc: &mut Vec<(ast::Fn, ExprIdx, Vec<ExprIdx>)>,
+    ) {
+        let mut exprs = vec![];
+        match comp.get(p) {
+            ir::Prop::True | ir::Prop::False => (),
+            ir::Prop::Cmp(ir::CmpOp { lhs, rhs, .. }) => {
+                exprs.extend([*lhs, *rhs])
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { lhs, rhs, .. }) => {
+                exprs.extend([comp.get(*lhs).offset, comp.get(*rhs).offset])
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { lhs, rhs, .. }) => {
+                for ts in [lhs, rhs] {
+                    match ts {
+                        ir::TimeSub::Unit(e) => exprs.push(*e),
+                        ir::TimeSub::Sym { l, r } => exprs
+                            .extend([comp.get(*l).offset, comp.get(*r).offset]),
+                    }
+                }
+            }
+            ir::Prop::Not(p) => Self::fn_apps(*p, comp, acc),
+            ir::Prop::And(l, r)
+            | ir::Prop::Or(l, r)
+            | ir::Prop::Implies(l, r) => {
+                Self::fn_apps(*l, comp, acc);
+                Self::fn_apps(*r, comp, acc);
+            }
+        }
+        while let Some(e) = exprs.pop() {
+            match comp.get(e) {
+                ir::Expr::Param(_) | ir::Expr::Concrete(_) => (),
+                ir::Expr::Bin { lhs, rhs, .. } => exprs.extend([*lhs, *rhs]),
+                ir::Expr::Fn { op, args } => {
+                    exprs.extend(args.iter().copied());
+                    if args.len() > 1 {
+                        acc.push((*op, e, args.clone()));
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Assume {
     /// Checks a proposition for whether it matches the form `#l = f(#r)` for some custom function `f`. Additionally recurses on `&` chains.
     /// Generates the assumptions associated with each [ast::Fn] and returns a list of [ir::Prop]s for each.
-    /// TODO: Implement assumption generation for functions taking more than one argument.
+    /// Functions with multiple arguments are handled by [Self::fn_axioms].
     fn prop(p: ir::PropIdx, comp: &mut ir::Component) -> Vec<PropIdx> {
         let p = comp.get(p);
         match p {
@@ -70,28 +147,17 @@ impl Assume {
             }) => {
                 // Matches over the cases `op(args) = rhs` and `lhs = op(args)` to
                 // define the `op`, `left`, and `right` for the equivalent equation `left = op(right)`
-                if let Some((op, lhs, rhs)) = match (
-                    comp.get(*lhs),
-                    comp.get(*rhs),
-                ) {
-                    (ir::Expr::Fn { op, args }, _) => {
-                        assert!(
-                            args.len() == 1,
-                            "Currently Unimplemented: {} requires {} arguments, automatic assumptions only implemented for single argument functions.",
-                            op, args.len()
-                        );
-                        Some((*op, *rhs, args[0]))
+                if let Some((op, lhs, rhs)) =
+                    match (comp.get(*lhs), comp.get(*rhs)) {
+                        (ir::Expr::Fn { op, args }, _) if args.len() == 1 => {
+                            Some((*op, *rhs, args[0]))
+                        }
+                        (_, ir::Expr::Fn { op, args }) if args.len() == 1 => {
+                            Some((*op, *lhs, args[0]))
+                        }
+                        _ => None,
                     }
-                    (_, ir::Expr::Fn { op, args }) => {
-                        assert!(
-                            args.len() == 1,
-                            "Currently Unimplemented: {} requires {} arguments, automatic assumptions only implemented for single argument functions.",
-                            op, args.len()
-                        );
-                        Some((*op, *lhs, args[0]))
-                    }
-                    _ => None,
-                } {
+                {
                     log::debug!("Generating default assumptions for {p}");
                     Self::add_assumptions(comp, op, lhs, rhs)
                 } else {
@@ -117,15 +183,26 @@ impl Visitor for Assume {
     }
 
     fn fact(&mut self, f: &mut ir::Fact, data: &mut VisitorData) -> Action {
-        if f.is_assume() {
-            Action::AddBefore(
-                Assume::prop(f.prop, &mut data.comp)
-                    .into_iter()
-                    .filter_map(|prop| data.comp.assume(prop, f.reason))
-                    .collect(),
-            )
+        let comp = &mut data.comp;
+        let mut props = if f.is_assume() {
+            Assume::prop(f.prop, comp)
         } else {
-            Action::Continue
+            vec![]
+        };
+        let mut apps = vec![];
+        Assume::fn_apps(f.prop, comp, &mut apps);
+        for (op, e, args) in apps {
+            props.extend(Assume::fn_axioms(comp, op, e, &args));
+        }
+        if props.is_empty() {
+            return Action::Continue;
         }
+        Action::AddBefore(
+            props
+                .into_iter()
+                .unique()
+                .filter_map(|prop| comp.assume(prop, f.reason))
+                .collect(),
+        )
     }
 }
@@ -0,0 +1,239 @@
+//! Equivalence checking of two components.
+//!
+//! Both components are unrolled for a single transaction starting in cycle 0
+//! and read the same values from inputs with the same name. The components are
+//! equivalent if every output carries the same value in both of them during
+//! every cycle of its availability interval.
+use super::unroll::{port_names, Unroll};
+use super::{external_solver, start_solver};
+use crate::options;
+use easy_smt::{self as smt, SExpr};
+use fil_ir::{self as ir, Ctx};
+use fil_utils::{codes, Diagnostics, Error};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Width and availability interval of a port in cycles
+type PortSig = (u64, u64, u64);
+
+/// The inputs and outputs of an unrolled component by name
+struct Interface {
+    inputs: BTreeMap<String, (ir::PortIdx, PortSig)>,
+    outputs: BTreeMap<String, (ir::PortIdx, PortSig)>,
+}
+
+impl Interface {
+    fn new(un: &Unroll, f: usize, comp: &ir::Component) -> Self {
+        let mut names = port_names(comp);
+        let mut sig = |p: ir::PortIdx| {
+            let range = &comp.get(p).live.range;
+            let sig = (
+                un.width(f, p),
+                un.cycle(f, range.start),
+                un.cycle(f, range.end),
+            );
+            (names.remove(&p).unwrap(), (p, sig))
+        };
+        let inputs = comp.inputs().map(|(p, _)| sig(p)).collect();
+        let outputs = comp.outputs().map(|(p, _)| sig(p)).collect();
+        Self { inputs, outputs }
+    }
+
+    /// Differences between the ports of the interfaces
+    fn diff(
+        kind: &str,
+        left: &BTreeMap<String, (ir::PortIdx, PortSig)>,
+        right: &BTreeMap<String, (ir::PortIdx, PortSig)>,
+        names: (&str, &str),
+    ) -> Vec<String> {
+        let show = |(w, s, e): &PortSig| format!("[{s}, {e}) {w}");
+        let mut diffs = vec![];
+        for (name, (_, l)) in left {
+            match right.get(name) {
+                None => diffs.push(format!(
+                    "{kind} `{name}' of `{}' does not exist in `{}'",
+                    names.0, names.1
+                )),
+                Some((_, r)) if l != r => diffs.push(format!(
+                    "{kind} `{name}' is `{}' in `{}' but `{}' in `{}'",
+                    show(l),
+                    names.0,
+                    show(r),
+                    names.1
+                )),
+                Some(_) => (),
+            }
+        }
+        for name in right.keys().filter(|n| !left.contains_key(*n)) {
+            diffs.push(format!(
+                "{kind} `{name}' of `{}' does not exist in `{}'",
+                names.1, names.0
+            ));
+        }
+        diffs
+    }
+}
+
+/// Result of comparing the outputs of the components
+enum Outcome {
+    Equivalent,
+    /// The outputs differ. Contains notes describing the difference.
+    Different(Vec<String>),
+    Unknown(Vec<String>),
+}
+
+/// Values of an output in both components during a cycle
+struct Sample {
+    name: String,
+    cycle: u64,
+    left: SExpr,
+    right: SExpr,
+}
+
+/// Check that the entrypoints of the two contexts are equivalent. The
+/// contexts must contain lowered programs. Returns the number of errors.
+pub fn check_equiv(
+    opts: &options::Options,
+    (left_name, left): (&str, &ir::Context),
+    (right_name, right): (&str, &ir::Context),
+) -> Result<(), u64> {
+    let mut diag = Diagnostics::default();
+    let names = (left_name, right_name);
+    let fail = |msg: &str, notes: Vec<String>, diag: &mut Diagnostics| {
+        let mut err =
+            Error::misc(msg.to_string()).with_code(codes::NOT_EQUIVALENT);
+        for note in notes {
+            err = err.add_note(diag.add_message(note));
+        }
+        diag.add_error(err);
+        diag.report_all().map_or(Ok(()), Err)
+    };
+    let msg =
+        format!("cannot check equivalence of `{left_name}' and `{right_name}'");
+
+    let comps = [(left_name, left), (right_name, right)]
+        .into_iter()
+        .map(|(name, ctx)| match ctx.entrypoints.first() {
+            Some(idx) => Ok(ctx.get(*idx)),
+            None => Err(format!("there is no component named `{name}'")),
+        })
+        .collect::<Result<Vec<_>, _>>();
+    let comps = match comps {
+        Ok(comps) => comps,
+        Err(e) => return fail(&msg, vec![e], &mut diag),
+    };
+    let mut sol = match start_solver(external_solver(opts), opts.solver_timeout)
+    {
+        Ok(sol) => sol,
+        Err(e) => return fail(&msg, vec![e], &mut diag),
+    };
+
+    let mut un = Unroll::new(&mut sol);
+    let tops = [(left, comps[0]), (right, comps[1])]
+        .into_iter()
+        .zip([left_name, right_name])
+        .map(|((ctx, comp), name)| {
+            un.add_top(ctx, comp).map_err(|e| format!("`{name}': {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>();
+    let (fl, fr) = match tops {
+        Ok(tops) => (tops[0], tops[1]),
+        Err(e) => return fail(&msg, vec![e], &mut diag),
+    };
+
+    let li = Interface::new(&un, fl, comps[0]);
+    let ri = Interface::new(&un, fr, comps[1]);
+    let mut diffs = Interface::diff("input", &li.inputs, &ri.inputs, names);
+    diffs.extend(Interface::diff("output", &li.outputs, &ri.outputs, names));
+    if !diffs.is_empty() {
+        return fail(
+            &format!("components `{left_name}' and `{right_name}' have different interfaces"),
+            diffs,
+            &mut diag,
+        );
+    }
+
+    match compare(&mut un, (fl, fr), &li, &ri, names) {
+        Ok(Outcome::Equivalent) => {
+            println!("Components `{left_name}' and `{right_name}' are equivalent.");
+            Ok(())
+        }
+        Ok(Outcome::Different(notes)) => fail(
+            &format!("components `{left_name}' and `{right_name}' are not equivalent"),
+            notes,
+            &mut diag,
+        ),
+        Ok(Outcome::Unknown(notes)) => fail(&msg, notes, &mut diag),
+        Err(e) => fail(
+            &msg,
+            vec![format!("Failed to communicate with the solver: {e}.")],
+            &mut diag,
+        ),
+    }
+}
+
+/// Check whether the outputs of the unrolled components can differ
+fn compare(
+    un: &mut Unroll,
+    (fl, fr): (usize, usize),
+    li: &Interface,
+    ri: &Interface,
+    names: (&str, &str),
+) -> io::Result<Outcome> {
+    let mut samples = vec![];
+    for (name, (lp, (_, start, end))) in &li.outputs {
+        let (rp, _) = ri.outputs[name];
+        for cycle in *start..*end {
+            samples.push(Sample {
+                name: name.clone(),
+                cycle,
+                left: un.value(fl, *lp, cycle)?,
+                right: un.value(fr, rp, cycle)?,
+            });
+        }
+    }
+    if samples.is_empty() {
+        return Ok(Outcome::Equivalent);
+    }
+    let sol = un.sol();
+    let differ = sol.or_many(
+        samples
+            .iter()
+            .map(|s| sol.not(sol.eq(s.left, s.right)))
+            .collect::<Vec<_>>(),
+    );
+    un.sol_mut().assert(differ)?;
+    let unmodeled = un.unmodeled_notes();
+    match un.sol_mut().check()? {
+        smt::Response::Unsat => Ok(Outcome::Equivalent),
+        smt::Response::Sat => {
+            let terms =
+                samples.iter().flat_map(|s| [s.left, s.right]).collect();
+            let values = un.sol_mut().get_value(terms)?;
+            let mut notes = vec![];
+            let sol = un.sol();
+            for (s, vals) in samples.iter().zip(values.chunks(2)) {
+                let l = Unroll::bv_value(&sol.display(vals[0].1).to_string());
+                let r = Unroll::bv_value(&sol.display(vals[1].1).to_string());
+                if l != r {
+                    notes.push(format!(
+                        "Output `{}' is {l} in `{}' but {r} in `{}' in cycle {}",
+                        s.name, names.0, names.1, s.cycle,
+                    ));
+                }
+            }
+            notes.push(format!("Trace of `{}':\n{}", names.0, un.trace(fl)?));
+            notes.push(format!("Trace of `{}':\n{}", names.1, un.trace(fr)?));
+            notes.extend(unmodeled);
+            Ok(Outcome::Different(notes))
+        }
+        smt::Response::Unknown => {
+            let mut notes = vec![
+                "The solver could not decide whether the outputs can differ."
+                    .to_string(),
+            ];
+            notes.extend(unmodeled);
+            Ok(Outcome::Unknown(notes))
+        }
+    }
+}
@@ -0,0 +1,325 @@
+mod equiv;
+mod models;
+mod unroll;
+
+pub use equiv::check_equiv;
+
+use super::discharge::solver_cmd;
+use crate::ir_visitor::{Action, Construct, Visitor, VisitorData};
+use crate::options;
+use easy_smt::{self as smt, SExpr};
+use fil_ir::{self as ir, Ctx};
+use fil_utils::{codes, Diagnostics, Error, GPosIdx};
+use unroll::Unroll;
+
+/// The external solver used to check the unrolled design: the configured
+/// solver or the first external solver in the portfolio.
+fn external_solver(opts: &options::Options) -> Option<options::Solver> {
+    let external = |s: &options::Solver| !matches!(s, options::Solver::Builtin);
+    Some(opts.solver)
+        .filter(external)
+        .or_else(|| opts.portfolio.iter().copied().find(external))
+}
+
+/// Start the external solver. Returns the reason if it cannot be started.
+fn start_solver(
+    solver: Option<options::Solver>,
+    timeout: Option<u64>,
+) -> Result<smt::Context, String> {
+    let Some(solver) = solver else {
+        return Err("The built-in solver cannot reason about the values of ports. Use an external solver with `--solver` or `--portfolio`.".to_string());
+    };
+    let (name, args) = solver_cmd(solver, timeout);
+    smt::ContextBuilder::new()
+        .solver(name, args)
+        .build()
+        .map_err(|e| {
+            format!("The external solver `{name}' could not be started: {e}.")
+        })
+}
+
+/// Checks assertions on the values of ports using bounded model checking.
+/// Must run after [super::BundleElim] so that every port is a scalar.
+///
+/// Each component is checked on its own by unrolling a single transaction
+/// starting in cycle 0 for as many cycles as the assertions mention. The
+/// inputs of the component are unconstrained in every cycle and the outputs
+/// of primitives are computed from the semantic models in [models]. Invoked
+/// user-level components are inlined with their events scheduled at the
+/// cycles given by the invocation. A violated assertion is reported with a
+/// trace of the values of the ports the assertion depends on.
+pub struct Bmc {
+    /// Solver used to check the assertions
+    solver: Option<options::Solver>,
+    timeout: Option<u64>,
+    /// The running solver and the reason it could not be started
+    sol: Option<smt::Context>,
+    sol_err: Option<String>,
+    /// Assertions in the current component
+    asserts: Vec<ir::ValueAssert>,
+    diag: Diagnostics,
+}
+
+impl Construct for Bmc {
+    fn from(opts: &options::Options, _: &mut ir::Context) -> Self {
+        Self {
+            solver: external_solver(opts),
+            timeout: opts.solver_timeout,
+            sol: None,
+            sol_err: None,
+            asserts: vec![],
+            diag: Diagnostics::default(),
+        }
+    }
+
+    fn clear_data(&mut self) {
+        self.asserts.clear();
+    }
+}
+
+impl Bmc {
+    /// Start the solver if it has not been started yet. Returns the reason
+    /// if the solver is not available.
+    fn start_solver(&mut self) -> Result<(), String> {
+        if self.sol.is_some() {
+            return Ok(());
+        }
+        if let Some(e) = &self.sol_err {
+            return Err(e.clone());
+        }
+        match start_solver(self.solver, self.timeout) {
+            Ok(sol) => {
+                self.sol = Some(sol);
+                Ok(())
+            }
+            Err(e) => {
+                self.sol_err = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    /// Locations of the assertion
+    fn info(a: &ir::ValueAssert, comp: &ir::Component) -> (GPosIdx, GPosIdx) {
+        comp.get(a.info)
+            .as_value_assert()
+            .map(|i| (i.cons_loc, i.range_loc))
+            .unwrap_or((GPosIdx::UNKNOWN, GPosIdx::UNKNOWN))
+    }
+
+    /// Add an error about the assertion with the notes
+    fn error(
+        &mut self,
+        msg: String,
+        label: &str,
+        a: &ir::ValueAssert,
+        comp: &ir::Component,
+        notes: Vec<String>,
+    ) {
+        let (cons_loc, range_loc) = Self::info(a, comp);
+        let mut err = Error::misc(msg).with_code(codes::VALUE_ASSERT);
+        if cons_loc != GPosIdx::UNKNOWN {
+            err = err.add_note(self.diag.add_info(label, cons_loc));
+        }
+        if range_loc != GPosIdx::UNKNOWN {
+            err = err.add_note(
+                self.diag.add_info("during this interval", range_loc),
+            );
+        }
+        for note in notes {
+            err = err.add_note(self.diag.add_message(note));
+        }
+        self.diag.add_error(err);
+    }
+
+    /// Width of the term. Constants use as many bits as their value needs.
+    fn width(un: &Unroll, t: &ir::ValueTerm, comp: &ir::Component) -> u64 {
+        match t {
+            ir::ValueTerm::Port(a) => un.width(0, a.port),
+            ir::ValueTerm::Expr(e) => {
+                let n = Unroll::eval(comp, *e);
+                (64 - n.leading_zeros()).max(1) as u64
+            }
+        }
+    }
+
+    /// Value of the term during the cycle extended to the given width
+    fn term(
+        un: &mut Unroll,
+        t: &ir::ValueTerm,
+        cycle: u64,
+        width: u64,
+        comp: &ir::Component,
+    ) -> std::io::Result<SExpr> {
+        Ok(match t {
+            ir::ValueTerm::Port(a) => {
+                let ext = width - un.width(0, a.port);
+                let v = un.value(0, a.port, cycle)?;
+                let sol = un.sol();
+                if ext == 0 {
+                    v
+                } else {
+                    let f = sol.list(vec![
+                        sol.atom("_"),
+                        sol.atom("zero_extend"),
+                        sol.numeral(ext),
+                    ]);
+                    sol.list(vec![f, v])
+                }
+            }
+            ir::ValueTerm::Expr(e) => {
+                let sol = un.sol();
+                sol.list(vec![
+                    sol.atom("_"),
+                    sol.atom(format!("bv{}", Unroll::eval(comp, *e))),
+                    sol.numeral(width),
+                ])
+            }
+        })
+    }
+
+    /// The condition that the assertion holds in every cycle of its interval
+    fn holds(
+        un: &mut Unroll,
+        a: &ir::ValueAssert,
+        comp: &ir::Component,
+    ) -> std::io::Result<SExpr> {
+        let ir::CmpOp { op, lhs, rhs } = &a.cons;
+        let start = un.cycle(0, a.range.start);
+        let end = un.cycle(0, a.range.end);
+        let width = Self::width(un, lhs, comp).max(Self::width(un, rhs, comp));
+        let mut conds = vec![];
+        for c in start..end {
+            let l = Self::term(un, lhs, c, width, comp)?;
+            let r = Self::term(un, rhs, c, width, comp)?;
+            let sol = un.sol();
+            conds.push(match op {
+                ir::Cmp::Gt => sol.bvugt(l, r),
+                ir::Cmp::Gte => sol.bvuge(l, r),
+                ir::Cmp::Eq => sol.eq(l, r),
+            });
+        }
+        let sol = un.sol();
+        Ok(if conds.is_empty() {
+            sol.true_()
+        } else {
+            sol.and_many(conds)
+        })
+    }
+
+    /// Check the assertions of the component
+    fn check(
+        &mut self,
+        sol: &mut smt::Context,
+        comp: &ir::Component,
+        ctx: &ir::Context,
+    ) -> std::io::Result<()> {
+        let asserts = std::mem::take(&mut self.asserts);
+        for a in &asserts {
+            sol.push()?;
+            let mut un = Unroll::new(sol);
+            let top = match un.add_top(ctx, comp) {
+                Ok(top) => top,
+                Err(e) => {
+                    self.error(
+                        "assertion on port values cannot be checked".into(),
+                        "unchecked assertion",
+                        a,
+                        comp,
+                        vec![e],
+                    );
+                    un.sol_mut().pop()?;
+                    continue;
+                }
+            };
+            let holds = Self::holds(&mut un, a, comp)?;
+            let neg = un.sol().not(holds);
+            un.sol_mut().assert(neg)?;
+            let unmodeled = un.unmodeled_notes();
+            match un.sol_mut().check()? {
+                smt::Response::Unsat => (),
+                smt::Response::Sat => {
+                    let trace = un.trace(top)?;
+                    let mut notes =
+                        vec![format!("Counterexample trace:\n{trace}")];
+                    notes.extend(unmodeled);
+                    self.error(
+                        "assertion on port values is violated".into(),
+                        "assertion does not hold",
+                        a,
+                        comp,
+                        notes,
+                    );
+                }
+                smt::Response::Unknown => {
+                    let mut notes = vec!["The solver could not decide whether the assertion holds.".to_string()];
+                    notes.extend(unmodeled);
+                    self.error(
+                        "assertion on port values could not be checked".into(),
+                        "assertion may not hold",
+                        a,
+                        comp,
+                        notes,
+                    );
+                }
+            }
+            un.sol_mut().pop()?;
+        }
+        Ok(())
+    }
+}
+
+impl Visitor for Bmc {
+    fn name() -> &'static str {
+        "bmc"
+    }
+
+    fn start(&mut self, data: &mut VisitorData) -> Action {
+        // Precompiled components were checked when their library was
+        // precompiled
+        if data.comp.is_ext || data.comp.precompiled {
+            Action::Stop
+        } else {
+            Action::Continue
+        }
+    }
+
+    fn value_assert(
+        &mut self,
+        a: &mut ir::ValueAssert,
+        _: &mut VisitorData,
+    ) -> Action {
+        self.asserts.push(a.clone());
+        Action::Continue
+    }
+
+    fn end(&mut self, data: &mut VisitorData) {
+        if self.asserts.is_empty() || data.comp.is_ext {
+            return;
+        }
+        let comp = &data.comp;
+        if let Err(e) = self.start_solver() {
+            for a in std::mem::take(&mut self.asserts) {
+                self.error(
+                    "assertion on port values cannot be checked".into(),
+                    "unchecked assertion",
+                    &a,
+                    comp,
+                    vec![e.clone()],
+                );
+            }
+            return;
+        }
+        let mut sol = self.sol.take().unwrap();
+        if let Err(e) = self.check(&mut sol, comp, &*data.mut_ctx) {
+            comp.internal_error(format!(
+                "failed to communicate with solver: {e}"
+            ))
+        }
+        self.sol = Some(sol);
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        self.diag.report_all()
+    }
+}
@@ -0,0 +1,211 @@
+//! Semantic models of the external primitives in the standard library.
+//!
+//! A model computes the value of the output of a primitive from the values of
+//! its inputs. Combinational primitives read their inputs in the same cycle as
+//! the output while registered primitives read them once at the start of
+//! their availability. The models follow the Verilog implementations in
+//! `primitives/*.sv`.
+use easy_smt::{self as smt, SExpr};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Values available to a model of a primitive
+pub struct Prim<'a> {
+    pub sol: &'a smt::Context,
+    /// Values of the parameters of the instance
+    pub params: HashMap<String, u64>,
+    /// Values and widths of the inputs in the order given by [Model::inputs]
+    pub inputs: Vec<(SExpr, u64)>,
+    /// Width of the output
+    pub width: u64,
+}
+
+impl Prim<'_> {
+    fn param(&self, name: &str) -> u64 {
+        self.params[name]
+    }
+
+    fn input(&self, i: usize) -> SExpr {
+        self.inputs[i].0
+    }
+
+    fn input_width(&self, i: usize) -> u64 {
+        self.inputs[i].1
+    }
+
+    /// Bitvector constant of the given width
+    pub fn bv(&self, val: u64, width: u64) -> SExpr {
+        let val = if width < 64 {
+            val & ((1 << width) - 1)
+        } else {
+            val
+        };
+        self.sol.list(vec![
+            self.sol.atom("_"),
+            self.sol.atom(format!("bv{val}")),
+            self.sol.numeral(width),
+        ])
+    }
+
+    fn extend(&self, op: &str, e: SExpr, by: u64) -> SExpr {
+        if by == 0 {
+            return e;
+        }
+        let f = self.sol.list(vec![
+            self.sol.atom("_"),
+            self.sol.atom(op),
+            self.sol.numeral(by),
+        ]);
+        self.sol.list(vec![f, e])
+    }
+
+    /// Zero-extend the input to the given width
+    fn zext(&self, i: usize, width: u64) -> SExpr {
+        self.extend("zero_extend", self.input(i), width - self.input_width(i))
+    }
+
+    fn extract(&self, msb: u64, lsb: u64, e: SExpr) -> SExpr {
+        self.sol.extract(msb as i32, lsb as i32, e)
+    }
+
+    /// One-bit value of a boolean
+    fn bit(&self, cond: SExpr) -> SExpr {
+        self.sol.ite(cond, self.bv(1, 1), self.bv(0, 1))
+    }
+
+    /// Binary operation in Verilog's context-determined width, i.e., the
+    /// largest of the widths of the inputs and the output, truncated to the
+    /// width of the output.
+    fn arith(&self, op: fn(&smt::Context, SExpr, SExpr) -> SExpr) -> SExpr {
+        let w = self.input_width(0).max(self.input_width(1)).max(self.width);
+        let e = op(self.sol, self.zext(0, w), self.zext(1, w));
+        if w == self.width {
+            e
+        } else {
+            self.extract(self.width - 1, 0, e)
+        }
+    }
+}
+
+/// Model of the output of a primitive
+pub struct Model {
+    /// The inputs the output is computed from
+    pub inputs: &'static [&'static str],
+    /// The inputs are read at the start of their availability instead of in
+    /// the same cycle as the output.
+    pub registered: bool,
+    pub output: fn(&Prim) -> SExpr,
+}
+
+impl Model {
+    const fn comb(
+        inputs: &'static [&'static str],
+        output: fn(&Prim) -> SExpr,
+    ) -> Self {
+        Self {
+            inputs,
+            registered: false,
+            output,
+        }
+    }
+}
+
+const BIN: &[&str] = &["left", "right"];
+const UN: &[&str] = &["in"];
+
+/// The model of the primitive `name` defined in the external `file`.
+pub fn model(file: &str, name: &str) -> Option<Model> {
+    let file = Path::new(file).file_name()?.to_str()?;
+    Some(match (file, name) {
+        ("comb.sv", "Const") => {
+            Model::comb(&[], |p| p.bv(p.param("VALUE"), p.width))
+        }
+        ("comb.sv", "Add") => {
+            Model::comb(BIN, |p| p.arith(smt::Context::bvadd))
+        }
+        ("comb.sv", "Sub") => {
+            Model::comb(BIN, |p| p.arith(smt::Context::bvsub))
+        }
+        ("comb.sv", "MultComb") => {
+            Model::comb(BIN, |p| p.arith(smt::Context::bvmul))
+        }
+        ("comb.sv", "And") => {
+            Model::comb(BIN, |p| p.sol.bvand(p.input(0), p.input(1)))
+        }
+        ("comb.sv", "Or") => {
+            Model::comb(BIN, |p| p.sol.bvor(p.input(0), p.input(1)))
+        }
+        ("comb.sv", "Xor") => {
+            Model::comb(BIN, |p| p.sol.bvxor(p.input(0), p.input(1)))
+        }
+        ("comb.sv", "Not") => Model::comb(UN, |p| p.sol.bvnot(p.input(0))),
+        ("comb.sv", "Eq") => {
+            Model::comb(BIN, |p| p.bit(p.sol.eq(p.input(0), p.input(1))))
+        }
+        ("comb.sv", "Neq") => Model::comb(BIN, |p| {
+            p.bit(p.sol.not(p.sol.eq(p.input(0), p.input(1))))
+        }),
+        ("comb.sv", "Gt") => {
+            Model::comb(BIN, |p| p.bit(p.sol.bvugt(p.input(0), p.input(1))))
+        }
+        ("comb.sv", "Lt") => {
+            Model::comb(BIN, |p| p.bit(p.sol.bvult(p.input(0), p.input(1))))
+        }
+        ("comb.sv", "Lte") => {
+            Model::comb(BIN, |p| p.bit(p.sol.bvule(p.input(0), p.input(1))))
+        }
+        ("comb.sv", "Gte") => {
+            Model::comb(BIN, |p| p.bit(p.sol.bvuge(p.input(0), p.input(1))))
+        }
+        ("comb.sv", "SignExtend") => Model::comb(UN, |p| {
+            p.extend("sign_extend", p.input(0), p.width - p.input_width(0))
+        }),
+        ("comb.sv", "ZeroExtend") => Model::comb(UN, |p| p.zext(0, p.width)),
+        ("comb.sv", "Extend") => Model::comb(UN, |p| {
+            // Replicate the input and keep the low bits
+            let w = p.input_width(0);
+            let copies = (p.width + w - 1) / w;
+            let e = (1..copies)
+                .fold(p.input(0), |acc, _| p.sol.concat(acc, p.input(0)));
+            p.extract(p.width - 1, 0, e)
+        }),
+        ("comb.sv", "Concat") => {
+            Model::comb(BIN, |p| p.sol.concat(p.input(0), p.input(1)))
+        }
+        ("comb.sv", "Select") => Model::comb(UN, |p| {
+            let pos = p.param("POS");
+            p.extract(pos, pos, p.input(0))
+        }),
+        ("comb.sv", "Slice") => Model::comb(UN, |p| {
+            p.extract(p.param("MSB"), p.param("LSB"), p.input(0))
+        }),
+        ("comb.sv", "ReduceAnd") => Model::comb(UN, |p| {
+            let ones = p.sol.bvnot(p.bv(0, p.input_width(0)));
+            p.bit(p.sol.eq(p.input(0), ones))
+        }),
+        ("comb.sv", "ReduceOr") => Model::comb(UN, |p| {
+            let zero = p.bv(0, p.input_width(0));
+            p.bit(p.sol.not(p.sol.eq(p.input(0), zero)))
+        }),
+        ("comb.sv", "ShiftLeft") => {
+            Model::comb(&["in", "shift"], |p| p.arith(smt::Context::bvshl))
+        }
+        ("comb.sv", "ShiftRight") => {
+            Model::comb(&["in", "shift"], |p| p.arith(smt::Context::bvlshr))
+        }
+        ("comb.sv", "ArithShiftRight") => Model::comb(&["in", "shift"], |p| {
+            p.sol.bvashr(p.input(0), p.input(1))
+        }),
+        ("comb.sv", "Mux") => Model::comb(&["sel", "in0", "in1"], |p| {
+            let sel = p.sol.eq(p.input(0), p.bv(1, 1));
+            p.sol.ite(sel, p.input(1), p.input(2))
+        }),
+        ("signed.sv", "Neg") => Model::comb(UN, |p| p.sol.bvneg(p.input(0))),
+        ("state.sv", "Register" | "Delay") => Model {
+            inputs: UN,
+            registered: true,
+            output: |p| p.input(0),
+        },
+        _ => return None,
+    })
+}
@@ -0,0 +1,435 @@
+use super::models;
+use easy_smt::{self as smt, SExpr};
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use itertools::Itertools;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{io, iter};
+
+/// A component being unrolled. Components under check are top-level frames
+/// and the user-level components they invoke are inlined into new frames.
+struct Frame<'a> {
+    ctx: &'a ir::Context,
+    comp: &'a ir::Component,
+    /// The cycle in which each event of the component is triggered
+    events: HashMap<ir::EventIdx, u64>,
+    /// The frame invoking the component and the ports of the invocation
+    /// bound to each input of the component.
+    parent: Option<(usize, HashMap<ir::PortIdx, ir::PortIdx>)>,
+    /// The port driving each port written by a connection
+    drivers: HashMap<ir::PortIdx, ir::PortIdx>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(
+        ctx: &'a ir::Context,
+        comp: &'a ir::Component,
+        events: HashMap<ir::EventIdx, u64>,
+        parent: Option<(usize, HashMap<ir::PortIdx, ir::PortIdx>)>,
+    ) -> Self {
+        let drivers = comp
+            .cmds
+            .iter()
+            .filter_map(|cmd| match cmd {
+                ir::Command::Connect(ir::Connect { src, dst, .. }) => {
+                    Some((dst.port, src.port))
+                }
+                _ => None,
+            })
+            .collect();
+        Self {
+            ctx,
+            comp,
+            events,
+            parent,
+            drivers,
+        }
+    }
+}
+
+/// Names of the ports of a lowered component. The elements of a bundle share
+/// the name of the bundle and are distinguished by their index.
+pub fn port_names(comp: &ir::Component) -> HashMap<ir::PortIdx, String> {
+    let mut names: HashMap<String, Vec<ir::PortIdx>> = HashMap::new();
+    for (p, _) in comp.ports().iter() {
+        names.entry(comp.display(p)).or_default().push(p);
+    }
+    names
+        .into_iter()
+        .flat_map(|(name, ports)| {
+            let bundle = ports.len() > 1;
+            ports.into_iter().enumerate().map(move |(i, p)| {
+                (
+                    p,
+                    if bundle {
+                        format!("{name}{{{i}}}")
+                    } else {
+                        name.clone()
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// Unrolling of a single transaction of components into bitvector terms.
+/// Terms for the value of a port in a cycle are built on demand.
+pub struct Unroll<'a> {
+    sol: &'a mut smt::Context,
+    frames: Vec<Frame<'a>>,
+    /// The frame inlining each invocation of a user-level component
+    inlined: HashMap<(usize, ir::InvIdx), usize>,
+    values: HashMap<(usize, ir::PortIdx, u64), SExpr>,
+    /// Values of the inputs of top-level frames. Inputs with the same name
+    /// carry the same value in all top-level frames.
+    inputs: HashMap<(String, u64), SExpr>,
+    /// Number of unconstrained values across all frames
+    fresh: usize,
+    /// Primitives without a semantic model. Their outputs are unconstrained.
+    pub unmodeled: BTreeSet<String>,
+}
+
+impl<'a> Unroll<'a> {
+    pub fn new(sol: &'a mut smt::Context) -> Self {
+        Self {
+            sol,
+            frames: vec![],
+            inlined: HashMap::new(),
+            values: HashMap::new(),
+            inputs: HashMap::new(),
+            fresh: 0,
+            unmodeled: BTreeSet::new(),
+        }
+    }
+
+    /// Unroll the component whose only event is triggered in cycle 0. The
+    /// callees of the component are defined in the context. Returns the
+    /// frame of the component.
+    pub fn add_top(
+        &mut self,
+        ctx: &'a ir::Context,
+        comp: &'a ir::Component,
+    ) -> Result<usize, String> {
+        let events = comp.events().idx_iter().collect::<Vec<_>>();
+        let [event] = events[..] else {
+            return Err(format!(
+                "the component has {} events but only components with exactly one event can be unrolled",
+                events.len()
+            ));
+        };
+        let events = HashMap::from([(event, 0)]);
+        self.frames.push(Frame::new(ctx, comp, events, None));
+        Ok(self.frames.len() - 1)
+    }
+
+    pub fn sol(&self) -> &smt::Context {
+        self.sol
+    }
+
+    pub fn sol_mut(&mut self) -> &mut smt::Context {
+        self.sol
+    }
+
+    /// The values computed for ports of the component in the frame
+    pub fn frame_values(
+        &self,
+        f: usize,
+    ) -> impl Iterator<Item = (ir::PortIdx, u64, SExpr)> + '_ {
+        self.values
+            .iter()
+            .filter(move |((g, ..), _)| *g == f)
+            .map(|((_, p, c), v)| (*p, *c, *v))
+    }
+
+    /// Value of a concrete expression
+    pub fn eval(comp: &ir::Component, e: ir::ExprIdx) -> u64 {
+        match comp.get(e) {
+            ir::Expr::Concrete(n) => *n,
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let (l, r) = (Self::eval(comp, *lhs), Self::eval(comp, *rhs));
+                match op {
+                    ast::Op::Add => l + r,
+                    ast::Op::Sub => l - r,
+                    ast::Op::Mul => l * r,
+                    ast::Op::Div => l / r,
+                    ast::Op::Mod => l % r,
+                }
+            }
+            ir::Expr::Param(_) | ir::Expr::Fn { .. } => comp.internal_error(
+                format!("{} is not a concrete number", comp.display(e)),
+            ),
+        }
+    }
+
+    /// The cycle denoted by the time in the frame
+    pub fn cycle(&self, f: usize, t: ir::TimeIdx) -> u64 {
+        let comp = self.frames[f].comp;
+        let ir::Time { event, offset } = comp.get(t);
+        self.frames[f].events[event] + Self::eval(comp, *offset)
+    }
+
+    /// Width of the port in the frame
+    pub fn width(&self, f: usize, p: ir::PortIdx) -> u64 {
+        let comp = self.frames[f].comp;
+        Self::eval(comp, comp.get(p).width)
+    }
+
+    /// A new unconstrained value for the port
+    fn fresh(&mut self, f: usize, p: ir::PortIdx) -> io::Result<SExpr> {
+        let width = self.width(f, p);
+        let name = format!("v{}", self.fresh);
+        self.fresh += 1;
+        let sort = self.sol.bit_vec_sort(self.sol.numeral(width));
+        self.sol.declare_const(name, sort)
+    }
+
+    /// The value carried by the port of the frame during the cycle
+    pub fn value(
+        &mut self,
+        f: usize,
+        p: ir::PortIdx,
+        c: u64,
+    ) -> io::Result<SExpr> {
+        if let Some(v) = self.values.get(&(f, p, c)) {
+            return Ok(*v);
+        }
+        let comp = self.frames[f].comp;
+        let port = comp.get(p);
+        let v = match &port.owner {
+            ir::PortOwner::Sig { .. } if port.is_sig_in() => {
+                match &self.frames[f].parent {
+                    Some((parent, args)) => {
+                        let (parent, arg) = (*parent, args[&p]);
+                        self.value(parent, arg, c)?
+                    }
+                    None => {
+                        let name = port_names(comp).remove(&p).unwrap();
+                        match self.inputs.get(&(name.clone(), c)) {
+                            Some(v) => *v,
+                            None => {
+                                let v = self.fresh(f, p)?;
+                                self.inputs.insert((name, c), v);
+                                v
+                            }
+                        }
+                    }
+                }
+            }
+            // Outputs of the component and inputs of invocations are driven
+            // by connections. Undriven inputs are unconstrained.
+            ir::PortOwner::Sig { .. }
+            | ir::PortOwner::Inv {
+                dir: ir::Direction::In,
+                ..
+            } => match self.frames[f].drivers.get(&p) {
+                Some(src) => self.value(f, *src, c)?,
+                None => self.fresh(f, p)?,
+            },
+            ir::PortOwner::Inv {
+                inv,
+                dir: ir::Direction::Out,
+                base,
+            } => self.output(f, *inv, base.key(), p, c)?,
+            ir::PortOwner::Local => {
+                comp.internal_error("local ports should have been eliminated")
+            }
+        };
+        self.values.insert((f, p, c), v);
+        Ok(v)
+    }
+
+    /// The value of an output of an invocation
+    fn output(
+        &mut self,
+        f: usize,
+        inv: ir::InvIdx,
+        base: ir::PortIdx,
+        p: ir::PortIdx,
+        c: u64,
+    ) -> io::Result<SExpr> {
+        let Frame { ctx, comp, .. } = self.frames[f];
+        let callee_idx = inv.comp(comp);
+        let callee = ctx.get(callee_idx);
+        if !callee.is_ext {
+            let inner = self.inline(f, inv);
+            return self.value(inner, base, c);
+        }
+
+        // Externals keep the names of their definition in the source
+        let Some(src) = &callee.src_info else {
+            callee
+                .internal_error("external component has no source information")
+        };
+        let name = src.name.to_string();
+        let model = ctx
+            .get_filename(callee_idx)
+            .and_then(|file| models::model(&file, &name));
+        let Some(model) = model else {
+            self.unmodeled.insert(name);
+            return self.fresh(f, p);
+        };
+
+        // Ports of the invocation by their name in the primitive
+        let ports: HashMap<_, _> = comp
+            .get(inv)
+            .ports
+            .iter()
+            .map(|p| {
+                let ir::PortOwner::Inv { base, .. } = &comp.get(*p).owner
+                else {
+                    unreachable!(
+                        "invocation port is not owned by an invocation"
+                    )
+                };
+                (src.ports.get(base.key()).to_string(), *p)
+            })
+            .collect();
+        let mut inputs = Vec::with_capacity(model.inputs.len());
+        for input in model.inputs {
+            let port = ports[*input];
+            let cycle = if model.registered {
+                self.cycle(f, comp.get(port).live.range.start)
+            } else {
+                c
+            };
+            inputs.push((self.value(f, port, cycle)?, self.width(f, port)));
+        }
+        let inst = comp.get(comp.get(inv).inst);
+        // Arguments are given for the signature parameters in order
+        let params = callee
+            .params()
+            .iter()
+            .filter(|(_, p)| p.owner == ir::ParamOwner::Sig)
+            .map(|(p, _)| p)
+            .zip(inst.args.iter())
+            .map(|(p, v)| (src.params.get(p).to_string(), Self::eval(comp, *v)))
+            .collect();
+        let prim = models::Prim {
+            sol: self.sol,
+            params,
+            inputs,
+            width: self.width(f, p),
+        };
+        Ok((model.output)(&prim))
+    }
+
+    /// The frame inlining the invocation of a user-level component
+    fn inline(&mut self, f: usize, inv: ir::InvIdx) -> usize {
+        if let Some(inner) = self.inlined.get(&(f, inv)) {
+            return *inner;
+        }
+        let Frame { ctx, comp, .. } = self.frames[f];
+        let invoke = comp.get(inv);
+        let callee = ctx.get(inv.comp(comp));
+        let events = invoke
+            .events
+            .iter()
+            .map(|eb| (eb.base.key(), self.cycle(f, eb.arg)))
+            .collect();
+        let args = invoke
+            .ports
+            .iter()
+            .filter_map(|p| match &comp.get(*p).owner {
+                ir::PortOwner::Inv {
+                    dir: ir::Direction::In,
+                    base,
+                    ..
+                } => Some((base.key(), *p)),
+                _ => None,
+            })
+            .collect();
+        self.frames
+            .push(Frame::new(ctx, callee, events, Some((f, args))));
+        let inner = self.frames.len() - 1;
+        self.inlined.insert((f, inv), inner);
+        inner
+    }
+
+    /// Notes about the primitives whose outputs are unconstrained
+    pub fn unmodeled_notes(&self) -> Vec<String> {
+        self.unmodeled
+            .iter()
+            .map(|p| format!("The outputs of primitive `{p}' are unconstrained because it does not have a semantic model."))
+            .collect()
+    }
+
+    /// Decimal value of a bitvector literal like `#b101` or `#x5`
+    pub fn bv_value(v: &str) -> String {
+        let n = if let Some(b) = v.strip_prefix("#b") {
+            u128::from_str_radix(b, 2).ok()
+        } else if let Some(x) = v.strip_prefix("#x") {
+            u128::from_str_radix(x, 16).ok()
+        } else {
+            None
+        };
+        n.map_or_else(|| v.to_string(), |n| n.to_string())
+    }
+
+    /// Table of the values of the inputs and outputs of the component and
+    /// the invocations in the frame in each cycle of the model found by the
+    /// solver.
+    pub fn trace(&mut self, f: usize) -> io::Result<String> {
+        let comp = self.frames[f].comp;
+        let vals = self
+            .frame_values(f)
+            .filter(|(p, ..)| {
+                let port = comp.get(*p);
+                port.is_sig_in()
+                    || port.is_sig_out()
+                    || matches!(
+                        port.owner,
+                        ir::PortOwner::Inv {
+                            dir: ir::Direction::Out,
+                            ..
+                        }
+                    )
+            })
+            .collect_vec();
+        let values = self
+            .sol
+            .get_value(vals.iter().map(|(.., v)| *v).collect())?;
+        let mut rows: BTreeMap<ir::PortIdx, HashMap<u64, String>> =
+            BTreeMap::new();
+        for ((p, c, _), (_, v)) in vals.iter().zip(values) {
+            let v = Self::bv_value(&self.sol.display(v).to_string());
+            rows.entry(*p).or_default().insert(*c, v);
+        }
+        let cycles = vals.iter().map(|(_, c, _)| *c).max().unwrap_or(0) + 1;
+
+        let row = |first: String, rest: Vec<String>| {
+            iter::once(first).chain(rest).collect_vec()
+        };
+        let mut names = port_names(comp);
+        let mut table = vec![row(
+            "cycle".into(),
+            (0..cycles).map(|c| c.to_string()).collect(),
+        )];
+        for (p, vals) in rows {
+            table.push(row(
+                names.remove(&p).unwrap(),
+                (0..cycles)
+                    .map(|c| vals.get(&c).cloned().unwrap_or("-".into()))
+                    .collect(),
+            ));
+        }
+        let widths = (0..table[0].len())
+            .map(|i| table.iter().map(|r| r[i].len()).max().unwrap())
+            .collect_vec();
+        Ok(table
+            .iter()
+            .map(|r| {
+                r.iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(i, (v, w))| {
+                        if i == 0 {
+                            format!("{v:<w$}")
+                        } else {
+                            format!("{v:>w$}")
+                        }
+                    })
+                    .join("  ")
+            })
+            .join("\n"))
+    }
+}
@@ -1,10 +1,10 @@
 use crate::{
-    cmdline,
     ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
 };
 use fil_ir::{
     self as ir, Access, AddCtx, Bind, Command, Component, Connect, Ctx,
-    DenseIndexInfo, DisplayCtx, Expr, Foreign, Info, InvIdx, Invoke, Liveness,
+    DenseIndexInfo, DisplayCtx, Expr, Foreign, InvIdx, Invoke, Liveness,
     MutCtx, Port, PortIdx, PortOwner, Range, Subst, Time,
 };
 use fil_utils as utils;
@@ -198,7 +198,7 @@ impl BundleElim {
 }
 
 impl Construct for BundleElim {
-    fn from(_opts: &cmdline::Opts, ctx: &mut ir::Context) -> Self {
+    fn from(_opts: &options::Options, ctx: &mut ir::Context) -> Self {
         let mut visitor = Self {
             context: DenseIndexInfo::default(),
             local_map: HashMap::new(),
@@ -228,7 +228,7 @@ impl Visitor for BundleElim {
         connect: &mut Connect,
         data: &mut VisitorData,
     ) -> Action {
-        let Connect { src, dst, .. } = connect;
+        let Connect { src, dst, info } = connect;
 
         if !self.context.get(data.idx).contains_key(&dst.port) {
             // we are writing to a local port here.
@@ -256,13 +256,33 @@ impl Visitor for BundleElim {
                     Command::Connect(Connect {
                         src: Access::port(src, &mut data.comp),
                         dst: Access::port(dst, &mut data.comp),
-                        info: data.comp.add(Info::empty()),
+                        // Keep the source location of the original connection
+                        info: *info,
                     })
                 })
                 .collect(),
         )
     }
 
+    /// Replaces the ports read by the assertion with their expanded versions.
+    fn value_assert(
+        &mut self,
+        assert: &mut ir::ValueAssert,
+        data: &mut VisitorData,
+    ) -> Action {
+        for access in assert.ports_mut() {
+            let ports = self.get(access, data);
+            let [port] = ports[..] else {
+                data.comp.internal_error(format!(
+                    "Assertion reads {} ports",
+                    ports.len()
+                ))
+            };
+            *access = Access::port(port, &mut data.comp);
+        }
+        Action::Continue
+    }
+
     fn bundle_def(
         &mut self,
         _: ir::PortIdx,
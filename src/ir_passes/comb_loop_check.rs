@@ -0,0 +1,314 @@
+use crate::{
+    ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
+};
+use fil_ir::{self as ir, Ctx, DisplayCtx, PortIdx};
+use fil_utils::{codes, Diagnostics, Error, GPosIdx};
+use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Why a port combinationally depends on another port.
+#[derive(Clone, Copy)]
+enum Edge {
+    /// The destination is driven by the source using a connection
+    Connect(ir::InfoIdx),
+    /// The output of an invocation depends on one of its inputs
+    Invoke(ir::InvIdx),
+}
+
+/// Primitives from `primitives/state.fil` whose outputs are available in the
+/// same cycle as their inputs but only expose the value stored in a register.
+const REGISTERED: &[&str] = &["Prev", "ContPrev"];
+
+/// Combinational dependencies of a component: maps each output port in the
+/// signature to the input ports in the signature that it depends on within the
+/// same cycle.
+type Summary = HashMap<PortIdx, Vec<PortIdx>>;
+
+/// Detects combinational loops in the lowered program.
+/// Must run after [super::BundleElim] so that every connection is between
+/// two single ports.
+///
+/// The pass builds a port-level dependency graph for each component where an
+/// edge `a -> b` means that `b` depends on `a` within the same cycle:
+/// * A connection `dst = src` adds the edge `src -> dst`.
+/// * An invocation of an external component adds the edge `in -> out` when
+///   `out` becomes available in the same cycle that `in` does, i.e., the
+///   primitive may compute `out` from `in` combinationally. The primitives in
+///   [REGISTERED] are the exception: their outputs read stored values.
+/// * An invocation of a user-level component adds the edge `in -> out` when
+///   the body of the component has a combinational path from `in` to `out`.
+pub struct CombLoopCheck {
+    /// Combinational dependencies of the components that have been analyzed
+    summaries: HashMap<ir::CompIdx, Summary>,
+    diag: Diagnostics,
+}
+
+impl Construct for CombLoopCheck {
+    fn from(_: &options::Options, _: &mut ir::Context) -> Self {
+        Self {
+            summaries: HashMap::new(),
+            diag: Diagnostics::default(),
+        }
+    }
+
+    /// Summaries are kept across components so that invocations of
+    /// already-visited components do not need to be re-analyzed.
+    fn clear_data(&mut self) {}
+}
+
+impl CombLoopCheck {
+    /// Returns true if the output `out` of an invocation of an external
+    /// component may combinationally depend on the input `inp`, i.e., `out`
+    /// becomes available at the same time as `inp`.
+    fn ext_dep(out: &ir::Port, inp: &ir::Port, comp: &ir::Component) -> bool {
+        let out = comp.get(out.live.range.start);
+        let inp = comp.get(inp.live.range.start);
+        if out.event != inp.event {
+            // Without a common event, we cannot relate the two times
+            return false;
+        }
+        match (out.offset.as_concrete(comp), inp.offset.as_concrete(comp)) {
+            (Some(o), Some(i)) => o == i,
+            _ => false,
+        }
+    }
+
+    /// Compute the combinational summary of a user-level component, reusing
+    /// the summaries of the components it instantiates.
+    fn summary(&mut self, idx: ir::CompIdx, ctx: &ir::Context) -> Summary {
+        if let Some(summary) = self.summaries.get(&idx) {
+            return summary.clone();
+        }
+        let comp = ctx.get(idx);
+        let graph = self.graph(comp, ctx);
+        let summary = Self::summarize(comp, &graph);
+        self.summaries.insert(idx, summary.clone());
+        summary
+    }
+
+    /// Maps each output port of the signature to the input ports of the
+    /// signature it can reach backwards in the graph.
+    fn summarize(
+        comp: &ir::Component,
+        graph: &HashMap<PortIdx, Vec<(PortIdx, Edge)>>,
+    ) -> Summary {
+        let mut summary = Summary::new();
+        for (inp, _) in comp.ports().iter().filter(|(_, p)| p.is_sig_in()) {
+            let mut seen = HashSet::new();
+            let mut stack = vec![inp];
+            while let Some(p) = stack.pop() {
+                if !seen.insert(p) {
+                    continue;
+                }
+                if comp.get(p).is_sig_out() {
+                    summary.entry(p).or_default().push(inp);
+                }
+                if let Some(next) = graph.get(&p) {
+                    stack.extend(next.iter().map(|(n, _)| *n));
+                }
+            }
+        }
+        summary
+    }
+
+    /// Build the dependency graph for the ports of a component.
+    fn graph(
+        &mut self,
+        comp: &ir::Component,
+        ctx: &ir::Context,
+    ) -> HashMap<PortIdx, Vec<(PortIdx, Edge)>> {
+        let mut graph: HashMap<PortIdx, Vec<(PortIdx, Edge)>> = HashMap::new();
+
+        for cmd in &comp.cmds {
+            let ir::Command::Connect(ir::Connect { src, dst, info }) = cmd
+            else {
+                continue;
+            };
+            graph
+                .entry(src.port)
+                .or_default()
+                .push((dst.port, Edge::Connect(*info)));
+        }
+
+        for (inv_idx, inv) in comp.invocations().iter() {
+            let callee_idx = comp.get(inv.inst).comp;
+            let callee = ctx.get(callee_idx);
+            let (ins, outs): (Vec<_>, Vec<_>) = inv
+                .ports
+                .iter()
+                .copied()
+                .filter(|p| comp.ports().is_valid(*p))
+                .partition(|p| comp.get(*p).is_inv_in());
+
+            if callee.is_ext {
+                let registered = callee
+                    .src_info
+                    .as_ref()
+                    .map_or(false, |s| REGISTERED.contains(&s.name.as_ref()));
+                if registered {
+                    continue;
+                }
+                for (&out, &inp) in outs.iter().cartesian_product(&ins) {
+                    if Self::ext_dep(comp.get(out), comp.get(inp), comp) {
+                        graph
+                            .entry(inp)
+                            .or_default()
+                            .push((out, Edge::Invoke(inv_idx)));
+                    }
+                }
+            } else {
+                let summary = self.summary(callee_idx, ctx);
+                // Map the ports of the callee's signature to the invocation's ports
+                let base = |p: &PortIdx| {
+                    let ir::PortOwner::Inv { base, .. } = &comp.get(*p).owner
+                    else {
+                        unreachable!("invocation port is not owned by it")
+                    };
+                    base.key()
+                };
+                let ins: HashMap<_, _> =
+                    ins.iter().map(|p| (base(p), *p)).collect();
+                for out in &outs {
+                    let Some(deps) = summary.get(&base(out)) else {
+                        continue;
+                    };
+                    for dep in deps {
+                        if let Some(&inp) = ins.get(dep) {
+                            graph
+                                .entry(inp)
+                                .or_default()
+                                .push((*out, Edge::Invoke(inv_idx)));
+                        }
+                    }
+                }
+            }
+        }
+
+        graph
+    }
+
+    /// Find cycles in the graph. Each cycle is reported as the list of edges
+    /// that form it, starting at the port where it was detected.
+    fn cycles(
+        comp: &ir::Component,
+        graph: &HashMap<PortIdx, Vec<(PortIdx, Edge)>>,
+    ) -> Vec<Vec<(PortIdx, PortIdx, Edge)>> {
+        // Ports that have been completely explored
+        let mut done: HashSet<PortIdx> = HashSet::new();
+        // Set of ports in each reported cycle to avoid duplicates
+        let mut reported: HashSet<BTreeSet<PortIdx>> = HashSet::new();
+        let mut cycles = Vec::new();
+
+        for (root, _) in comp.ports().iter() {
+            if done.contains(&root) {
+                continue;
+            }
+            // The current path through the graph: the port and the index of
+            // the next edge to explore.
+            let mut path: Vec<(PortIdx, usize)> = vec![(root, 0)];
+            // Edges taken along the path
+            let mut taken: Vec<(PortIdx, PortIdx, Edge)> = vec![];
+            while let Some((port, next)) = path.last_mut() {
+                let port = *port;
+                let edges = graph.get(&port).map_or(&[][..], |e| e.as_slice());
+                let Some(&(dst, edge)) = edges.get(*next) else {
+                    done.insert(port);
+                    path.pop();
+                    taken.pop();
+                    continue;
+                };
+                *next += 1;
+
+                if done.contains(&dst) {
+                    continue;
+                }
+                if let Some(pos) = path.iter().position(|(p, _)| *p == dst) {
+                    let mut cycle = taken[pos..].to_vec();
+                    cycle.push((port, dst, edge));
+                    let ports = cycle.iter().map(|(s, _, _)| *s).collect();
+                    if reported.insert(ports) {
+                        cycles.push(cycle);
+                    }
+                    continue;
+                }
+                taken.push((port, dst, edge));
+                path.push((dst, 0));
+            }
+        }
+        cycles
+    }
+
+    /// Report a cycle with the location of each dependency along it.
+    fn report(
+        &mut self,
+        cycle: Vec<(PortIdx, PortIdx, Edge)>,
+        comp: &ir::Component,
+    ) {
+        let path = cycle
+            .iter()
+            .map(|(src, _, _)| comp.display(*src))
+            .chain(std::iter::once(comp.display(cycle[0].0)))
+            .join(" -> ");
+        let mut err =
+            Error::malformed(format!("combinational loop detected: {path}"))
+                .with_code(codes::COMB_LOOP);
+
+        for (src, dst, edge) in cycle {
+            let (msg, pos) = match edge {
+                Edge::Connect(info) => (
+                    format!(
+                        "`{}' is driven by `{}'",
+                        comp.display(dst),
+                        comp.display(src)
+                    ),
+                    comp.get(info)
+                        .as_connect()
+                        .map(|c| c.src_loc)
+                        .unwrap_or(GPosIdx::UNKNOWN),
+                ),
+                Edge::Invoke(inv) => (
+                    format!(
+                        "`{}' depends on `{}' in the same cycle",
+                        comp.display(dst),
+                        comp.display(src)
+                    ),
+                    comp.get(comp.get(inv).info)
+                        .as_invoke()
+                        .map(|i| i.bind_loc)
+                        .unwrap_or(GPosIdx::UNKNOWN),
+                ),
+            };
+            let info = if pos == GPosIdx::UNKNOWN {
+                self.diag.add_message(msg)
+            } else {
+                self.diag.add_info(msg, pos)
+            };
+            err = err.add_note(info);
+        }
+        self.diag.add_error(err);
+    }
+}
+
+impl Visitor for CombLoopCheck {
+    fn name() -> &'static str {
+        "comb-loop-check"
+    }
+
+    fn start(&mut self, data: &mut VisitorData) -> Action {
+        if data.comp.is_ext {
+            return Action::Stop;
+        }
+        let graph = self.graph(&data.comp, &*data.mut_ctx);
+        for cycle in Self::cycles(&data.comp, &graph) {
+            self.report(cycle, &data.comp);
+        }
+        let summary = Self::summarize(&data.comp, &graph);
+        self.summaries.insert(data.idx, summary);
+        Action::Stop
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        self.diag.report_all()
+    }
+}
@@ -0,0 +1,444 @@
+//! Bounds on the values of terms implied by the assumptions of facts.
+//!
+//! Parameters and events are natural numbers, so a bitvector encoding of a
+//! proposition is exact as long as none of its terms overflows or goes below
+//! zero. For a fact `a => c`, we compute intervals for every term under the
+//! assumptions `a`. If all the intervals fit in `w` bits, every assignment
+//! that satisfies `a` evaluates every term exactly with `w` bits. Assignments
+//! that do not satisfy `a` satisfy the fact trivially so a proof using the
+//! encoding is a proof of the fact.
+//!
+//! The same intervals determine the arguments for which the values of
+//! functions like `bit_rev` are added to the encoding as a lookup table.
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use itertools::Itertools;
+use linked_hash_set::LinkedHashSet;
+use std::collections::HashMap;
+
+/// Number of times the bounds on parameters are refined using the
+/// assumptions.
+const ROUNDS: usize = 4;
+
+/// Interval of values of a term. `None` represents an unknown bound.
+#[derive(Clone, Copy)]
+struct Interval {
+    lo: Option<i128>,
+    hi: Option<i128>,
+}
+
+impl Interval {
+    fn exact(n: i128) -> Self {
+        Self {
+            lo: Some(n),
+            hi: Some(n),
+        }
+    }
+
+    fn nat() -> Self {
+        Self {
+            lo: Some(0),
+            hi: None,
+        }
+    }
+
+    fn unknown() -> Self {
+        Self { lo: None, hi: None }
+    }
+
+    /// Interval of values of a natural number with at most `bits` bits.
+    fn bits(bits: Option<i128>) -> Self {
+        Self {
+            lo: Some(0),
+            hi: bits.filter(|b| *b < 127).map(|b| (1 << b) - 1),
+        }
+    }
+
+    fn is_nat(&self) -> bool {
+        self.lo.map_or(false, |lo| lo >= 0)
+    }
+
+    fn sub(self, r: Self) -> Self {
+        Self {
+            lo: lift(self.lo, r.hi, i128::checked_sub),
+            hi: lift(self.hi, r.lo, i128::checked_sub),
+        }
+    }
+}
+
+/// Apply the operation to both bounds if they are known.
+fn lift(
+    l: Option<i128>,
+    r: Option<i128>,
+    f: impl Fn(i128, i128) -> Option<i128>,
+) -> Option<i128> {
+    f(l?, r?)
+}
+
+/// Number of bits needed to represent the natural number
+fn bits(n: i128) -> u8 {
+    (128 - n.leading_zeros()).max(1) as u8
+}
+
+/// A term that is evaluated by the encoding of a proposition
+#[derive(Clone, Copy)]
+enum Term {
+    Expr(ir::ExprIdx),
+    /// Difference between the offsets of two times with the same event
+    Diff(ir::ExprIdx, ir::ExprIdx),
+}
+
+struct Bounds<'a> {
+    comp: &'a ir::Component,
+    params: HashMap<ir::ParamIdx, Interval>,
+    /// Lower bounds on the difference of terms ordered by the assumptions
+    order: HashMap<(ir::ExprIdx, ir::ExprIdx), i128>,
+}
+
+impl<'a> Bounds<'a> {
+    /// Bounds implied by the assumptions of the fact
+    fn assuming(comp: &'a ir::Component, prop: ir::PropIdx) -> Self {
+        let mut assumes = vec![];
+        if let ir::Prop::Implies(a, _) = comp.get(prop) {
+            conjuncts(comp, *a, &mut assumes);
+        }
+        let mut bounds = Bounds {
+            comp,
+            params: HashMap::new(),
+            order: HashMap::new(),
+        };
+        for _ in 0..ROUNDS {
+            for a in &assumes {
+                match comp.get(*a) {
+                    ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => {
+                        bounds.assume(op, *lhs, *rhs)
+                    }
+                    ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => {
+                        let (l, r) = (comp.get(*lhs), comp.get(*rhs));
+                        if l.event == r.event {
+                            bounds.assume(op, l.offset, r.offset)
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+        bounds
+    }
+
+    fn expr(&self, e: ir::ExprIdx) -> Interval {
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => {
+                self.params.get(p).copied().unwrap_or_else(Interval::nat)
+            }
+            ir::Expr::Concrete(n) => Interval::exact(*n as i128),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.expr(*lhs);
+                let r = self.expr(*rhs);
+                // Bounds of products and quotients are only computed for
+                // natural numbers.
+                let nat = l.is_nat() && r.is_nat();
+                let pos = nat && r.lo.map_or(false, |lo| lo > 0);
+                match op {
+                    ast::Op::Add => Interval {
+                        lo: lift(l.lo, r.lo, i128::checked_add),
+                        hi: lift(l.hi, r.hi, i128::checked_add),
+                    },
+                    ast::Op::Sub => self.diff(*lhs, *rhs),
+                    ast::Op::Mul if nat => Interval {
+                        lo: lift(l.lo, r.lo, i128::checked_mul),
+                        hi: lift(l.hi, r.hi, i128::checked_mul),
+                    },
+                    ast::Op::Div if pos => Interval {
+                        lo: lift(l.lo, r.hi, i128::checked_div),
+                        hi: lift(l.hi, r.lo, i128::checked_div),
+                    },
+                    ast::Op::Mod if pos => Interval {
+                        lo: Some(0),
+                        hi: r.hi.map(|hi| hi - 1),
+                    },
+                    ast::Op::Mul | ast::Op::Div | ast::Op::Mod => {
+                        Interval::unknown()
+                    }
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let args =
+                    args.iter().map(|a| self.expr(*a)).collect::<Vec<_>>();
+                if !args.iter().all(Interval::is_nat) {
+                    return Interval::unknown();
+                }
+                match (op, &args[..]) {
+                    (ast::Fn::Pow2, [n]) => Interval {
+                        lo: n.lo.filter(|lo| *lo < 127).map(|lo| 1 << lo),
+                        hi: n.hi.filter(|hi| *hi < 127).map(|hi| 1 << hi),
+                    },
+                    (ast::Fn::Log2, [n]) => Interval {
+                        lo: Some(0),
+                        hi: n.hi.map(|hi| bits(hi) as i128),
+                    },
+                    // The bits of a 32-bit floating point number
+                    (ast::Fn::SinB | ast::Fn::CosB, _) => {
+                        Interval::bits(Some(32))
+                    }
+                    (ast::Fn::BitRev, [_, width]) => Interval::bits(width.hi),
+                    _ => Interval::unknown(),
+                }
+            }
+        }
+    }
+
+    /// Interval of `l - r`
+    fn diff(&self, l: ir::ExprIdx, r: ir::ExprIdx) -> Interval {
+        let mut d = self.expr(l).sub(self.expr(r));
+        if let Some(min) = self.order.get(&(l, r)) {
+            d.lo = Some(d.lo.map_or(*min, |lo| lo.max(*min)));
+        }
+        d
+    }
+
+    fn term(&self, t: Term) -> Interval {
+        match t {
+            Term::Expr(e) => self.expr(e),
+            Term::Diff(l, r) => self.diff(l, r),
+        }
+    }
+
+    /// Refine the bounds of the parameters assuming `lhs op rhs`
+    fn assume(&mut self, op: &ir::Cmp, lhs: ir::ExprIdx, rhs: ir::ExprIdx) {
+        let strict = matches!(op, ir::Cmp::Gt) as i128;
+        self.order.insert((lhs, rhs), strict);
+        if matches!(op, ir::Cmp::Eq) {
+            self.order.insert((rhs, lhs), 0);
+        }
+        let (l, r) = (self.expr(lhs), self.expr(rhs));
+        // rhs <= lhs - strict
+        if let ir::Expr::Param(p) = self.comp.get(rhs) {
+            let hi = l.hi.and_then(|hi| hi.checked_sub(strict));
+            self.refine(*p, None, hi);
+        }
+        // lhs >= rhs + strict
+        if let ir::Expr::Param(p) = self.comp.get(lhs) {
+            let lo = r.lo.and_then(|lo| lo.checked_add(strict));
+            self.refine(*p, lo, None);
+        }
+        if matches!(op, ir::Cmp::Eq) {
+            if let ir::Expr::Param(p) = self.comp.get(lhs) {
+                self.refine(*p, None, r.hi);
+            }
+            if let ir::Expr::Param(p) = self.comp.get(rhs) {
+                self.refine(*p, l.lo, None);
+            }
+        }
+    }
+
+    fn refine(&mut self, p: ir::ParamIdx, lo: Option<i128>, hi: Option<i128>) {
+        let cur = self.params.entry(p).or_insert_with(Interval::nat);
+        cur.lo = match (cur.lo, lo) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        cur.hi = match (cur.hi, hi) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+}
+
+/// Conjuncts of the proposition
+fn conjuncts(comp: &ir::Component, p: ir::PropIdx, out: &mut Vec<ir::PropIdx>) {
+    match comp.get(p) {
+        ir::Prop::And(l, r) => {
+            conjuncts(comp, *l, out);
+            conjuncts(comp, *r, out);
+        }
+        _ => out.push(p),
+    }
+}
+
+/// The terms compared by the proposition. Times with the same event are
+/// compared using their offsets. Returns an error if the proposition relates
+/// different events but still collects the offsets of the times.
+fn terms(
+    comp: &ir::Component,
+    p: ir::PropIdx,
+    out: &mut Vec<Term>,
+) -> Result<(), String> {
+    let mut res = Ok(());
+    let mut times = |l: ir::TimeIdx, r: ir::TimeIdx, out: &mut Vec<Term>| {
+        let (l, r) = (comp.get(l), comp.get(r));
+        out.extend([Term::Expr(l.offset), Term::Expr(r.offset)]);
+        if l.event == r.event {
+            Some((l.offset, r.offset))
+        } else {
+            if res.is_ok() {
+                res = Err(format!(
+                    "it relates the events {} and {}",
+                    comp.display(l.event),
+                    comp.display(r.event)
+                ));
+            }
+            None
+        }
+    };
+    match comp.get(p) {
+        ir::Prop::True | ir::Prop::False => (),
+        ir::Prop::Cmp(ir::CmpOp { lhs, rhs, .. }) => {
+            out.extend([Term::Expr(*lhs), Term::Expr(*rhs)]);
+        }
+        ir::Prop::TimeCmp(ir::CmpOp { lhs, rhs, .. }) => {
+            times(*lhs, *rhs, out);
+        }
+        ir::Prop::TimeSubCmp(ir::CmpOp { lhs, rhs, .. }) => {
+            for ts in [lhs, rhs] {
+                match ts {
+                    ir::TimeSub::Unit(e) => out.push(Term::Expr(*e)),
+                    ir::TimeSub::Sym { l, r } => {
+                        if let Some((l, r)) = times(*l, *r, out) {
+                            out.push(Term::Diff(l, r));
+                        }
+                    }
+                }
+            }
+        }
+        ir::Prop::Not(p) => return terms(comp, *p, out),
+        ir::Prop::And(l, r) | ir::Prop::Or(l, r) | ir::Prop::Implies(l, r) => {
+            let l = terms(comp, *l, out);
+            let r = terms(comp, *r, out);
+            return l.and(r);
+        }
+    }
+    res
+}
+
+/// The terms and all their sub-terms
+fn sub_terms(comp: &ir::Component, ts: Vec<Term>) -> Vec<Term> {
+    let mut out = vec![];
+    let mut todo = ts;
+    while let Some(t) = todo.pop() {
+        out.push(t);
+        let Term::Expr(e) = t else {
+            continue;
+        };
+        match comp.get(e) {
+            ir::Expr::Param(_) | ir::Expr::Concrete(_) => (),
+            ir::Expr::Bin { lhs, rhs, .. } => {
+                todo.extend([Term::Expr(*lhs), Term::Expr(*rhs)])
+            }
+            ir::Expr::Fn { args, .. } => {
+                todo.extend(args.iter().map(|a| Term::Expr(*a)))
+            }
+        }
+    }
+    out
+}
+
+/// Number of bits needed to exactly evaluate every term of the fact under its
+/// assumptions. Returns the reason why no width up to `max` bits suffices
+/// otherwise.
+///
+/// The bounds of every parameter start at `[0, inf)` even though the integer
+/// and built-in encodings do not constrain parameters to be non-negative.
+/// Parameters are only ever instantiated with natural numbers, so a proof
+/// using the selected width holds for every instantiation of the component
+/// but, unlike the other encodings, says nothing about negative values.
+pub fn fact_width(
+    comp: &ir::Component,
+    prop: ir::PropIdx,
+    max: u8,
+) -> Result<u8, String> {
+    let bounds = Bounds::assuming(comp, prop);
+    let mut ts = vec![];
+    terms(comp, prop, &mut ts)?;
+    let mut width = 1;
+    for t in sub_terms(comp, ts) {
+        let disp = || match t {
+            Term::Expr(e) => comp.display(e),
+            Term::Diff(l, r) => {
+                format!("{} - {}", comp.display(l), comp.display(r))
+            }
+        };
+        let Interval { lo, hi } = bounds.term(t);
+        match (lo, hi) {
+            (None, _) => return Err(format!("`{}' may be negative", disp())),
+            (Some(lo), _) if lo < 0 => {
+                return Err(format!("`{}' may be negative", disp()))
+            }
+            (_, None) => {
+                return Err(format!("`{}' has no upper bound", disp()))
+            }
+            (_, Some(hi)) if bits(hi) > max => {
+                return Err(format!(
+                    "`{}' may need more than {max} bits",
+                    disp()
+                ))
+            }
+            (_, Some(hi)) => width = width.max(bits(hi)),
+        }
+    }
+    Ok(width)
+}
+
+/// Arguments of the applications of `bit_rev`, `sin_bits`, and `cos_bits` in
+/// the facts for which the assumptions of the fact bound the arguments to
+/// finite ranges. At most `max` arguments are returned.
+pub fn fn_table(
+    comp: &ir::Component,
+    props: impl IntoIterator<Item = ir::PropIdx>,
+    max: usize,
+) -> Vec<(ast::Fn, Vec<u64>)> {
+    let mut table = LinkedHashSet::new();
+    for prop in props {
+        let bounds = Bounds::assuming(comp, prop);
+        let mut ts = vec![];
+        // Functions applied to offsets of different events are still bounded
+        let _ = terms(comp, prop, &mut ts);
+        for t in sub_terms(comp, ts) {
+            let Term::Expr(e) = t else {
+                continue;
+            };
+            let ir::Expr::Fn { op, args } = comp.get(e) else {
+                continue;
+            };
+            if !matches!(op, ast::Fn::BitRev | ast::Fn::SinB | ast::Fn::CosB) {
+                continue;
+            }
+            let Some(ranges) = args
+                .iter()
+                .map(|a| match bounds.expr(*a) {
+                    Interval {
+                        lo: Some(lo),
+                        hi: Some(hi),
+                    } if lo >= 0
+                        && hi <= u64::MAX as i128
+                        && hi - lo < max as i128 =>
+                    {
+                        Some(lo as u64..=hi as u64)
+                    }
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let size = ranges
+                .iter()
+                .try_fold(1usize, |acc, r| acc.checked_mul(r.clone().count()));
+            if size.map_or(true, |size| table.len() + size > max) {
+                continue;
+            }
+            for point in ranges.into_iter().multi_cartesian_product() {
+                // Values outside the domain of the functions are unspecified
+                let valid = match (op, &point[..]) {
+                    (ast::Fn::BitRev, [_, bits]) => *bits <= 64,
+                    (ast::Fn::SinB | ast::Fn::CosB, [_, den]) => *den > 0,
+                    _ => false,
+                };
+                if valid {
+                    table.insert((*op, point));
+                }
+            }
+        }
+    }
+    table.into_iter().collect()
+}
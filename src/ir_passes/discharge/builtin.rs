@@ -0,0 +1,915 @@
+//! A decision procedure for the constraints generated by the type checker.
+//!
+//! The procedure handles linear integer arithmetic over parameters and events.
+//! `pow2` and `log2` are evaluated when their arguments are concrete, and
+//! division and modulo by a constant are encoded using fresh variables. All
+//! other terms (non-linear multiplication, uninterpreted functions, and the
+//! functions defining existential parameters of instances) are abstracted as
+//! variables.
+//!
+//! Because of the abstraction, a satisfying assignment for the negation of a
+//! proposition is only reported as a counterexample after it has been
+//! validated against the real semantics of every abstracted term. Queries that
+//! cannot be decided either way are reported as [Res::Unknown] so that the
+//! caller can fall back to an external solver.
+
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// Maximum number of conjunctions of literals explored when case-splitting
+/// on disjunctions.
+const MAX_CUBES: usize = 1024;
+/// Maximum number of constraints generated while eliminating a variable.
+const MAX_CONSTRAINTS: usize = 4096;
+/// Maximum number of times a query is refined with lemmas about abstracted
+/// terms before giving up.
+const MAX_REFINEMENTS: usize = 16;
+
+/// Outcome of checking the validity of a proposition.
+pub enum Res {
+    /// The proposition holds for every assignment
+    Valid,
+    /// An assignment to the parameters that falsifies the proposition
+    Invalid(HashMap<ir::ParamIdx, i128>),
+    /// The procedure cannot decide the proposition
+    Unknown,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Var {
+    Param(ir::ParamIdx),
+    Event(ir::EventIdx),
+    /// An abstracted non-linear or uninterpreted term
+    Term(ir::ExprIdx),
+    /// Variable introduced to encode division and modulo by a constant
+    Fresh(u32),
+}
+
+type Model = HashMap<Var, i128>;
+
+/// A linear term: `sum(coeff * var) + constant`
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+struct Lin {
+    coeffs: BTreeMap<Var, i128>,
+    constant: i128,
+}
+
+impl Lin {
+    fn cons(n: i128) -> Self {
+        Self {
+            coeffs: BTreeMap::new(),
+            constant: n,
+        }
+    }
+
+    fn var(v: Var) -> Self {
+        Self {
+            coeffs: BTreeMap::from([(v, 1)]),
+            constant: 0,
+        }
+    }
+
+    fn as_const(&self) -> Option<i128> {
+        self.coeffs.is_empty().then_some(self.constant)
+    }
+
+    fn scale(&self, k: i128) -> Option<Lin> {
+        if k == 0 {
+            return Some(Lin::cons(0));
+        }
+        let coeffs = self
+            .coeffs
+            .iter()
+            .map(|(v, c)| Some((*v, c.checked_mul(k)?)))
+            .collect::<Option<_>>()?;
+        Some(Lin {
+            coeffs,
+            constant: self.constant.checked_mul(k)?,
+        })
+    }
+
+    fn add(&self, other: &Lin) -> Option<Lin> {
+        let mut out = self.clone();
+        for (v, c) in &other.coeffs {
+            let n = out.coeffs.get(v).copied().unwrap_or(0).checked_add(*c)?;
+            if n == 0 {
+                out.coeffs.remove(v);
+            } else {
+                out.coeffs.insert(*v, n);
+            }
+        }
+        out.constant = out.constant.checked_add(other.constant)?;
+        Some(out)
+    }
+
+    fn sub(&self, other: &Lin) -> Option<Lin> {
+        self.add(&other.scale(-1)?)
+    }
+
+    fn offset(&self, n: i128) -> Option<Lin> {
+        self.add(&Lin::cons(n))
+    }
+
+    /// Replace `v` with `val` in this term
+    fn subst(&self, v: Var, val: &Lin) -> Option<Lin> {
+        let Some(&c) = self.coeffs.get(&v) else {
+            return Some(self.clone());
+        };
+        let mut out = self.clone();
+        out.coeffs.remove(&v);
+        out.add(&val.scale(c)?)
+    }
+
+    /// Value of the term in the model. Missing variables are zero.
+    fn eval(&self, model: &Model) -> Option<i128> {
+        self.coeffs.iter().try_fold(self.constant, |acc, (v, c)| {
+            let val = model.get(v).copied().unwrap_or(0);
+            acc.checked_add(c.checked_mul(val)?)
+        })
+    }
+
+    /// Greatest common divisor of the coefficients
+    fn gcd(&self) -> i128 {
+        self.coeffs.values().fold(0, |a, b| gcd(a, b.abs()))
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+#[derive(Clone)]
+enum Lit {
+    /// `lin >= 0`
+    Ge(Lin),
+    /// `lin == 0`
+    Eq(Lin),
+}
+
+impl Lit {
+    fn holds(&self, model: &Model) -> Option<bool> {
+        match self {
+            Lit::Ge(l) => Some(l.eval(model)? >= 0),
+            Lit::Eq(l) => Some(l.eval(model)? == 0),
+        }
+    }
+}
+
+/// A formula in negation normal form.
+/// `And(vec![])` is true and `Or(vec![])` is false.
+#[derive(Clone)]
+enum Formula {
+    Lit(Lit),
+    And(Vec<Formula>),
+    Or(Vec<Formula>),
+}
+
+impl Formula {
+    fn holds(&self, model: &Model) -> Option<bool> {
+        match self {
+            Formula::Lit(l) => l.holds(model),
+            Formula::And(fs) => {
+                for f in fs {
+                    if !f.holds(model)? {
+                        return Some(false);
+                    }
+                }
+                Some(true)
+            }
+            Formula::Or(fs) => {
+                for f in fs {
+                    if f.holds(model)? {
+                        return Some(true);
+                    }
+                }
+                Some(false)
+            }
+        }
+    }
+}
+
+/// Outcome of checking the satisfiability of a conjunction of literals
+enum Cube {
+    Sat(Model),
+    Unsat,
+    Unknown,
+}
+
+/// Normalize `lin >= 0` by dividing by the gcd of the coefficients and
+/// rounding the constant down, which is exact over the integers.
+/// Returns `Err` if the constraint is trivially false and `Ok(None)` if it is
+/// trivially true.
+fn normalize_ge(lin: Lin) -> Result<Option<Lin>, ()> {
+    let g = lin.gcd();
+    if g == 0 {
+        return if lin.constant >= 0 { Ok(None) } else { Err(()) };
+    }
+    Ok(Some(Lin {
+        coeffs: lin.coeffs.into_iter().map(|(v, c)| (v, c / g)).collect(),
+        constant: lin.constant.div_euclid(g),
+    }))
+}
+
+/// Normalize `lin == 0`. Returns `Err` if the equality has no integer
+/// solutions and `Ok(None)` if it is trivially true.
+fn normalize_eq(lin: Lin) -> Result<Option<Lin>, ()> {
+    let g = lin.gcd();
+    if g == 0 {
+        return if lin.constant == 0 { Ok(None) } else { Err(()) };
+    }
+    if lin.constant % g != 0 {
+        return Err(());
+    }
+    Ok(Some(Lin {
+        coeffs: lin.coeffs.into_iter().map(|(v, c)| (v, c / g)).collect(),
+        constant: lin.constant / g,
+    }))
+}
+
+/// Decide the satisfiability of a conjunction of literals over the integers.
+/// Equalities with a unit coefficient are solved by substitution and the
+/// remaining constraints are eliminated using Fourier-Motzkin elimination with
+/// integer tightening. Elimination is exact over the rationals, so an
+/// unsatisfiable projection proves unsatisfiability. A model is constructed by
+/// back-substitution and reported only if it is integral and satisfies every
+/// literal.
+fn decide(lits: &[Lit]) -> Cube {
+    macro_rules! unknown_on_none {
+        ($e:expr) => {
+            match $e {
+                Some(v) => v,
+                None => return Cube::Unknown,
+            }
+        };
+    }
+
+    let mut eqs = vec![];
+    let mut ges = vec![];
+    for lit in lits {
+        match lit {
+            Lit::Eq(l) => eqs.push(l.clone()),
+            Lit::Ge(l) => ges.push(l.clone()),
+        }
+    }
+
+    // Variables solved using equalities, in the order they were solved
+    let mut solved: Vec<(Var, Lin)> = vec![];
+    while let Some(eq) = eqs.pop() {
+        let eq = match normalize_eq(eq) {
+            Ok(Some(eq)) => eq,
+            Ok(None) => continue,
+            Err(()) => return Cube::Unsat,
+        };
+        let Some((&v, &c)) = eq.coeffs.iter().find(|(_, c)| c.abs() == 1)
+        else {
+            // Without a unit coefficient, we cannot solve for a variable
+            // without introducing fractions so we use two inequalities.
+            ges.push(unknown_on_none!(eq.scale(-1)));
+            ges.push(eq);
+            continue;
+        };
+        // c*v + rest = 0 => v = -c*rest since c = +-1
+        let mut rest = eq.clone();
+        rest.coeffs.remove(&v);
+        let val = unknown_on_none!(rest.scale(-c));
+        for e in eqs.iter_mut().chain(ges.iter_mut()) {
+            *e = unknown_on_none!(e.subst(v, &val));
+        }
+        solved.push((v, val));
+    }
+
+    let mut cur: HashSet<Lin> = HashSet::new();
+    for ge in ges {
+        match normalize_ge(ge) {
+            Ok(Some(ge)) => {
+                cur.insert(ge);
+            }
+            Ok(None) => (),
+            Err(()) => return Cube::Unsat,
+        }
+    }
+
+    // The constraints mentioning each eliminated variable
+    let mut levels: Vec<(Var, Vec<Lin>)> = vec![];
+    loop {
+        let vars: BTreeSet<Var> =
+            cur.iter().flat_map(|l| l.coeffs.keys().copied()).collect();
+        // Eliminate the variable that generates the fewest constraints
+        let Some(v) = vars.into_iter().min_by_key(|v| {
+            let pos = cur.iter().filter(|l| l.coeffs.get(v) > Some(&0)).count();
+            let neg = cur.iter().filter(|l| l.coeffs.get(v) < Some(&0)).count();
+            pos * neg
+        }) else {
+            break;
+        };
+
+        let (with, without): (Vec<_>, Vec<_>) =
+            cur.into_iter().partition(|l| l.coeffs.contains_key(&v));
+        let (pos, neg): (Vec<_>, Vec<_>) =
+            with.iter().partition(|l| l.coeffs[&v] > 0);
+
+        let mut next: HashSet<Lin> = without.into_iter().collect();
+        for p in &pos {
+            for n in &neg {
+                let a = p.coeffs[&v];
+                let b = -n.coeffs[&v];
+                let comb = unknown_on_none!(unknown_on_none!(p.scale(b))
+                    .add(&unknown_on_none!(n.scale(a))));
+                match normalize_ge(comb) {
+                    Ok(Some(c)) => {
+                        next.insert(c);
+                    }
+                    Ok(None) => (),
+                    Err(()) => return Cube::Unsat,
+                }
+            }
+        }
+        if next.len() > MAX_CONSTRAINTS {
+            return Cube::Unknown;
+        }
+        levels.push((v, with));
+        cur = next;
+    }
+
+    // Construct a model by assigning the variables in the reverse order of
+    // elimination. Each value is chosen to be as close to zero as possible.
+    let mut model = Model::new();
+    for (v, cons) in levels.iter().rev() {
+        let mut lo: Option<i128> = None;
+        let mut hi: Option<i128> = None;
+        for con in cons {
+            let a = con.coeffs[v];
+            let mut rest = con.clone();
+            rest.coeffs.remove(v);
+            let rest = unknown_on_none!(rest.eval(&model));
+            // a*v + rest >= 0
+            if a > 0 {
+                let bound = -rest.div_euclid(a);
+                lo = Some(lo.map_or(bound, |l| l.max(bound)));
+            } else {
+                let bound = rest.div_euclid(-a);
+                hi = Some(hi.map_or(bound, |h| h.min(bound)));
+            }
+        }
+        let val = match (lo, hi) {
+            (Some(l), Some(h)) if l > h => return Cube::Unknown,
+            (Some(l), _) if l > 0 => l,
+            (_, Some(h)) if h < 0 => h,
+            _ => 0,
+        };
+        model.insert(*v, val);
+    }
+    for (v, val) in solved.iter().rev() {
+        let val = unknown_on_none!(val.eval(&model));
+        model.insert(*v, val);
+    }
+
+    if lits.iter().all(|l| l.holds(&model) == Some(true)) {
+        Cube::Sat(model)
+    } else {
+        Cube::Unknown
+    }
+}
+
+/// Functions whose value is only constrained by congruence during validation
+#[derive(PartialEq, Eq, Hash)]
+enum Func {
+    Op(ast::Op),
+    Fn(ast::Fn),
+    Comp(ir::Foreign<ir::Param, ir::Component>),
+}
+
+/// An application of an uninterpreted function: the variable holding its
+/// result and its arguments.
+type App = (Var, Vec<ir::ExprIdx>);
+
+/// Evaluates propositions under a model using the real semantics of
+/// abstracted terms and checks that uninterpreted functions are consistent.
+struct Eval<'a> {
+    comp: &'a ir::Component,
+    model: &'a Model,
+    /// Encoding of the expressions used to find abstracted terms
+    exprs: &'a HashMap<ir::ExprIdx, Option<Lin>>,
+    /// Values of uninterpreted function applications
+    apps: HashMap<(Func, Vec<i128>), (i128, App)>,
+    /// Applications with equal arguments but different values
+    conflicts: Vec<(App, App)>,
+    /// Abstracted terms whose value in the model differs from the real one
+    mismatches: Vec<(ir::ExprIdx, Vec<ir::ExprIdx>, Vec<i128>, i128)>,
+}
+
+impl Eval<'_> {
+    /// Record the value of an uninterpreted function application and any
+    /// conflict with a previous application to the same arguments.
+    fn app(&mut self, f: Func, vals: Vec<i128>, app: App) -> i128 {
+        let val = self.model.get(&app.0).copied().unwrap_or(0);
+        let (v, prev) = self
+            .apps
+            .entry((f, vals))
+            .or_insert_with(|| (val, app.clone()));
+        if *v != val {
+            self.conflicts.push((prev.clone(), app));
+        }
+        val
+    }
+
+    /// Record the real value of a term and whether the model agrees with it.
+    fn interp(
+        &mut self,
+        e: ir::ExprIdx,
+        args: &[ir::ExprIdx],
+        vals: Vec<i128>,
+        real: i128,
+    ) -> i128 {
+        let term = Var::Term(e);
+        if self.exprs.get(&e) == Some(&Some(Lin::var(term)))
+            && self.model.get(&term).copied().unwrap_or(0) != real
+        {
+            self.mismatches.push((e, args.to_vec(), vals, real));
+        }
+        real
+    }
+
+    fn expr(&mut self, e: ir::ExprIdx) -> Option<i128> {
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => {
+                Some(self.model.get(&Var::Param(*p)).copied().unwrap_or(0))
+            }
+            ir::Expr::Concrete(n) => Some(*n as i128),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let (op, args) = (*op, [*lhs, *rhs]);
+                let l = self.expr(args[0])?;
+                let r = self.expr(args[1])?;
+                match op {
+                    ast::Op::Add => l.checked_add(r),
+                    ast::Op::Sub => l.checked_sub(r),
+                    ast::Op::Mul => Some(self.interp(
+                        e,
+                        &args,
+                        vec![l, r],
+                        l.checked_mul(r)?,
+                    )),
+                    ast::Op::Div if r != 0 => {
+                        Some(self.interp(e, &args, vec![l, r], l.div_euclid(r)))
+                    }
+                    ast::Op::Mod if r != 0 => {
+                        Some(self.interp(e, &args, vec![l, r], l.rem_euclid(r)))
+                    }
+                    ast::Op::Div | ast::Op::Mod => Some(self.app(
+                        Func::Op(op),
+                        vec![l, r],
+                        (Var::Term(e), args.to_vec()),
+                    )),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let (op, args) = (*op, args.clone());
+                let vals = args
+                    .iter()
+                    .map(|a| self.expr(*a))
+                    .collect::<Option<Vec<_>>>()?;
+                match eval_fn(op, &vals) {
+                    Some(real) => Some(self.interp(e, &args, vals, real)),
+                    None => {
+                        Some(self.app(Func::Fn(op), vals, (Var::Term(e), args)))
+                    }
+                }
+            }
+        }
+    }
+
+    fn time(&mut self, t: ir::TimeIdx) -> Option<i128> {
+        let ir::Time { event, offset } = self.comp.get(t);
+        let ev = self.model.get(&Var::Event(*event)).copied().unwrap_or(0);
+        ev.checked_add(self.expr(*offset)?)
+    }
+
+    fn time_sub(&mut self, ts: &ir::TimeSub) -> Option<i128> {
+        match ts {
+            ir::TimeSub::Unit(e) => self.expr(*e),
+            ir::TimeSub::Sym { l, r } => {
+                self.time(*l)?.checked_sub(self.time(*r)?)
+            }
+        }
+    }
+
+    fn cmp(op: &ir::Cmp, l: i128, r: i128) -> bool {
+        match op {
+            ir::Cmp::Gt => l > r,
+            ir::Cmp::Gte => l >= r,
+            ir::Cmp::Eq => l == r,
+        }
+    }
+
+    fn prop(&mut self, p: ir::PropIdx) -> Option<bool> {
+        match self.comp.get(p) {
+            ir::Prop::True => Some(true),
+            ir::Prop::False => Some(false),
+            ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => {
+                Some(Self::cmp(op, self.expr(*lhs)?, self.expr(*rhs)?))
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => {
+                Some(Self::cmp(op, self.time(*lhs)?, self.time(*rhs)?))
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { op, lhs, rhs }) => {
+                Some(Self::cmp(op, self.time_sub(lhs)?, self.time_sub(rhs)?))
+            }
+            ir::Prop::Not(p) => Some(!self.prop(*p)?),
+            ir::Prop::And(l, r) => Some(self.prop(*l)? & self.prop(*r)?),
+            ir::Prop::Or(l, r) => Some(self.prop(*l)? | self.prop(*r)?),
+            ir::Prop::Implies(l, r) => Some(!self.prop(*l)? | self.prop(*r)?),
+        }
+    }
+}
+
+/// `ceil(log2(n))` for `n >= 1`
+fn log2(n: i128) -> i128 {
+    (128 - (n - 1).leading_zeros()) as i128
+}
+
+/// Value of the function applied to the arguments if they are in its domain
+pub fn eval_fn(op: ast::Fn, args: &[i128]) -> Option<i128> {
+    match (op, args) {
+        (ast::Fn::Pow2, &[n]) if (0..=62).contains(&n) => Some(1 << n),
+        (ast::Fn::Log2, &[n]) if n >= 1 => Some(log2(n)),
+        (ast::Fn::BitRev, &[x, bits]) if (0..=64).contains(&bits) => {
+            let x = u64::try_from(x).ok()?;
+            Some(op.eval(vec![x, bits as u64]) as i128)
+        }
+        (ast::Fn::SinB | ast::Fn::CosB, &[num, den]) if den > 0 => {
+            let num = u64::try_from(num).ok()?;
+            let den = u64::try_from(den).ok()?;
+            Some(op.eval(vec![num, den]) as i128)
+        }
+        _ => None,
+    }
+}
+
+/// Built-in solver for the propositions of a single component.
+pub struct Solver<'a> {
+    comp: &'a ir::Component,
+    /// Encoding of the expressions. `None` if the encoding overflowed.
+    exprs: HashMap<ir::ExprIdx, Option<Lin>>,
+    /// Constraints defining the fresh variables
+    defs: Vec<Lit>,
+    fresh: u32,
+    /// Parameters of instances defined by applying the function that computes
+    /// an existential parameter to the instance's arguments
+    apps: Vec<(
+        ir::Foreign<ir::Param, ir::Component>,
+        Vec<ir::ExprIdx>,
+        ir::ParamIdx,
+    )>,
+    /// Equalities between parameters defined by the same application
+    background: Vec<Lit>,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(
+        comp: &'a ir::Component,
+        apps: Vec<(
+            ir::Foreign<ir::Param, ir::Component>,
+            Vec<ir::ExprIdx>,
+            ir::ParamIdx,
+        )>,
+    ) -> Self {
+        let mut first: HashMap<_, ir::ParamIdx> = HashMap::new();
+        let mut background = vec![];
+        for (f, args, p) in &apps {
+            let q = *first.entry((*f, args.clone())).or_insert(*p);
+            if q != *p {
+                let eq = Lin::var(Var::Param(*p)).sub(&Lin::var(Var::Param(q)));
+                background.extend(eq.map(Lit::Eq));
+            }
+        }
+        Self {
+            comp,
+            exprs: HashMap::new(),
+            defs: vec![],
+            fresh: 0,
+            apps,
+            background,
+        }
+    }
+
+    fn fresh(&mut self) -> Lin {
+        self.fresh += 1;
+        Lin::var(Var::Fresh(self.fresh))
+    }
+
+    fn expr(&mut self, e: ir::ExprIdx) -> Option<Lin> {
+        if let Some(lin) = self.exprs.get(&e) {
+            return lin.clone();
+        }
+        let lin = self.encode_expr(e);
+        self.exprs.insert(e, lin.clone());
+        lin
+    }
+
+    fn encode_expr(&mut self, e: ir::ExprIdx) -> Option<Lin> {
+        let term = Lin::var(Var::Term(e));
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => Some(Lin::var(Var::Param(*p))),
+            ir::Expr::Concrete(n) => Some(Lin::cons(*n as i128)),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let (op, lhs, rhs) = (*op, *lhs, *rhs);
+                let l = self.expr(lhs)?;
+                let r = self.expr(rhs)?;
+                match (op, l.as_const(), r.as_const()) {
+                    (ast::Op::Add, _, _) => l.add(&r),
+                    (ast::Op::Sub, _, _) => l.sub(&r),
+                    (ast::Op::Mul, Some(c), _) => r.scale(c),
+                    (ast::Op::Mul, _, Some(c)) => l.scale(c),
+                    (ast::Op::Mul, _, _) => Some(term),
+                    (ast::Op::Div, Some(a), Some(b)) if b != 0 => {
+                        Some(Lin::cons(a.div_euclid(b)))
+                    }
+                    (ast::Op::Mod, Some(a), Some(b)) if b != 0 => {
+                        Some(Lin::cons(a.rem_euclid(b)))
+                    }
+                    (ast::Op::Div | ast::Op::Mod, _, Some(b)) if b != 0 => {
+                        // l = b*q + r && 0 <= r < |b|
+                        let q = self.fresh();
+                        let rem = self.fresh();
+                        let def = l.sub(&q.scale(b)?)?.sub(&rem)?;
+                        self.defs.push(Lit::Eq(def));
+                        self.defs.push(Lit::Ge(rem.clone()));
+                        self.defs
+                            .push(Lit::Ge(Lin::cons(b.abs() - 1).sub(&rem)?));
+                        Some(if op == ast::Op::Div { q } else { rem })
+                    }
+                    (ast::Op::Div | ast::Op::Mod, _, _) => Some(term),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let op = *op;
+                let args = args
+                    .clone()
+                    .into_iter()
+                    .map(|a| self.expr(a))
+                    .collect::<Option<Vec<_>>>()?;
+                let consts =
+                    args.iter().map(Lin::as_const).collect::<Option<Vec<_>>>();
+                match consts.and_then(|c| eval_fn(op, &c)) {
+                    Some(n) => Some(Lin::cons(n)),
+                    None => Some(term),
+                }
+            }
+        }
+    }
+
+    fn time(&mut self, t: ir::TimeIdx) -> Option<Lin> {
+        let ir::Time { event, offset } = self.comp.get(t);
+        let event = *event;
+        self.expr(*offset)?.add(&Lin::var(Var::Event(event)))
+    }
+
+    fn time_sub(&mut self, ts: &ir::TimeSub) -> Option<Lin> {
+        match ts {
+            ir::TimeSub::Unit(e) => self.expr(*e),
+            ir::TimeSub::Sym { l, r } => self.time(*l)?.sub(&self.time(*r)?),
+        }
+    }
+
+    /// Encode `lhs op rhs` if `pos` is true and its negation otherwise.
+    fn cmp(op: &ir::Cmp, lhs: Lin, rhs: Lin, pos: bool) -> Option<Formula> {
+        let d = lhs.sub(&rhs)?;
+        let lit = |l| Formula::Lit(Lit::Ge(l));
+        Some(match (op, pos) {
+            (ir::Cmp::Gt, true) => lit(d.offset(-1)?),
+            (ir::Cmp::Gt, false) => lit(d.scale(-1)?),
+            (ir::Cmp::Gte, true) => lit(d),
+            (ir::Cmp::Gte, false) => lit(d.scale(-1)?.offset(-1)?),
+            (ir::Cmp::Eq, true) => Formula::Lit(Lit::Eq(d)),
+            (ir::Cmp::Eq, false) => Formula::Or(vec![
+                lit(d.offset(-1)?),
+                lit(d.scale(-1)?.offset(-1)?),
+            ]),
+        })
+    }
+
+    /// Encode the proposition if `pos` is true and its negation otherwise.
+    fn prop(&mut self, p: ir::PropIdx, pos: bool) -> Option<Formula> {
+        let t = || Formula::And(vec![]);
+        let f = || Formula::Or(vec![]);
+        let both = |l, r| {
+            if pos {
+                Formula::And(vec![l, r])
+            } else {
+                Formula::Or(vec![l, r])
+            }
+        };
+        match self.comp.get(p).clone() {
+            ir::Prop::True => Some(if pos { t() } else { f() }),
+            ir::Prop::False => Some(if pos { f() } else { t() }),
+            ir::Prop::Cmp(ir::CmpOp { op, lhs, rhs }) => {
+                Self::cmp(&op, self.expr(lhs)?, self.expr(rhs)?, pos)
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { op, lhs, rhs }) => {
+                Self::cmp(&op, self.time(lhs)?, self.time(rhs)?, pos)
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { op, lhs, rhs }) => {
+                Self::cmp(&op, self.time_sub(&lhs)?, self.time_sub(&rhs)?, pos)
+            }
+            ir::Prop::Not(p) => self.prop(p, !pos),
+            ir::Prop::And(l, r) => {
+                Some(both(self.prop(l, pos)?, self.prop(r, pos)?))
+            }
+            ir::Prop::Or(l, r) => {
+                let (l, r) = (self.prop(l, pos)?, self.prop(r, pos)?);
+                Some(if pos {
+                    Formula::Or(vec![l, r])
+                } else {
+                    Formula::And(vec![l, r])
+                })
+            }
+            ir::Prop::Implies(l, r) => {
+                let (l, r) = (self.prop(l, !pos)?, self.prop(r, pos)?);
+                Some(if pos {
+                    Formula::Or(vec![l, r])
+                } else {
+                    Formula::And(vec![l, r])
+                })
+            }
+        }
+    }
+
+    /// Check that the model falsifies the proposition under the real
+    /// semantics of all abstracted terms. If it does not, returns lemmas
+    /// that rule out the model. The lemmas are empty if the model could not
+    /// be evaluated.
+    fn validate(
+        &mut self,
+        model: &Model,
+        prop: ir::PropIdx,
+    ) -> Result<(), Vec<Formula>> {
+        let mut eval = Eval {
+            comp: self.comp,
+            model,
+            exprs: &self.exprs,
+            apps: HashMap::new(),
+            conflicts: vec![],
+            mismatches: vec![],
+        };
+        for (f, args, p) in &self.apps {
+            let Some(vals) = args
+                .iter()
+                .map(|a| eval.expr(*a))
+                .collect::<Option<Vec<_>>>()
+            else {
+                return Err(vec![]);
+            };
+            eval.app(Func::Comp(*f), vals, (Var::Param(*p), args.clone()));
+        }
+        let Some(val) = eval.prop(prop) else {
+            return Err(vec![]);
+        };
+        if !val && eval.conflicts.is_empty() {
+            return Ok(());
+        }
+        let Eval {
+            conflicts,
+            mismatches,
+            ..
+        } = eval;
+
+        // Congruence: equal arguments imply equal results
+        let mut lemmas = vec![];
+        for ((v1, args1), (v2, args2)) in conflicts {
+            let mut fs = vec![];
+            for (a1, a2) in args1.into_iter().zip(args2) {
+                fs.push(Self::neq(self.expr(a1), self.expr(a2)));
+            }
+            fs.push(Self::eq(Some(Lin::var(v1)), Some(Lin::var(v2))));
+            lemmas.extend(
+                fs.into_iter().collect::<Option<Vec<_>>>().map(Formula::Or),
+            );
+        }
+        // The real value of a term at the arguments in the model
+        for (e, args, vals, real) in mismatches {
+            let mut fs = vec![];
+            for (a, v) in args.into_iter().zip(vals) {
+                fs.push(Self::neq(self.expr(a), Some(Lin::cons(v))));
+            }
+            fs.push(Self::eq(
+                Some(Lin::var(Var::Term(e))),
+                Some(Lin::cons(real)),
+            ));
+            lemmas.extend(
+                fs.into_iter().collect::<Option<Vec<_>>>().map(Formula::Or),
+            );
+        }
+        Err(lemmas)
+    }
+
+    fn eq(l: Option<Lin>, r: Option<Lin>) -> Option<Formula> {
+        Self::cmp(&ir::Cmp::Eq, l?, r?, true)
+    }
+
+    fn neq(l: Option<Lin>, r: Option<Lin>) -> Option<Formula> {
+        Self::cmp(&ir::Cmp::Eq, l?, r?, false)
+    }
+
+    /// Search for a model of the literals and formulas by case-splitting on
+    /// disjunctions.
+    fn search(
+        &self,
+        mut lits: Vec<Lit>,
+        mut todo: Vec<Formula>,
+        budget: &mut usize,
+    ) -> Cube {
+        let mut ors = vec![];
+        while let Some(f) = todo.pop() {
+            match f {
+                Formula::Lit(l) => lits.push(l),
+                Formula::And(fs) => todo.extend(fs),
+                Formula::Or(fs) => ors.push(fs),
+            }
+        }
+        if *budget == 0 {
+            return Cube::Unknown;
+        }
+        *budget -= 1;
+
+        let model = match decide(&lits) {
+            Cube::Unsat => return Cube::Unsat,
+            Cube::Sat(model) => Some(model),
+            Cube::Unknown => None,
+        };
+        if ors.is_empty() {
+            return model.map_or(Cube::Unknown, Cube::Sat);
+        }
+
+        // Split on the first disjunction not satisfied by the model
+        let split = model.as_ref().map_or(Some(0), |m| {
+            ors.iter()
+                .position(|fs| Formula::Or(fs.clone()).holds(m) != Some(true))
+        });
+        let Some(split) = split else {
+            return model.map_or(Cube::Unknown, Cube::Sat);
+        };
+
+        let branches = ors.swap_remove(split);
+        let rest = ors.into_iter().map(Formula::Or).collect::<Vec<_>>();
+        let mut unknown = false;
+        for branch in branches {
+            let mut todo = rest.clone();
+            todo.push(branch);
+            match self.search(lits.clone(), todo, budget) {
+                Cube::Sat(model) => return Cube::Sat(model),
+                Cube::Unsat => (),
+                Cube::Unknown => unknown = true,
+            }
+        }
+        if unknown {
+            Cube::Unknown
+        } else {
+            Cube::Unsat
+        }
+    }
+
+    /// Check whether the proposition is valid.
+    pub fn check(&mut self, prop: ir::PropIdx) -> Res {
+        let Some(neg) = self.prop(prop, false) else {
+            return Res::Unknown;
+        };
+        let mut lemmas = vec![];
+        for _ in 0..MAX_REFINEMENTS {
+            let lits = self
+                .background
+                .iter()
+                .chain(self.defs.iter())
+                .cloned()
+                .collect();
+            let mut todo = lemmas.clone();
+            todo.push(neg.clone());
+            let mut budget = MAX_CUBES;
+            let model = match self.search(lits, todo, &mut budget) {
+                Cube::Unsat => return Res::Valid,
+                Cube::Unknown => return Res::Unknown,
+                Cube::Sat(model) => model,
+            };
+            match self.validate(&model, prop) {
+                Ok(()) => {
+                    return Res::Invalid(
+                        model
+                            .into_iter()
+                            .filter_map(|(v, n)| match v {
+                                Var::Param(p) => Some((p, n)),
+                                _ => None,
+                            })
+                            .collect(),
+                    )
+                }
+                Err(new) if new.is_empty() => return Res::Unknown,
+                Err(new) => lemmas.extend(new),
+            }
+        }
+        Res::Unknown
+    }
+}
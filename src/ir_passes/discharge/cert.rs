@@ -0,0 +1,281 @@
+//! Proof certificates for components.
+//!
+//! A certificate is an SMT-LIB script that encodes all the facts a
+//! component needs to prove, asserts that at least one of them is false, and
+//! records that the solver answered `unsat`. The header of the certificate is
+//! made up of `; key: value` comments so that it remains a valid script that
+//! can be replayed by any solver.
+//!
+//! The header also records the encoding options and a digest of the compiler
+//! version, the options, and the source files the component was checked in.
+//! A certificate is only trusted if the digest recomputed from the sources
+//! being verified matches it and its script is the one rebuilt from the
+//! facts of the component in the sources.
+use super::portfolio::{Answer, Portfolio};
+use super::{solver_cmd, Discharge};
+use crate::ir_visitor::{Construct, Visitor};
+use crate::{cmdline, options::Options, pipeline::Pipeline};
+use fil_utils::GlobalPositionTable;
+use itertools::Itertools;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Scripts of the certificates of the source components or the reason they
+/// cannot be produced
+type Scripts = BTreeMap<String, Result<String, String>>;
+
+/// Extension of certificate files
+const EXT: &str = "cert";
+
+/// Options that change how the facts of a component are encoded.
+pub fn options(solver_bv: Option<u8>) -> String {
+    match solver_bv {
+        Some(width) => format!("bv{width}"),
+        None => "int".to_string(),
+    }
+}
+
+/// Digest of the compiler version, the encoding options, and the contents of
+/// all the source files parsed so far. The files are hashed in the order of
+/// their contents so that the digest does not depend on where they are.
+pub fn digest(options: &str) -> String {
+    let files = GlobalPositionTable::as_ref().files();
+    let sources = (0..)
+        .map_while(|idx| files.get(idx).ok())
+        .map(|file| file.source().as_str())
+        .sorted();
+    let mut hasher = Sha256::new();
+    for part in [env!("CARGO_PKG_VERSION"), options]
+        .into_iter()
+        .chain(sources)
+    {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Write the certificate for the component into the directory.
+pub fn write(
+    dir: &Path,
+    comp: &str,
+    solver: cmdline::Solver,
+    options: &str,
+    script: &str,
+) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{comp}.{EXT}"));
+    let (name, _) = solver_cmd(solver, None);
+    let cert = format!(
+        "; filament proof certificate\n; component: {comp}\n; compiler: {}\n; options: {options}\n; digest: {}\n; solver: {name}\n; answer: unsat\n{script}",
+        env!("CARGO_PKG_VERSION"),
+        digest(options),
+    );
+    fs::write(&path, cert)?;
+    Ok(path)
+}
+
+/// Value of a header field in the certificate
+fn field<'a>(cert: &'a str, key: &str) -> Option<&'a str> {
+    cert.lines()
+        .map_while(|l| l.strip_prefix("; "))
+        .find_map(|l| l.strip_prefix(key)?.strip_prefix(": "))
+}
+
+/// Commands of a script without its comments
+fn commands(script: &str) -> impl Iterator<Item = &str> {
+    script.lines().filter(|l| !l.starts_with(';'))
+}
+
+/// Rebuild the scripts of the certificates of the components in the source
+/// program by running the passes up to the discharge pass, which encodes the
+/// facts of each component without proving them.
+fn rebuild(opts: &cmdline::VerifyCert) -> Result<Scripts, u64> {
+    let mut options = Options {
+        input: Some(opts.source.clone()),
+        library: opts.library.clone(),
+        check: true,
+        solver_bv: opts.solver_bv,
+        ..Options::default()
+    };
+    let passes = match Pipeline::new(options.clone()).passes() {
+        Ok(passes) => passes,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return Err(1);
+        }
+    };
+    options.passes = Some(
+        passes
+            .into_iter()
+            .take_while(|p| p != Discharge::name())
+            .collect(),
+    );
+    let pipeline = Pipeline::new(options.clone());
+    let mut resolver = pipeline.resolver();
+    let mut ctx = pipeline.verify(pipeline.build(&mut resolver, None)?)?;
+
+    let mut pass = Discharge::rebuild_certs(&options, &mut ctx);
+    for idx in ctx.comps.idx_iter() {
+        pass.clear_data();
+        pass.visit((idx, &options, &mut ctx).into());
+    }
+    Ok(pass
+        .rebuilt
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .collect())
+}
+
+/// Check a single certificate against the options, the digest of the
+/// sources being verified, and the script rebuilt from the sources. Adds the
+/// component of the certificate to `certified`.
+fn verify_one(
+    path: &Path,
+    options: &str,
+    digest: &str,
+    scripts: &Scripts,
+    certified: &mut Vec<String>,
+    opts: &cmdline::VerifyCert,
+) -> Result<String, String> {
+    let cert = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let comp = field(&cert, "component")
+        .ok_or_else(|| "not a proof certificate".to_string())?;
+    certified.push(comp.to_string());
+    if field(&cert, "answer") != Some("unsat") {
+        return Err(format!(
+            "certificate for `{comp}' does not record a proof"
+        ));
+    }
+    match field(&cert, "options") {
+        Some(opt) if opt == options => (),
+        Some(opt) => {
+            return Err(format!(
+                "certificate for `{comp}' was produced with `{opt}' encoding but `{options}' is required"
+            ))
+        }
+        None => {
+            return Err(format!(
+                "certificate for `{comp}' does not record its options"
+            ))
+        }
+    }
+    if field(&cert, "digest") != Some(digest) {
+        return Err(format!(
+            "certificate for `{comp}' does not match the sources of {} or the compiler version",
+            opts.source.display()
+        ));
+    }
+    match scripts.get(comp) {
+        None => {
+            return Err(format!(
+                "`{comp}' is not a component of {}",
+                opts.source.display()
+            ))
+        }
+        Some(Err(reason)) => {
+            return Err(format!(
+                "cannot rebuild the facts of `{comp}': {reason}"
+            ))
+        }
+        Some(Ok(script)) if !commands(&cert).eq(commands(script)) => {
+            return Err(format!(
+                "certificate for `{comp}' does not encode the facts of `{comp}' in {}",
+                opts.source.display()
+            ))
+        }
+        Some(Ok(_)) => (),
+    }
+    let solver = match opts.solver {
+        Some(solver) => solver,
+        None => field(&cert, "solver")
+            .ok_or_else(|| "certificate does not name a solver".to_string())?
+            .parse()?,
+    };
+    if matches!(solver, cmdline::Solver::Builtin) {
+        return Err(
+            "certificates must be checked by an external solver".to_string()
+        );
+    }
+    match Portfolio::new(vec![solver], opts.solver_timeout).run(&cert) {
+        Answer::Unsat => Ok(comp.to_string()),
+        Answer::Sat(_) => Err(format!(
+            "solver found a counterexample to the facts of `{comp}'"
+        )),
+        Answer::Unknown(reason) => {
+            Err(format!("could not check the proof of `{comp}': {reason}"))
+        }
+    }
+}
+
+/// Check the provided certificates and the certificates in the provided
+/// directories against the source program. Returns the number of
+/// certificates that failed to check and of source components without a
+/// certificate.
+pub fn verify(opts: &cmdline::VerifyCert) -> Result<(), u64> {
+    // Checking the program loads all of its source files
+    let scripts = rebuild(opts)?;
+    let options = options(opts.solver_bv);
+    let digest = digest(&options);
+
+    let mut paths = vec![];
+    for path in &opts.certs {
+        if !path.is_dir() {
+            paths.push(path.clone());
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(path) else {
+            eprintln!("error: cannot read directory {}", path.display());
+            return Err(1);
+        };
+        let mut certs = entries
+            .filter_map(|e| Some(e.ok()?.path()))
+            .filter(|p| p.extension().map_or(false, |ext| ext == EXT))
+            .collect::<Vec<_>>();
+        certs.sort();
+        paths.extend(certs);
+    }
+    if paths.is_empty() {
+        eprintln!("error: no certificates provided");
+        return Err(1);
+    }
+
+    let mut failed = 0;
+    let mut certified = vec![];
+    for path in paths {
+        match verify_one(
+            &path,
+            &options,
+            &digest,
+            &scripts,
+            &mut certified,
+            opts,
+        ) {
+            Ok(comp) => println!("verified `{comp}' ({})", path.display()),
+            Err(e) => {
+                eprintln!("error: {}: {e}", path.display());
+                failed += 1;
+            }
+        }
+    }
+    // Every component of the source must be certified
+    for comp in scripts.keys().filter(|c| !certified.contains(c)) {
+        eprintln!(
+            "error: component `{comp}' of {} has no certificate",
+            opts.source.display()
+        );
+        failed += 1;
+    }
+    if failed > 0 {
+        Err(failed)
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+//! Designs reproducing counterexamples found by [super::Discharge].
+//!
+//! A counterexample to a fact of the entrypoint assigns a value to each of its
+//! parameters. The design instantiates the entrypoint with these values inside
+//! a concrete `main` component whose ports mirror the ones of the entrypoint.
+//! Bundle ports are split into one port per element because the simulation
+//! harness only supports scalar ports. The stimulus file assigns distinct
+//! values to the inputs of each transaction so that values read outside of
+//! their availability interval are visible in the outputs.
+use super::builtin;
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use fil_utils as utils;
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of transactions in the stimulus
+const TXNS: u64 = 4;
+
+/// Name of the instance of the entrypoint in the design
+const INST: &str = "cex";
+
+/// A scalar port of the design
+struct Elem {
+    name: String,
+    /// Access of the element in the bundle port of the entrypoint
+    access: String,
+    width: u64,
+    start: String,
+    end: String,
+}
+
+/// A signature port of the entrypoint
+struct SigPort {
+    name: String,
+    /// Argument passing the port to the entrypoint
+    arg: String,
+    /// Declaration of the bundle if the port is a bundle
+    bundle: Option<String>,
+    elems: Vec<Elem>,
+}
+
+pub struct Counterexample<'a> {
+    comp: &'a ir::Component,
+    /// Values of the parameters in the counterexample
+    env: HashMap<ir::ParamIdx, u64>,
+}
+
+impl<'a> Counterexample<'a> {
+    pub fn new(
+        comp: &'a ir::Component,
+        env: HashMap<ir::ParamIdx, u64>,
+    ) -> Self {
+        Self { comp, env }
+    }
+
+    /// Write the design and its stimulus into the directory. Returns the path
+    /// of the design.
+    pub fn write(
+        &self,
+        dir: &Path,
+        name: &str,
+        input: &Path,
+        header: &[String],
+    ) -> Result<PathBuf, String> {
+        let design = self.design(input, header)?;
+        let stimulus = self.stimulus()?;
+        let write = || -> std::io::Result<PathBuf> {
+            fs::create_dir_all(dir)?;
+            let path = dir.join(format!("{name}.fil"));
+            fs::write(&path, design)?;
+            fs::write(dir.join(format!("{name}.fil.data")), stimulus)?;
+            Ok(path)
+        };
+        write().map_err(|e| format!("cannot write counterexample: {e}"))
+    }
+
+    /// Value of the expression in the counterexample
+    fn eval(
+        &self,
+        e: ir::ExprIdx,
+        idxs: &HashMap<ir::ParamIdx, u64>,
+    ) -> Option<u64> {
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => {
+                idxs.get(p).or_else(|| self.env.get(p)).copied()
+            }
+            ir::Expr::Concrete(n) => Some(*n),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.eval(*lhs, idxs)?;
+                let r = self.eval(*rhs, idxs)?;
+                match op {
+                    ast::Op::Add => l.checked_add(r),
+                    ast::Op::Sub => l.checked_sub(r),
+                    ast::Op::Mul => l.checked_mul(r),
+                    ast::Op::Div => l.checked_div(r),
+                    ast::Op::Mod => l.checked_rem(r),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| self.eval(*a, idxs).map(|v| v as i128))
+                    .collect::<Option<Vec<_>>>()?;
+                let v = builtin::eval_fn(*op, &args)?;
+                u64::try_from(v).ok()
+            }
+        }
+    }
+
+    /// Source representation of the expression with the parameters of the
+    /// counterexample replaced by their values.
+    fn expr(&self, e: ir::ExprIdx) -> String {
+        if let Some(n) = self.eval(e, &HashMap::new()) {
+            return n.to_string();
+        }
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => self.comp.display(*p),
+            ir::Expr::Concrete(n) => n.to_string(),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                format!("({}{op}{})", self.expr(*lhs), self.expr(*rhs))
+            }
+            ir::Expr::Fn { op, args } => format!(
+                "{op}({})",
+                args.iter().map(|a| self.expr(*a)).join(", ")
+            ),
+        }
+    }
+
+    fn time(&self, t: ir::TimeIdx) -> String {
+        let ir::Time { event, offset } = self.comp.get(t);
+        let ev = self.comp.display(*event);
+        match self.expr(*offset).as_str() {
+            "0" => ev,
+            offset => format!("{ev}+{offset}"),
+        }
+    }
+
+    fn concrete_time(
+        &self,
+        t: ir::TimeIdx,
+        idxs: &HashMap<ir::ParamIdx, u64>,
+    ) -> Option<String> {
+        let ir::Time { event, offset } = self.comp.get(t);
+        let offset = self.eval(*offset, idxs)?;
+        let ev = self.comp.display(*event);
+        Some(if offset == 0 {
+            ev
+        } else {
+            format!("{ev}+{offset}")
+        })
+    }
+
+    /// The ports of the entrypoint split into scalar ports
+    fn port(&self, idx: ir::PortIdx) -> Result<SigPort, String> {
+        let name = self.comp.display(idx);
+        let ir::Port { width, live, .. } = self.comp.get(idx);
+        let ir::Liveness { idxs, lens, range } = live;
+        let scalar = idx.is_not_bundle(self.comp);
+        let err = |what: &str| {
+            format!("the {what} of port `{name}' cannot be evaluated")
+        };
+        let lens = lens
+            .iter()
+            .map(|l| self.eval(*l, &HashMap::new()).map(|l| l as usize))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| err("size"))?;
+        let ranges = lens.iter().map(|l| (0, *l)).collect_vec();
+        let mut elems = vec![];
+        for (i, vals) in utils::all_indices(ranges).into_iter().enumerate() {
+            let bind: HashMap<_, _> = idxs
+                .iter()
+                .copied()
+                .zip(vals.iter().map(|v| *v as u64))
+                .collect();
+            let (name, access) = if scalar {
+                (name.clone(), String::new())
+            } else {
+                let access = vals.iter().map(|v| format!("{{{v}}}")).join("");
+                (format!("{name}_{i}"), access)
+            };
+            elems.push(Elem {
+                name,
+                access,
+                width: self.eval(*width, &bind).ok_or_else(|| err("width"))?,
+                start: self
+                    .concrete_time(range.start, &bind)
+                    .ok_or_else(|| err("availability"))?,
+                end: self
+                    .concrete_time(range.end, &bind)
+                    .ok_or_else(|| err("availability"))?,
+            });
+        }
+        let bundle = (!scalar).then(|| {
+            format!(
+                "bundle {name}{}: for<{}> [{}, {}] {};",
+                lens.iter().map(|l| format!("[{l}]")).join(""),
+                idxs.iter().map(|p| self.comp.display(*p)).join(", "),
+                self.time(range.start),
+                self.time(range.end),
+                self.expr(*width)
+            )
+        });
+        let arg = if scalar {
+            name.clone()
+        } else {
+            format!(
+                "{name}{}",
+                lens.iter().map(|l| format!("{{0..{l}}}")).join("")
+            )
+        };
+        Ok(SigPort {
+            name,
+            arg,
+            bundle,
+            elems,
+        })
+    }
+
+    fn inputs(&self) -> Result<Vec<SigPort>, String> {
+        self.comp.inputs().map(|(idx, _)| self.port(idx)).collect()
+    }
+
+    fn outputs(&self) -> Result<Vec<SigPort>, String> {
+        self.comp.outputs().map(|(idx, _)| self.port(idx)).collect()
+    }
+
+    /// The design instantiating the entrypoint
+    fn design(
+        &self,
+        input: &Path,
+        header: &[String],
+    ) -> Result<String, String> {
+        let comp_name = self.comp.name.as_ref().unwrap().to_string();
+        let import = format!("import \"{}\";\n", input.display());
+        let header = header.iter().map(|l| format!("// {l}\n")).join("");
+        if comp_name == "main" {
+            if !self.comp.param_args().is_empty() {
+                return Err("the design instantiating component `main' would also be called `main'. Rename the component or select another one with `--toplevel`".to_string());
+            }
+            // The entrypoint is already concrete
+            return Ok(format!("{header}{import}"));
+        }
+
+        let mut events = vec![];
+        let mut interfaces = vec![];
+        for ev in self.comp.event_args() {
+            let name = self.comp.display(*ev);
+            let event = self.comp.get(*ev);
+            let none = HashMap::new();
+            let delay = match &event.delay {
+                ir::TimeSub::Unit(e) => self.eval(*e, &none),
+                ir::TimeSub::Sym { l, r } => {
+                    let (l, r) = (self.comp.get(*l), self.comp.get(*r));
+                    let lo = self.eval(l.offset, &none);
+                    let ro = self.eval(r.offset, &none);
+                    (l.event == r.event).then(|| lo?.checked_sub(ro?)).flatten()
+                }
+            }
+            .ok_or_else(|| {
+                format!("the delay of event `{name}' cannot be evaluated")
+            })?;
+            events.push(format!("{name}: {delay}"));
+            let info = self.comp.get(event.info).as_event();
+            if let Some(port) = info.and_then(|i| i.interface_name.as_ref()) {
+                interfaces.push(format!("    {port}: interface[{name}],\n"));
+            }
+        }
+
+        let (inputs, outputs) = (self.inputs()?, self.outputs()?);
+        let decl = |p: &SigPort| {
+            p.elems
+                .iter()
+                .map(|e| {
+                    format!(
+                        "    {}: [{}, {}] {},\n",
+                        e.name, e.start, e.end, e.width
+                    )
+                })
+                .join("")
+        };
+        let mut body = vec![];
+        for p in &inputs {
+            if let Some(bundle) = &p.bundle {
+                body.push(bundle.clone());
+                body.extend(
+                    p.elems.iter().map(|e| {
+                        format!("{}{} = {};", p.name, e.access, e.name)
+                    }),
+                );
+            }
+        }
+        let params = self
+            .comp
+            .param_args()
+            .iter()
+            .map(|p| self.env.get(p).copied().unwrap_or(0))
+            .collect_vec();
+        body.push(format!(
+            "{INST} := new {comp_name}{}<{}>({});",
+            if params.is_empty() {
+                String::new()
+            } else {
+                format!("[{}]", params.iter().join(", "))
+            },
+            self.comp
+                .event_args()
+                .iter()
+                .map(|e| self.comp.display(*e))
+                .join(", "),
+            inputs.iter().map(|p| &p.arg).join(", ")
+        ));
+        for p in &outputs {
+            body.extend(p.elems.iter().map(|e| {
+                format!("{} = {INST}.{}{};", e.name, p.name, e.access)
+            }));
+        }
+
+        Ok(format!(
+            "{header}{import}\ncomp main<{}>(\n{}{}) -> (\n{}) {{\n{}}}\n",
+            events.join(", "),
+            interfaces.join(""),
+            inputs.iter().map(decl).join(""),
+            outputs.iter().map(decl).join(""),
+            body.iter().map(|c| format!("    {c}\n")).join("")
+        ))
+    }
+
+    /// Inputs for the transactions of the design
+    fn stimulus(&self) -> Result<String, String> {
+        let inputs = self.inputs()?;
+        let fields = inputs
+            .iter()
+            .flat_map(|p| p.elems.iter())
+            .enumerate()
+            .map(|(k, e)| {
+                let vals = (0..TXNS).map(|t| {
+                    let v = k as u64 * TXNS + t + 1;
+                    if e.width < 64 {
+                        v & ((1 << e.width) - 1)
+                    } else {
+                        v
+                    }
+                });
+                format!("  \"{}\": [{}]", e.name, vals.format(", "))
+            })
+            .collect_vec();
+        Ok(if fields.is_empty() {
+            "{}\n".to_string()
+        } else {
+            format!("{{\n{}\n}}\n", fields.join(",\n"))
+        })
+    }
+}
@@ -0,0 +1,1847 @@
+mod bounds;
+pub(super) mod builtin;
+mod cert;
+mod counterexample;
+mod portfolio;
+mod profile;
+mod replay;
+
+pub use cert::verify as verify_certs;
+pub use replay::replay;
+
+use crate::ir_visitor::{Action, Construct, Visitor, VisitorData};
+use crate::options;
+use crate::{log_time, time};
+use codespan_reporting::diagnostic::Diagnostic;
+use codespan_reporting::{diagnostic as cr, term};
+use easy_smt as smt;
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use fil_utils::{codes, GlobalPositionTable, Reported};
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use std::collections::HashMap;
+use std::iter;
+use std::path::{Path, PathBuf};
+use term::termcolor::{ColorChoice, StandardStream};
+
+/// Command and arguments used to start an external solver. The timeout is in
+/// milliseconds and applies to each query.
+pub(super) fn solver_cmd(
+    solver: options::Solver,
+    timeout: Option<u64>,
+) -> (&'static str, Vec<String>) {
+    let (name, args): (_, &[&str]) = match solver {
+        options::Solver::Z3 => ("z3", &["-smt2", "-in"]),
+        options::Solver::Boolector => ("boolector", &["--incremental"]),
+        options::Solver::CVC5 => {
+            ("cvc5", &["--incremental", "--force-logic=ALL"])
+        }
+        options::Solver::Bitwuzla => ("bitwuzla", &[]),
+        options::Solver::Builtin => {
+            unreachable!("built-in solver does not use an external process")
+        }
+    };
+    let mut args = args.iter().map(|a| a.to_string()).collect_vec();
+    if let Some(ms) = timeout {
+        args.push(match solver {
+            options::Solver::Z3 => format!("-t:{ms}"),
+            options::Solver::CVC5 => format!("--tlimit-per={ms}"),
+            options::Solver::Bitwuzla => format!("--time-limit-per={ms}"),
+            // Boolector only supports a time limit in seconds
+            options::Solver::Boolector => {
+                format!("--time={}", (ms + 999) / 1000)
+            }
+            options::Solver::Builtin => unreachable!(),
+        });
+    }
+    (name, args)
+}
+
+/// Maximum width of the bitvector encoding used by solvers that only support
+/// bitvectors
+const DEFAULT_BV_SIZE: u8 = 64;
+
+/// Maximum number of entries in the lookup tables of the functions of a
+/// component
+const MAX_TABLE: usize = 1024;
+
+/// Source location of the primary label of the diagnostic as
+/// `file:line:column`
+fn location(diag: &cr::Diagnostic<usize>) -> Option<String> {
+    let label = diag.labels.first()?;
+    replay::span_location(label.file_id, label.range.start)
+}
+
+/// Result of checking the satisfiability of a goal with the external solver
+enum Outcome {
+    Sat(Assign),
+    Unsat,
+    /// The solver could not decide the goal. Contains the reason why.
+    Unknown(String),
+}
+
+#[derive(Default)]
+struct Assign(Vec<(ir::ParamIdx, String)>);
+
+impl Assign {
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn display(&self, ctx: &ir::Component) -> String {
+        self.0
+            .iter()
+            .filter_map(|(k, v)| {
+                let v = Self::bv_value(v).unwrap_or_else(|| v.clone());
+                // Attempt to parse value as a number
+                match v.parse::<i64>() {
+                    Ok(v) if v == 0 => None,
+                    _ => Some(format!("{} = {v}", ctx.display(*k))),
+                }
+            })
+            .join(", ")
+    }
+
+    /// Values of the parameters if they are all natural numbers
+    fn values(
+        &self,
+        ctx: &ir::Component,
+    ) -> Result<HashMap<ir::ParamIdx, u64>, String> {
+        self.0
+            .iter()
+            .map(|(k, v)| {
+                let v = Self::bv_value(v).unwrap_or_else(|| v.clone());
+                match v.parse::<u64>() {
+                    Ok(n) => Ok((*k, n)),
+                    Err(_) => Err(format!(
+                        "the value `{v}' of parameter `{}' is not a natural number",
+                        ctx.display(*k)
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    /// Decimal value of a bitvector literal like `#b101`, `#x5`, or
+    /// `(_ bv5 3)`.
+    fn bv_value(v: &str) -> Option<String> {
+        let n = if let Some(b) = v.strip_prefix("#b") {
+            u128::from_str_radix(b, 2).ok()?
+        } else if let Some(x) = v.strip_prefix("#x") {
+            u128::from_str_radix(x, 16).ok()?
+        } else {
+            v.strip_prefix("(_ bv")?.split(' ').next()?.parse().ok()?
+        };
+        Some(n.to_string())
+    }
+}
+
+/// Pass to discharge top-level `assert` statements in the IR and turn them into
+/// `assume` if they are true. Any assertions within the body are left as-is.
+/// Run [super::HoistFacts] before this pass to ensure that all facts are
+/// top-level.
+pub struct Discharge {
+    /// The external solver. Started when the first query that cannot be
+    /// decided by the built-in solver is encountered.
+    sol: Option<smt::Context>,
+    /// Maximum width of the bitvector encoding if it is used
+    bv_size: Option<u8>,
+    /// Width of the bitvector encoding of the current component
+    width: u8,
+    /// Which solver are we using
+    sol_base: options::Solver,
+    /// File to dump interactions with the external solver in
+    replay_file: Option<String>,
+    /// The log of interactions with the external solver once it is started
+    log: Option<replay::Log>,
+    /// Timeout in milliseconds for each query to the external solver
+    timeout: Option<u64>,
+    /// Solvers to run in parallel instead of the incremental solver
+    portfolio: Option<portfolio::Portfolio>,
+    /// Commands defining the encoding when using a portfolio. The solver
+    /// context is only used to build expressions in this case.
+    script: Vec<String>,
+    /// Directory to emit proof certificates into
+    cert_dir: Option<PathBuf>,
+    /// Scripts of the certificates of the components or the reason they
+    /// cannot be produced. Only set when the certificates are rebuilt
+    /// without proving the facts.
+    rebuilt: Option<Vec<(String, Result<String, String>)>>,
+    /// Are we in a scoped context?
+    scoped: bool,
+    /// Defined global functions
+    func_map: HashMap<ast::Fn, smt::SExpr>,
+    /// Defined functions for `some` parameters on components
+    comp_param_map: HashMap<ir::Foreign<ir::Param, ir::Component>, smt::SExpr>,
+    /// Name, number of arguments, and source description of the function
+    /// defining each non-opaque `some` parameter
+    comp_params: LinkedHashMap<
+        ir::Foreign<ir::Param, ir::Component>,
+        (String, usize, String),
+    >,
+    /// Arguments of the functions whose values are added to the encoding of
+    /// the current component
+    table: Vec<(ast::Fn, Vec<u64>)>,
+    /// Has the current component been encoded in the external solver?
+    encoded: bool,
+    /// Instances in the current component
+    instances: Vec<ir::InstIdx>,
+
+    // Defined names
+    /// Parameters deleted by bundle elimination leave gaps in the indices
+    param_map: HashMap<ir::ParamIdx, smt::SExpr>,
+    ev_map: ir::DenseIndexInfo<ir::Event, smt::SExpr>,
+    // Composite expressions
+    expr_map: ir::DenseIndexInfo<ir::Expr, smt::SExpr>,
+    time_map: ir::DenseIndexInfo<ir::Time, smt::SExpr>,
+    // Propositions
+    prop_map: ir::DenseIndexInfo<ir::Prop, smt::SExpr>,
+    // Whether the evaluation of a term overflows the bitvector encoding
+    expr_ovf: ir::DenseIndexInfo<ir::Expr, smt::SExpr>,
+    time_ovf: ir::DenseIndexInfo<ir::Time, smt::SExpr>,
+    // Facts that the bitvector encoding cannot decide and the reason why
+    inexact: HashMap<ir::PropIdx, String>,
+    // Facts that have no counterexample in the bitvector encoding but cannot
+    // be proven for integers. Contains the message and explanation reported
+    // for the fact.
+    wraparound: HashMap<ir::PropIdx, (String, String)>,
+    // Propositions that have already been checked
+    checked: HashMap<ir::PropIdx, Option<Assign>>,
+    // Propositions that could not be decided and the reason why
+    undecided: HashMap<ir::PropIdx, String>,
+    /// Error encountered while starting the external solver
+    sol_err: Option<String>,
+
+    // counter for activation literals generated
+    act_lit_count: u32,
+
+    /// Report the unsatisfied constraint and generate a model
+    show_models: bool,
+    /// Directory to write designs reproducing counterexamples into
+    cex_dir: Option<PathBuf>,
+    /// The program file imported by the counterexample designs
+    input: Option<PathBuf>,
+    /// Components whose counterexamples are written
+    entrypoints: Vec<ir::CompIdx>,
+    /// Parameters included in every model of the current component
+    model_params: Vec<ir::ParamIdx>,
+    /// Designs reproducing the counterexamples to facts or the reason why
+    /// they could not be written
+    cex_files: HashMap<ir::PropIdx, Result<PathBuf, String>>,
+    /// Statistics for each query if profiling is enabled
+    profile: Option<profile::Profile>,
+
+    to_prove: Vec<ir::Fact>,
+
+    // Diagnostics to be reported
+    diagnostics: Vec<cr::Diagnostic<usize>>,
+    /// Number of errors encountered
+    error_count: u64,
+}
+
+impl Discharge {
+    /// Configure solver to use in this pass
+    fn conf_solver(
+        solver: options::Solver,
+        timeout: Option<u64>,
+        log: Option<replay::Log>,
+    ) -> std::io::Result<smt::Context> {
+        let (name, s_opts) = solver_cmd(solver, timeout);
+        log::debug!("Using {name} solver");
+        smt::ContextBuilder::new()
+            .replay_file(log)
+            .solver(name, s_opts)
+            .build()
+            .map_err(|e| {
+                std::io::Error::new(e.kind(), format!("`{name}': {e}"))
+            })
+    }
+
+    /// The external solver.
+    /// REQUIRES: The solver has been started using [Self::start_solver].
+    fn sol(&self) -> &smt::Context {
+        self.sol.as_ref().expect("external solver was not started")
+    }
+
+    fn sol_mut(&mut self) -> &mut smt::Context {
+        self.sol.as_mut().expect("external solver was not started")
+    }
+
+    /// Start the external solver if it has not been started yet. Returns false if the solver could not be started.
+    fn start_solver(&mut self) -> bool {
+        if self.sol.is_some() {
+            return true;
+        }
+        if self.sol_err.is_some() {
+            return false;
+        }
+        if let Some(file) = &self.replay_file {
+            let solver = self
+                .portfolio
+                .as_ref()
+                .map_or(self.sol_base, |p| p.solvers()[0]);
+            self.log = Some(replay::Log::create(file, solver).unwrap());
+        }
+        let sol = if self.portfolio.is_some() {
+            smt::ContextBuilder::new().without_solver().build()
+        } else {
+            Self::conf_solver(self.sol_base, self.timeout, self.log.clone())
+        };
+        match sol {
+            Ok(sol) => self.sol = Some(sol),
+            Err(e) => {
+                log::warn!("Failed to start external solver {e}");
+                self.sol_err = Some(e.to_string());
+                return false;
+            }
+        }
+        if self.portfolio.is_none() {
+            self.sol_mut().push_many(1).unwrap();
+        }
+        true
+    }
+
+    /// Declare a function in the external solver
+    fn declare_fun(
+        &mut self,
+        name: impl Into<String> + AsRef<str>,
+        args: Vec<smt::SExpr>,
+        out: smt::SExpr,
+    ) -> smt::SExpr {
+        if self.portfolio.is_none() {
+            return self.sol_mut().declare_fun(name, args, out).unwrap();
+        }
+        let sol = self.sol();
+        let cmd = format!(
+            "(declare-fun {} ({}) {})",
+            name.as_ref(),
+            args.iter().map(|a| sol.display(*a)).join(" "),
+            sol.display(out)
+        );
+        let f = sol.atom(name);
+        self.script.push(cmd);
+        f
+    }
+
+    /// Define a constant in the external solver
+    fn define_const(
+        &mut self,
+        name: impl Into<String> + AsRef<str>,
+        sort: smt::SExpr,
+        val: smt::SExpr,
+    ) -> smt::SExpr {
+        if self.portfolio.is_none() {
+            return self.sol_mut().define_const(name, sort, val).unwrap();
+        }
+        let sol = self.sol();
+        let cmd = format!(
+            "(define-fun {} () {} {})",
+            name.as_ref(),
+            sol.display(sort),
+            sol.display(val)
+        );
+        let c = sol.atom(name);
+        self.script.push(cmd);
+        c
+    }
+
+    /// Add an assertion to the external solver
+    fn assert(&mut self, e: smt::SExpr) {
+        if self.portfolio.is_none() {
+            return self.sol_mut().assert(e).unwrap();
+        }
+        let cmd = format!("(assert {})", self.sol().display(e));
+        self.script.push(cmd);
+    }
+
+    fn app(&mut self, f: smt::SExpr, args: Vec<smt::SExpr>) -> smt::SExpr {
+        if args.is_empty() {
+            f
+        } else {
+            self.sol().list(iter::once(f).chain(args).collect_vec())
+        }
+    }
+
+    fn sort(&self) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bit_vec_sort(self.sol().numeral(self.width))
+        } else {
+            self.sol().int_sort()
+        }
+    }
+    /// The number in the encoding. Numbers that are too large for the
+    /// bitvector encoding are truncated and marked as overflowing by
+    /// [Self::expr_overflow].
+    fn num(&self, n: u64) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            let n = if self.width < 64 {
+                n & ((1 << self.width) - 1)
+            } else {
+                n
+            };
+            self.sol().binary(self.width as usize, n)
+        } else {
+            self.sol().numeral(n)
+        }
+    }
+    fn plus(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvadd(l, r)
+        } else {
+            self.sol().plus(l, r)
+        }
+    }
+    fn sub(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvsub(l, r)
+        } else {
+            self.sol().sub(l, r)
+        }
+    }
+    fn times(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvmul(l, r)
+        } else {
+            self.sol().times(l, r)
+        }
+    }
+    fn div(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvudiv(l, r)
+        } else {
+            self.sol().div(l, r)
+        }
+    }
+    fn modulo(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvurem(l, r)
+        } else {
+            self.sol().modulo(l, r)
+        }
+    }
+    fn gt(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvugt(l, r)
+        } else {
+            self.sol().gt(l, r)
+        }
+    }
+    fn gte(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        if self.bv_size.is_some() {
+            self.sol().bvuge(l, r)
+        } else {
+            self.sol().gte(l, r)
+        }
+    }
+    fn eq(&self, l: smt::SExpr, r: smt::SExpr) -> smt::SExpr {
+        self.sol().eq(l, r)
+    }
+    /// Zero-extend the bitvector to twice the width of the encoding
+    fn zero_extend(&self, e: smt::SExpr) -> smt::SExpr {
+        let sol = self.sol();
+        let ext = sol.list(vec![
+            sol.atom("_"),
+            sol.atom("zero_extend"),
+            sol.numeral(self.width),
+        ]);
+        sol.list(vec![ext, e])
+    }
+
+    /// Does the evaluation of the expression or any of its sub-expressions
+    /// overflow the bitvector encoding?
+    /// REQUIRES: The sub-expressions have already been defined.
+    fn expr_overflow(&self, expr: &ir::Expr) -> smt::SExpr {
+        let sol = self.sol();
+        match expr {
+            ir::Expr::Param(_) => sol.false_(),
+            ir::Expr::Concrete(n) => {
+                if self.width < 64 && n >> self.width != 0 {
+                    sol.true_()
+                } else {
+                    sol.false_()
+                }
+            }
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let (l, r) = (self.expr_map[*lhs], self.expr_map[*rhs]);
+                let here = match op {
+                    // The sum wraps around iff it is smaller than an operand
+                    ast::Op::Add => sol.bvult(sol.bvadd(l, r), l),
+                    ast::Op::Sub => sol.bvult(l, r),
+                    // The high bits of the full product are not zero
+                    ast::Op::Mul => {
+                        let w = self.width as i32;
+                        let prod =
+                            sol.bvmul(self.zero_extend(l), self.zero_extend(r));
+                        let high = sol.extract(2 * w - 1, w, prod);
+                        sol.not(sol.eq(high, self.num(0)))
+                    }
+                    ast::Op::Div | ast::Op::Mod => sol.eq(r, self.num(0)),
+                };
+                sol.or_many([self.expr_ovf[*lhs], self.expr_ovf[*rhs], here])
+            }
+            // Uninterpreted functions only overflow through their arguments
+            ir::Expr::Fn { args, .. } => {
+                sol.or_many(args.iter().map(|a| self.expr_ovf[*a]))
+            }
+        }
+    }
+
+    /// Does the evaluation of a term in the proposition overflow the
+    /// bitvector encoding?
+    fn prop_overflow(
+        &self,
+        prop: ir::PropIdx,
+        comp: &ir::Component,
+    ) -> smt::SExpr {
+        let sol = self.sol();
+        let time = |t: ir::TimeIdx| self.time_ovf[t];
+        let offsets = |l: ir::TimeIdx, r: ir::TimeIdx| {
+            let (l, r) = (comp.get(l), comp.get(r));
+            (l.event == r.event).then_some((l.offset, r.offset))
+        };
+        match comp.get(prop) {
+            ir::Prop::True | ir::Prop::False => sol.false_(),
+            ir::Prop::Cmp(ir::CmpOp { lhs, rhs, .. }) => {
+                sol.or(self.expr_ovf[*lhs], self.expr_ovf[*rhs])
+            }
+            ir::Prop::TimeCmp(ir::CmpOp { lhs, rhs, .. }) => {
+                match offsets(*lhs, *rhs) {
+                    Some((l, r)) => sol.or(self.expr_ovf[l], self.expr_ovf[r]),
+                    None => sol.or(time(*lhs), time(*rhs)),
+                }
+            }
+            ir::Prop::TimeSubCmp(ir::CmpOp { lhs, rhs, .. }) => {
+                sol.or_many([lhs, rhs].into_iter().map(|ts| match ts {
+                    ir::TimeSub::Unit(e) => self.expr_ovf[*e],
+                    ir::TimeSub::Sym { l, r } => {
+                        let (ovf, l, r) = match offsets(*l, *r) {
+                            Some((l, r)) => (
+                                sol.or(self.expr_ovf[l], self.expr_ovf[r]),
+                                self.expr_map[l],
+                                self.expr_map[r],
+                            ),
+                            None => (
+                                sol.or(time(*l), time(*r)),
+                                self.time_map[*l],
+                                self.time_map[*r],
+                            ),
+                        };
+                        sol.or(ovf, sol.bvult(l, r))
+                    }
+                }))
+            }
+            ir::Prop::Not(p) => self.prop_overflow(*p, comp),
+            ir::Prop::And(l, r)
+            | ir::Prop::Or(l, r)
+            | ir::Prop::Implies(l, r) => sol
+                .or(self.prop_overflow(*l, comp), self.prop_overflow(*r, comp)),
+        }
+    }
+
+    /// The goal that is satisfiable iff the proposition has a
+    /// counterexample. In the bitvector encoding, counterexamples must not
+    /// overflow.
+    fn goal(&self, prop: ir::PropIdx, comp: &ir::Component) -> smt::SExpr {
+        let sol = self.sol();
+        let neg = sol.not(self.prop_map[prop]);
+        if self.bv_size.is_some() {
+            sol.and(neg, sol.not(self.prop_overflow(prop, comp)))
+        } else {
+            neg
+        }
+    }
+}
+
+impl Construct for Discharge {
+    fn from(opts: &options::Options, ctx: &mut ir::Context) -> Self {
+        if matches!(opts.solver, options::Solver::Builtin)
+            && opts.solver_bv.is_some()
+        {
+            log::warn!("The built-in solver does not support the bitvector encoding. Ignoring --solver-bv.");
+        }
+
+        let portfolio = match (opts.solver, &opts.portfolio[..]) {
+            (_, []) => None,
+            (options::Solver::Builtin, _) => {
+                log::warn!("The built-in solver does not use external solvers. Ignoring --portfolio.");
+                None
+            }
+            (_, solvers) => {
+                if solvers
+                    .iter()
+                    .any(|s| matches!(s, options::Solver::Builtin))
+                {
+                    log::warn!("The built-in solver is always tried first and cannot be part of the portfolio.");
+                }
+                let solvers = solvers
+                    .iter()
+                    .filter(|s| !matches!(s, options::Solver::Builtin))
+                    .copied()
+                    .collect_vec();
+                (!solvers.is_empty()).then(|| {
+                    portfolio::Portfolio::new(solvers, opts.solver_timeout)
+                })
+            }
+        };
+        // Certificates contain the script encoding the component so queries
+        // are sent to the solver as scripts as well.
+        let portfolio = match (portfolio, &opts.emit_certs, opts.solver) {
+            (None, Some(_), solver)
+                if !matches!(solver, options::Solver::Builtin) =>
+            {
+                Some(portfolio::Portfolio::new(
+                    vec![solver],
+                    opts.solver_timeout,
+                ))
+            }
+            (portfolio, _, _) => portfolio,
+        };
+
+        // Boolector and bitwuzla only support bitvectors. The encoding is
+        // exact for facts with bounded terms so it is used by default.
+        let bv_only = |s: &options::Solver| {
+            matches!(s, options::Solver::Boolector | options::Solver::Bitwuzla)
+        };
+        let bv_size = match (opts.solver, opts.solver_bv) {
+            (options::Solver::Builtin, _) => None,
+            (_, Some(bv)) => Some(bv),
+            (solver, None)
+                if bv_only(&solver)
+                    || portfolio.is_some()
+                        && opts.portfolio.iter().any(bv_only) =>
+            {
+                Some(DEFAULT_BV_SIZE)
+            }
+            _ => None,
+        };
+
+        // For each `some` parameter of a component, record the function from
+        // the input parameters of the component to the `some` parameter.
+        let mut comp_params = LinkedHashMap::new();
+        for (comp_idx, comp) in ctx.comps.iter() {
+            let num_args = comp.param_args().len();
+            for some_param in comp.exist_params() {
+                let ir::ParamOwner::Exists { opaque } = &comp[some_param].owner
+                else {
+                    unreachable!()
+                };
+                if *opaque {
+                    // If this is an opaque parameter, then we don't define the function
+                    continue;
+                }
+                let name =
+                    format!("comp{}_param{}", comp_idx.get(), some_param.get());
+                let f = ir::Foreign::new(some_param, comp_idx);
+                let desc = format!(
+                    "`some' parameter {} of `{}'",
+                    Self::describe_param(some_param, comp),
+                    comp.name.map(|n| n.to_string()).unwrap_or_default()
+                );
+                comp_params.insert(f, (name, num_args, desc));
+            }
+        }
+
+        Self {
+            bv_size,
+            width: 0,
+            sol: None,
+            sol_base: opts.solver,
+            replay_file: opts.solver_replay_file.clone(),
+            log: None,
+            timeout: opts.solver_timeout,
+            portfolio,
+            script: vec![],
+            cert_dir: opts.emit_certs.clone(),
+            rebuilt: None,
+            scoped: false,
+            error_count: 0,
+            act_lit_count: 0,
+            to_prove: vec![],
+            show_models: opts.show_models,
+            cex_dir: opts.emit_counterexample.clone(),
+            input: opts.input.clone(),
+            entrypoints: ctx.entrypoints.clone(),
+            model_params: vec![],
+            cex_files: Default::default(),
+            profile: opts.profile_discharge.clone().map(profile::Profile::new),
+            func_map: Default::default(),
+            param_map: Default::default(),
+            prop_map: Default::default(),
+            expr_ovf: Default::default(),
+            time_ovf: Default::default(),
+            inexact: Default::default(),
+            wraparound: Default::default(),
+            time_map: Default::default(),
+            ev_map: Default::default(),
+            expr_map: Default::default(),
+            checked: Default::default(),
+            undecided: Default::default(),
+            sol_err: None,
+            diagnostics: Default::default(),
+            comp_param_map: Default::default(),
+            comp_params,
+            table: vec![],
+            encoded: false,
+            instances: vec![],
+        }
+    }
+
+    fn clear_data(&mut self) {
+        self.param_map.clear();
+        self.prop_map.clear();
+        self.expr_ovf.clear();
+        self.time_ovf.clear();
+        self.inexact.clear();
+        self.wraparound.clear();
+        self.time_map.clear();
+        self.ev_map.clear();
+        self.expr_map.clear();
+        self.checked.clear();
+        self.undecided.clear();
+        self.diagnostics.clear();
+        self.act_lit_count = 0;
+        self.to_prove.clear();
+        self.instances.clear();
+        self.model_params.clear();
+        self.cex_files.clear();
+        self.table.clear();
+        self.encoded = false;
+
+        // Create a new solver context
+        if self.portfolio.is_some() {
+            self.script.clear();
+        } else if let Some(sol) = &mut self.sol {
+            sol.pop_many(1).unwrap();
+            sol.push_many(1).unwrap();
+        }
+    }
+}
+
+impl Discharge {
+    fn fmt_param(&self, param: ir::ParamIdx, ctx: &ir::Component) -> String {
+        match self.sol_base {
+            // CVC5 does not correctly print out quoted SExps. Portfolio
+            // models are parsed from text and use plain names as well.
+            options::Solver::Z3 if self.portfolio.is_none() => {
+                format!("|{}@param{}|", ctx.display(param), param.get())
+            }
+            _ => {
+                format!("param{}", param.get())
+            }
+        }
+    }
+
+    fn fmt_event(&self, event: ir::EventIdx, ctx: &ir::Component) -> String {
+        match self.sol_base {
+            options::Solver::Z3 if self.portfolio.is_none() => {
+                format!("|{}@event{}|", ctx.display(event), event.get())
+            }
+            _ => format!("event{}", event.get()),
+        }
+    }
+
+    fn fmt_expr(expr: ir::ExprIdx) -> String {
+        format!("e{}", expr.get())
+    }
+
+    fn fmt_prop(prop: ir::PropIdx) -> String {
+        format!("prop{}", prop.get())
+    }
+
+    fn fmt_time(time: ir::TimeIdx) -> String {
+        format!("t{}", time.get())
+    }
+
+    /// Source name and location of the parameter
+    fn describe_param(param: ir::ParamIdx, ctx: &ir::Component) -> String {
+        let loc = ctx
+            .get(ctx.get(param).info)
+            .as_param()
+            .and_then(|p| replay::pos_location(p.bind_loc));
+        match loc {
+            Some(loc) => format!("`{}' at {loc}", ctx.display(param)),
+            None => format!("`{}'", ctx.display(param)),
+        }
+    }
+
+    /// Source name and location of the event
+    fn describe_event(event: ir::EventIdx, ctx: &ir::Component) -> String {
+        let loc = ctx
+            .get(ctx.get(event).info)
+            .as_event()
+            .and_then(|e| replay::pos_location(e.bind_loc));
+        match loc {
+            Some(loc) => format!("`{}' at {loc}", ctx.display(event)),
+            None => format!("`{}'", ctx.display(event)),
+        }
+    }
+
+    /// Describe a declaration in the solver log. Portfolio queries carry the
+    /// description in their script.
+    fn annotate(&mut self, text: impl FnOnce() -> String) {
+        if self.log.is_none() {
+            return;
+        }
+        let text = text();
+        if self.portfolio.is_some() {
+            self.script.push(format!("; {text}"));
+        } else {
+            self.log.as_ref().unwrap().comment(&text).unwrap();
+        }
+    }
+
+    /// Record the facts checked by the next query in the solver log along
+    /// with the diagnostics reported if they do not hold.
+    fn annotate_query(
+        &self,
+        kind: &str,
+        facts: &[ir::Fact],
+        ctx: &ir::Component,
+    ) {
+        let Some(log) = &self.log else {
+            return;
+        };
+        let mut text = format!("query: {kind}");
+        for fact in facts {
+            let constraint = ctx.display(fact.prop.consequent(ctx));
+            let diag = match ctx.get(fact.reason).as_assert() {
+                Some(ir::info::Assert(reason)) => reason.diag(ctx),
+                None => Diagnostic::error().with_message(format!(
+                    "Cannot prove constraint: {constraint}"
+                )),
+            };
+            text += "\n";
+            text += &replay::describe_fact(
+                &Self::fmt_prop(fact.prop),
+                &constraint,
+                &diag,
+            );
+        }
+        log.comment(&text).unwrap();
+    }
+
+    fn new_act_lit(&mut self) -> smt::SExpr {
+        self.act_lit_count += 1;
+        let bs = self.sol().bool_sort();
+        let name = format!("act_lit{}", self.act_lit_count);
+        self.sol_mut().declare_const(name, bs).unwrap()
+    }
+
+    /// Defines primitive functions used in the encoding like `pow` and `log`
+    fn define_funcs(&mut self) {
+        let is = self.sort();
+
+        macro_rules! sol_fn(
+            ($name:tt($($args:ident),*) -> $out:ident) => {
+                let f = self.declare_fun(
+                    stringify!($name).to_lowercase(),
+                    vec![$($args),*],
+                    $out,
+                );
+                self.func_map.insert(ast::Fn::$name, f);
+            }
+        );
+
+        self.func_map = Default::default();
+
+        sol_fn!(Pow2(is) -> is);
+        sol_fn!(Log2(is) -> is);
+        sol_fn!(SinB(is, is) -> is);
+        sol_fn!(CosB(is, is) -> is);
+        sol_fn!(BitRev(is, is) -> is);
+    }
+
+    /// Whether assignments to parameters are needed for counterexamples
+    fn want_models(&self) -> bool {
+        self.show_models || self.cex_dir.is_some()
+    }
+
+    /// Get bindings for the provided parameters in a model.
+    fn get_assignments(&mut self, relevant_vars: Vec<ir::ParamIdx>) -> Assign {
+        if relevant_vars.is_empty() {
+            return Assign(vec![]);
+        }
+        let mut rev_map = HashMap::with_capacity(relevant_vars.len());
+        // SExprs corresponding to the paramters
+        let sexps = relevant_vars
+            .iter()
+            .unique()
+            .map(|p| {
+                let s = self.param_map[p];
+                log::debug!("{} -> {}", self.sol().display(s), p);
+                rev_map.insert(s, *p);
+                s
+            })
+            .collect_vec();
+        let num_vars = sexps.len();
+
+        let model = self.sol_mut().get_value(sexps).unwrap();
+        assert!(model.len() == num_vars,
+            "{num_vars} relevant variables but the model contains assignments for {} variables",
+            model.len()
+        );
+
+        Assign(
+            model
+                .into_iter()
+                .flat_map(|(p, v)| {
+                    let Some(&p) = rev_map.get(&p) else {
+                        unreachable!(
+                            "missing binding for sexp {}",
+                            self.sol().display(p)
+                        );
+                    };
+                    Some((p, self.sol().display(v).to_string()))
+                })
+                .collect_vec(),
+        )
+    }
+
+    /// Check whether the goal is satisfiable using the external solver.
+    /// If models are requested, the returned assignment binds `params`.
+    fn query(
+        &mut self,
+        goal: smt::SExpr,
+        params: Vec<ir::ParamIdx>,
+    ) -> Outcome {
+        let out = if self.portfolio.is_some() {
+            self.query_portfolio(goal, params)
+        } else {
+            self.query_incremental(goal, params)
+        };
+        if let Some(log) = &self.log {
+            let answer = match out {
+                Outcome::Sat(_) => "sat",
+                Outcome::Unsat => "unsat",
+                Outcome::Unknown(_) => "unknown",
+            };
+            log.comment(&format!("answer: {answer}")).unwrap();
+        }
+        out
+    }
+
+    /// Check whether the goal is satisfiable using the incremental solver.
+    fn query_incremental(
+        &mut self,
+        goal: smt::SExpr,
+        params: Vec<ir::ParamIdx>,
+    ) -> Outcome {
+        let actlit = self.new_act_lit();
+        let imp = self.sol().imp(actlit, goal);
+        self.sol_mut().assert(imp).unwrap();
+        let out = match self.sol_mut().check_assuming([actlit]).unwrap() {
+            smt::Response::Sat => Outcome::Sat(if self.want_models() {
+                self.get_assignments(params)
+            } else {
+                Assign::default()
+            }),
+            smt::Response::Unsat => Outcome::Unsat,
+            smt::Response::Unknown => Outcome::Unknown(match self.timeout {
+                Some(ms) => format!("The solver could not decide this constraint within {ms}ms. Increase the limit with `--solver-timeout` or try other solvers with `--portfolio`."),
+                None => "The solver could not decide this constraint. Try other solvers with `--portfolio`.".to_string(),
+            }),
+        };
+        // Disable the activation literal after the `get-model` call.
+        let not_actlit = self.sol().not(actlit);
+        self.sol_mut().assert(not_actlit).unwrap();
+        out
+    }
+
+    /// Check whether the goal is satisfiable by running the portfolio on the
+    /// script encoding the current component.
+    fn query_portfolio(
+        &mut self,
+        goal: smt::SExpr,
+        params: Vec<ir::ParamIdx>,
+    ) -> Outcome {
+        let sol = self.sol();
+        let mut script = self.script_with(goal);
+        let names = if self.want_models() {
+            params
+                .into_iter()
+                .unique()
+                .map(|p| (sol.display(self.param_map[&p]).to_string(), p))
+                .collect_vec()
+        } else {
+            vec![]
+        };
+        if !names.is_empty() {
+            script += &format!(
+                "(get-value ({}))\n",
+                names.iter().map(|(n, _)| n).join(" ")
+            );
+        }
+        if let Some(log) = &self.log {
+            log.commands(&format!("(push 1)\n{script}(pop 1)\n"))
+                .unwrap();
+        }
+
+        let portfolio = self.portfolio.as_ref().unwrap();
+        match portfolio.run(&script) {
+            portfolio::Answer::Sat(values) => Outcome::Sat(Assign(
+                values
+                    .into_iter()
+                    .filter_map(|(n, v)| {
+                        let (_, p) = names.iter().find(|(m, _)| *m == n)?;
+                        Some((*p, v))
+                    })
+                    .collect(),
+            )),
+            portfolio::Answer::Unsat => Outcome::Unsat,
+            portfolio::Answer::Unknown(reason) => Outcome::Unknown(format!(
+                "No solver in the portfolio could decide this constraint: {reason}."
+            )),
+        }
+    }
+
+    /// The script encoding the current component followed by a query for the
+    /// satisfiability of the goal.
+    fn script_with(&self, goal: smt::SExpr) -> String {
+        let mut script = self.script.iter().map(|c| format!("{c}\n")).join("");
+        script +=
+            &format!("(assert {})\n(check-sat)\n", self.sol().display(goal));
+        script
+    }
+
+    /// Discharge pass that records the scripts of the certificates of the
+    /// components without proving their facts
+    fn rebuild_certs(opts: &options::Options, ctx: &mut ir::Context) -> Self {
+        let mut pass = <Self as Construct>::from(opts, ctx);
+        // Certificates are always encoded as scripts for a portfolio
+        pass.portfolio = Some(portfolio::Portfolio::new(
+            vec![opts.solver],
+            opts.solver_timeout,
+        ));
+        pass.rebuilt = Some(vec![]);
+        pass
+    }
+
+    /// The script of the certificate of the component: the encoding of the
+    /// component and a query for a counterexample to any of the facts.
+    fn cert_script(
+        &mut self,
+        facts: &[ir::Fact],
+        comp: &ir::Component,
+    ) -> Result<String, String> {
+        if matches!(self.sol_base, options::Solver::Builtin) {
+            return Err("the built-in solver cannot produce certificates. Use an external solver with `--solver`.".to_string());
+        }
+        if !self.encode(comp) {
+            return Err(format!(
+                "the external solver could not be started: {}",
+                self.sol_err.as_ref().unwrap()
+            ));
+        }
+        if let Some(f) =
+            facts.iter().find(|f| self.inexact.contains_key(&f.prop))
+        {
+            return Err(format!(
+                "the bitvector encoding of `{}' is not exact because {}",
+                comp.display(f.prop.consequent(comp)),
+                self.inexact[&f.prop]
+            ));
+        }
+        let goal = self.sol().or_many(
+            iter::once(self.sol().false_())
+                .chain(facts.iter().map(|f| self.goal(f.prop, comp))),
+        );
+        Ok(self.script_with(goal))
+    }
+
+    /// Prove all the facts of the component using an external solver and
+    /// write the query and its answer to a certificate.
+    fn emit_cert(
+        &mut self,
+        dir: &Path,
+        name: &str,
+        facts: &[ir::Fact],
+        comp: &ir::Component,
+    ) -> Result<PathBuf, String> {
+        let script = self.cert_script(facts, comp)?;
+        match self.portfolio.as_ref().unwrap().race(&script) {
+            (portfolio::Answer::Unsat, Some(solver)) => {
+                let options = cert::options(self.bv_size);
+                cert::write(dir, name, solver, &options, &script)
+                    .map_err(|e| format!("cannot write certificate: {e}"))
+            }
+            (portfolio::Answer::Sat(_), _) => {
+                Err("the solver found a counterexample to the facts"
+                    .to_string())
+            }
+            (portfolio::Answer::Unknown(reason), _) => Err(reason),
+            (portfolio::Answer::Unsat, None) => unreachable!(),
+        }
+    }
+
+    /// Check whether the proposition is valid using the external solver.
+    fn check_external(&mut self, fact: &ir::Fact, ctx: &ir::Component) {
+        let prop = fact.prop;
+        if self.checked.contains_key(&prop)
+            || self.undecided.contains_key(&prop)
+        {
+            return;
+        }
+        let goal = self.goal(prop, ctx);
+        self.annotate_query("goal", std::slice::from_ref(fact), ctx);
+        let mut params = ctx.prop_params(prop.consequent(ctx));
+        params.extend(self.model_params.iter().copied());
+        let (res, time) = time!(log_time!(
+            self.query(goal, params),
+            ctx.display(prop.consequent(ctx));
+            100
+        ));
+        let status = match res {
+            Outcome::Sat(assign) => {
+                self.checked.insert(prop, Some(assign));
+                profile::Status::Invalid
+            }
+            Outcome::Unsat => {
+                self.checked.insert(prop, None);
+                profile::Status::Valid
+            }
+            Outcome::Unknown(reason) => {
+                self.undecided.insert(prop, reason);
+                profile::Status::Unknown
+            }
+        };
+        if let Some(profile) = &mut self.profile {
+            profile.add(fact, ctx, profile::Query::External, time, status);
+        }
+    }
+
+    /// Check whether the proposition is valid using the built-in solver.
+    /// Returns false if the solver cannot decide the proposition.
+    fn check_builtin(
+        &mut self,
+        solver: &mut builtin::Solver,
+        fact: &ir::Fact,
+        ctx: &ir::Component,
+    ) -> bool {
+        let prop = fact.prop;
+        if self.checked.contains_key(&prop) {
+            return true;
+        }
+        let (res, time) = time!(log_time!(
+            solver.check(prop),
+            ctx.display(prop.consequent(ctx));
+            100
+        ));
+        if let Some(profile) = &mut self.profile {
+            let status = match res {
+                builtin::Res::Valid => profile::Status::Valid,
+                builtin::Res::Invalid(_) => profile::Status::Invalid,
+                builtin::Res::Unknown => profile::Status::Unknown,
+            };
+            profile.add(fact, ctx, profile::Query::Builtin, time, status);
+        }
+        let out = match res {
+            builtin::Res::Valid => None,
+            builtin::Res::Invalid(model) => {
+                if self.want_models() {
+                    Some(Assign(
+                        ctx.prop_params(prop.consequent(ctx))
+                            .into_iter()
+                            .chain(self.model_params.iter().copied())
+                            .unique()
+                            .map(|p| {
+                                let v = model.get(&p).copied().unwrap_or(0);
+                                (p, v.to_string())
+                            })
+                            .collect(),
+                    ))
+                } else {
+                    Some(Assign::default())
+                }
+            }
+            builtin::Res::Unknown => return false,
+        };
+        self.checked.insert(prop, out);
+        true
+    }
+
+    /// The parameters of the instances in the component that are defined by
+    /// the function computing a `some` parameter.
+    fn instance_apps(
+        &self,
+        comp: &ir::Component,
+    ) -> Vec<(
+        ir::Foreign<ir::Param, ir::Component>,
+        Vec<ir::ExprIdx>,
+        ir::ParamIdx,
+    )> {
+        let mut apps = vec![];
+        for idx in &self.instances {
+            let inst = &comp[*idx];
+            for param in &inst.params {
+                let ir::ParamOwner::Instance { base, .. } = &comp[*param].owner
+                else {
+                    unreachable!()
+                };
+                if self.comp_params.contains_key(base) {
+                    apps.push((*base, inst.args.to_vec(), *param));
+                }
+            }
+        }
+        apps
+    }
+
+    /// Select the width of the bitvector encoding for the facts of the current
+    /// component and record the facts that it cannot evaluate exactly.
+    fn select_width(&mut self, facts: &[ir::Fact], comp: &ir::Component) {
+        let max = self.bv_size.unwrap();
+        self.width = 1;
+        for fact in facts {
+            match bounds::fact_width(comp, fact.prop, max) {
+                Ok(width) => self.width = self.width.max(width),
+                Err(reason) => {
+                    self.inexact.insert(fact.prop, reason);
+                }
+            }
+        }
+        // Look for counterexamples to inexact facts in the largest domain
+        if !self.inexact.is_empty() {
+            self.width = max;
+        }
+    }
+
+    /// Record why a fact without counterexamples in the bitvector encoding
+    /// is not proven if the encoding does not evaluate it exactly.
+    fn check_wraparound(&mut self, fact: &ir::Fact, ctx: &ir::Component) {
+        let prop = fact.prop;
+        let Some(reason) = self.inexact.get(&prop).cloned() else {
+            return;
+        };
+        let width = self.width;
+        // Check whether the fact holds when terms are allowed to wrap around
+        let goal = self.sol().not(self.prop_map[prop]);
+        self.annotate_query(
+            &format!("wraparound {width}"),
+            std::slice::from_ref(fact),
+            ctx,
+        );
+        let (res, time) = time!(self.query(goal, vec![]));
+        let status = match res {
+            Outcome::Unsat => profile::Status::Valid,
+            Outcome::Sat(_) => profile::Status::Invalid,
+            Outcome::Unknown(_) => profile::Status::Unknown,
+        };
+        if let Some(profile) = &mut self.profile {
+            profile.add(fact, ctx, profile::Query::Wraparound, time, status);
+        }
+        let msg = match status {
+            profile::Status::Valid => {
+                format!("constraint only holds modulo {width}-bit wraparound")
+            }
+            profile::Status::Invalid | profile::Status::Unknown => format!(
+                "constraint could not be verified using {width}-bit bitvectors"
+            ),
+        };
+        let note = format!(
+            "The bitvector encoding of `{}' is not exact because {reason}. Bound the parameters with a `where` clause or use a solver that supports integers like `--solver z3`.",
+            ctx.display(prop.consequent(ctx))
+        );
+        self.wraparound.insert(prop, (msg, note));
+    }
+
+    /// Write designs reproducing the counterexamples to the facts of the
+    /// entrypoint. Facts with the same counterexample share the design.
+    fn emit_counterexamples(
+        &mut self,
+        dir: &Path,
+        facts: &[ir::Fact],
+        comp: &ir::Component,
+    ) {
+        let name = comp.name.unwrap();
+        let input = self
+            .input
+            .as_ref()
+            .and_then(|i| i.canonicalize().ok())
+            .ok_or_else(|| "the input file cannot be imported".to_string());
+        let mut designs: HashMap<Vec<u64>, Result<PathBuf, String>> =
+            HashMap::new();
+        for fact in facts {
+            let Some(Some(assign)) = self.checked.get(&fact.prop) else {
+                continue;
+            };
+            let env = match assign.values(comp) {
+                // Models may also bind parameters of bundles and loops
+                Ok(mut env) => {
+                    env.retain(|p, _| self.model_params.contains(p));
+                    env
+                }
+                Err(reason) => {
+                    self.cex_files.insert(fact.prop, Err(reason));
+                    continue;
+                }
+            };
+            let args = comp
+                .param_args()
+                .iter()
+                .map(|p| env.get(p).copied().unwrap_or(0))
+                .collect_vec();
+            let count = designs.len();
+            let out = designs.entry(args).or_insert_with_key(|args| {
+                let constraint = comp.display(fact.prop.consequent(comp));
+                let mut header = vec![format!(
+                    "Counterexample to `{constraint}' in component `{name}'"
+                )];
+                header.push("Simulate with `--unsafe-skip-discharge` because the component does not verify".to_string());
+                if let Some(ir::info::Assert(reason)) =
+                    comp.get(fact.reason).as_assert()
+                {
+                    let diag = reason.diag(comp);
+                    let loc = location(&diag).unwrap_or_default();
+                    header.push(format!("{loc}: {}", diag.message));
+                }
+                if !args.is_empty() {
+                    header.push(format!(
+                        "Parameters: {}",
+                        comp.param_args()
+                            .iter()
+                            .zip(args)
+                            .map(|(p, v)| format!("{} = {v}", comp.display(*p)))
+                            .join(", ")
+                    ));
+                }
+                let cex = counterexample::Counterexample::new(comp, env);
+                let file = format!("{name}-{count}");
+                cex.write(dir, &file, input.as_ref()?, &header)
+            });
+            self.cex_files.insert(fact.prop, out.clone());
+        }
+    }
+
+    /// Adds an error to the diagnostics if the fact could not be proved.
+    fn report(&mut self, fact: ir::Fact, ctx: &ir::Component) {
+        let prop = fact.prop;
+        let undecided = self.undecided.get(&prop);
+        let wraparound = self.wraparound.get(&prop);
+        let assign = match self.checked.get(&prop) {
+            Some(Some(assign)) => Some(assign),
+            _ if undecided.is_some() || wraparound.is_some() => None,
+            _ => return,
+        };
+        let Some(ir::info::Assert(reason)) = ctx.get(fact.reason).as_assert()
+        else {
+            // No information was given on who generated this error
+            let diag = Diagnostic::error().with_notes(vec![
+                format!(
+                    "Cannot prove constraint: {}",
+                    ctx.display(fact.prop.consequent(ctx))
+                ),
+                "No information was given on who generated this error. Please report this as a bug in the compiler with the program that triggered it."
+                    .to_string(),
+            ]);
+            self.diagnostics.push(diag);
+            return;
+        };
+        let mut diag = reason.diag(ctx);
+        if let Some((msg, note)) = wraparound {
+            let orig = std::mem::replace(&mut diag.message, msg.clone());
+            diag.code = Some(codes::WRAPAROUND.to_string());
+            diag.notes.push(format!("Required because: {orig}"));
+            diag.notes.push(note.clone());
+        }
+        if self.show_models {
+            diag = diag.with_notes(vec![format!(
+                "Cannot prove constraint: {}",
+                ctx.display(fact.prop.consequent(ctx))
+            )]);
+            if let Some(assign) = assign.filter(|a| !a.is_empty()) {
+                diag = diag.with_notes(vec![format!(
+                    "Counterexample: {} (unmentioned parameters are 0)",
+                    assign.display(ctx)
+                )]);
+            }
+        }
+        if let Some(note) = undecided {
+            diag = diag.with_notes(vec![note.clone()]);
+        }
+        match self.cex_files.get(&prop) {
+            Some(Ok(path)) => {
+                diag = diag.with_notes(vec![format!(
+                    "Design reproducing the counterexample: {}",
+                    path.display()
+                )])
+            }
+            Some(Err(reason)) => {
+                diag = diag.with_notes(vec![format!(
+                    "Cannot reproduce the counterexample: {reason}"
+                )])
+            }
+            None => (),
+        }
+        self.diagnostics.push(diag);
+    }
+
+    /// Reason reported for facts that the built-in solver could not decide
+    /// when no external solver is available.
+    fn unavailable_note(&self) -> String {
+        match &self.sol_err {
+            None => "The built-in solver could not decide this constraint. Use an external solver with `--solver`.".to_string(),
+            Some(err) => format!("The built-in solver could not decide this constraint and the external solver could not be started: {err}"),
+        }
+    }
+
+    /// Encode the current component in the external solver if it has not
+    /// been encoded yet. Returns false if the external solver is unavailable.
+    fn encode(&mut self, comp: &ir::Component) -> bool {
+        if self.encoded {
+            return true;
+        }
+        if !self.start_solver() {
+            return false;
+        }
+        self.encoded = true;
+        if let Some(name) = comp.name {
+            self.annotate(|| format!("component: {name}"));
+        }
+        self.define_funcs();
+
+        // For each `some` parameter of a component, define function from the
+        // input parameters of the component to the `some` parameter.
+        let int = self.sort();
+        let comp_params = std::mem::take(&mut self.comp_params);
+        for (f, (name, num_args, desc)) in &comp_params {
+            let func = self.declare_fun(name, vec![int; *num_args], int);
+            self.annotate(|| format!("{name} = {desc}"));
+            self.comp_param_map.insert(*f, func);
+        }
+        self.comp_params = comp_params;
+
+        // Define the values of functions for bounded arguments
+        for (op, args) in std::mem::take(&mut self.table) {
+            let Some(val) = op.checked_eval(args.clone()) else {
+                continue;
+            };
+            let fits = |n: &u64| self.width >= 64 || n >> self.width == 0;
+            if self.bv_size.is_some() && !args.iter().chain([&val]).all(fits) {
+                continue;
+            }
+            let args = args.into_iter().map(|a| self.num(a)).collect_vec();
+            let app = self.app(self.func_map[&op], args);
+            let def = self.sol().eq(app, self.num(val));
+            self.assert(def);
+        }
+
+        // Declare all parameters
+        for (idx, _) in comp.params().iter() {
+            let name = self.fmt_param(idx, comp);
+            let sexp = self.declare_fun(&name, vec![], int);
+            self.annotate(|| {
+                format!(
+                    "{name} = parameter {}",
+                    Self::describe_param(idx, comp)
+                )
+            });
+            self.param_map.insert(idx, sexp);
+        }
+
+        // Declare all events
+        for (idx, _) in comp.events().iter() {
+            let name = self.fmt_event(idx, comp);
+            let sexp = self.declare_fun(&name, vec![], int);
+            self.annotate(|| {
+                format!("{name} = event {}", Self::describe_event(idx, comp))
+            });
+            self.ev_map.push(idx, sexp);
+        }
+
+        // Declare all expressions
+        let bs = self.sol().bool_sort();
+        for (idx, expr) in comp.exprs().iter() {
+            let assign = self.expr_to_sexp(expr);
+            let sexp = self.define_const(Self::fmt_expr(idx), int, assign);
+            self.annotate(|| {
+                format!("{} = `{}'", Self::fmt_expr(idx), comp.display(idx))
+            });
+            self.expr_map.push(idx, sexp);
+            if self.bv_size.is_some() {
+                let ovf = self.expr_overflow(expr);
+                let name = format!("ovf_{}", Self::fmt_expr(idx));
+                let ovf = self.define_const(&name, bs, ovf);
+                self.annotate(|| {
+                    format!("{name} = overflow of `{}'", comp.display(idx))
+                });
+                self.expr_ovf.push(idx, ovf);
+            }
+        }
+
+        // Declare all time expressions
+        for (idx, ir::Time { event, offset }) in comp.times().iter() {
+            let ev = self.ev_map[*event];
+            let assign = self.plus(ev, self.expr_map[*offset]);
+            let sexp = self.define_const(Self::fmt_time(idx), int, assign);
+            self.annotate(|| {
+                format!("{} = `{}'", Self::fmt_time(idx), comp.display(idx))
+            });
+            self.time_map.push(idx, sexp);
+            if self.bv_size.is_some() {
+                let sol = self.sol();
+                let ovf = sol.or(self.expr_ovf[*offset], sol.bvult(sexp, ev));
+                let name = format!("ovf_{}", Self::fmt_time(idx));
+                let ovf = self.define_const(&name, bs, ovf);
+                self.annotate(|| {
+                    format!("{name} = overflow of `{}'", comp.display(idx))
+                });
+                self.time_ovf.push(idx, ovf);
+            }
+        }
+
+        // Declare all propositions
+        for (idx, prop) in comp.props().iter() {
+            // Define assertion equating the proposition to its assignment
+            let assign = self.prop_to_sexp(prop, comp);
+            let sexp = self.define_const(Discharge::fmt_prop(idx), bs, assign);
+            self.annotate(|| {
+                format!("{} = `{}'", Self::fmt_prop(idx), comp.display(idx))
+            });
+            self.prop_map.push(idx, sexp);
+        }
+
+        // Define the `some` parameters of instances
+        for idx in self.instances.clone() {
+            let inst = &comp[idx];
+            let sexp_args =
+                inst.args.iter().map(|e| self.expr_map[*e]).collect_vec();
+            for param in &inst.params {
+                let ir::ParamOwner::Instance { base, .. } = &comp[*param].owner
+                else {
+                    unreachable!()
+                };
+                // If the parameter is not opaque, we can assert that it is equal to the value of the function
+                if let Some(f) = self.comp_param_map.get(base) {
+                    let param_s = self.param_map[param];
+                    let app = self.app(*f, sexp_args.clone());
+                    let mut assign = self.sol().eq(param_s, app);
+                    // Arguments that wrap around do not determine the value
+                    if self.bv_size.is_some() {
+                        let ovf = self.sol().or_many(
+                            iter::once(self.sol().false_()).chain(
+                                inst.args.iter().map(|e| self.expr_ovf[*e]),
+                            ),
+                        );
+                        assign = self.sol().imp(self.sol().not(ovf), assign);
+                    }
+                    self.assert(assign);
+                }
+            }
+        }
+        true
+    }
+
+    fn expr_to_sexp(&mut self, expr: &ir::Expr) -> smt::SExpr {
+        match expr {
+            ir::Expr::Param(p) => self.param_map[p],
+            ir::Expr::Concrete(n) => self.num(*n),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                let l = self.expr_map[*lhs];
+                let r = self.expr_map[*rhs];
+                match op {
+                    ast::Op::Add => self.plus(l, r),
+                    ast::Op::Sub => self.sub(l, r),
+                    ast::Op::Mul => self.times(l, r),
+                    ast::Op::Div => self.div(l, r),
+                    ast::Op::Mod => self.modulo(l, r),
+                }
+            }
+            ir::Expr::Fn { op, args } => {
+                let args = args.iter().map(|e| self.expr_map[*e]);
+                self.sol().list(
+                    iter::once(self.func_map[op]).chain(args).collect_vec(),
+                )
+            }
+        }
+    }
+
+    fn cmp_to_sexp<F, T>(
+        &mut self,
+        cmp: &ir::CmpOp<T>,
+        mut transform: F,
+    ) -> smt::SExpr
+    where
+        F: FnMut(&T, &Self) -> smt::SExpr,
+    {
+        let ir::CmpOp { op, lhs, rhs } = cmp;
+        let l = transform(lhs, self);
+        let r = transform(rhs, self);
+        match op {
+            ir::Cmp::Gt => self.gt(l, r),
+            ir::Cmp::Gte => self.gte(l, r),
+            ir::Cmp::Eq => self.eq(l, r),
+        }
+    }
+
+    /// Convert a proposition to an SMT expression. Times with the same event
+    /// are compared using their offsets.
+    /// REQUIRES: Sub-terms mentioned in the proposition have already been defined.
+    fn prop_to_sexp(
+        &mut self,
+        prop: &ir::Prop,
+        comp: &ir::Component,
+    ) -> smt::SExpr {
+        let sol = self.sol();
+        match prop {
+            ir::Prop::True => sol.true_(),
+            ir::Prop::False => sol.false_(),
+            ir::Prop::Cmp(c) => self.cmp_to_sexp(c, |e, ctx| ctx.expr_map[*e]),
+            ir::Prop::TimeCmp(c) => {
+                let (l, r) = (comp.get(c.lhs), comp.get(c.rhs));
+                if l.event == r.event {
+                    let c = ir::CmpOp {
+                        op: c.op.clone(),
+                        lhs: l.offset,
+                        rhs: r.offset,
+                    };
+                    self.cmp_to_sexp(&c, |e, ctx| ctx.expr_map[*e])
+                } else {
+                    self.cmp_to_sexp(c, |t, ctx| ctx.time_map[*t])
+                }
+            }
+            ir::Prop::TimeSubCmp(c) => {
+                self.cmp_to_sexp(c, |ts, ctx| match ts {
+                    ir::TimeSub::Unit(e) => ctx.expr_map[*e],
+                    ir::TimeSub::Sym { l, r } => {
+                        let (lt, rt) = (comp.get(*l), comp.get(*r));
+                        if lt.event == rt.event {
+                            let l = ctx.expr_map[lt.offset];
+                            let r = ctx.expr_map[rt.offset];
+                            ctx.sub(l, r)
+                        } else {
+                            let l = ctx.time_map[*l];
+                            let r = ctx.time_map[*r];
+                            ctx.sub(l, r)
+                        }
+                    }
+                })
+            }
+            ir::Prop::Not(p) => sol.not(self.prop_map[*p]),
+            ir::Prop::And(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.and(l, r)
+            }
+            ir::Prop::Or(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.or(l, r)
+            }
+            ir::Prop::Implies(l, r) => {
+                let l = self.prop_map[*l];
+                let r = self.prop_map[*r];
+                sol.imp(l, r)
+            }
+        }
+    }
+}
+
+impl Visitor for Discharge {
+    fn name() -> &'static str {
+        "discharge"
+    }
+
+    fn start(&mut self, data: &mut VisitorData) -> Action {
+        // Verified when its library was precompiled
+        if data.comp.precompiled {
+            log::info!(
+                "Skipping `{}' defined by a precompiled library",
+                data.comp.name.map(|n| n.to_string()).unwrap_or_default()
+            );
+            return Action::Stop;
+        }
+        Action::Continue
+    }
+
+    fn fact(&mut self, f: &mut ir::Fact, _: &mut VisitorData) -> Action {
+        if self.scoped {
+            panic!("scoped facts not supported. Run `hoist-facts` before this pass");
+        }
+
+        if f.is_assume() {
+            panic!(
+                "assumptions should have been eliminated by `hoist-facts` pass"
+            )
+        }
+
+        // Defer proof obligations till the end of the component pass
+        self.to_prove.push(f.clone());
+        Action::Continue
+    }
+
+    fn do_if(&mut self, i: &mut ir::If, data: &mut VisitorData) -> Action {
+        let orig = self.scoped;
+        self.scoped = true;
+        let out = self
+            .visit_cmds(&mut i.then, data)
+            .and_then(|| self.visit_cmds(&mut i.alt, data));
+        self.scoped = orig;
+        out
+    }
+
+    fn do_loop(&mut self, l: &mut ir::Loop, data: &mut VisitorData) -> Action {
+        let orig = self.scoped;
+        self.scoped = true;
+        let out = self
+            .start_loop(l, data)
+            .and_then(|| self.visit_cmds(&mut l.body, data));
+        self.scoped = orig;
+        out
+    }
+
+    fn instance(&mut self, idx: ir::InstIdx, _: &mut VisitorData) -> Action {
+        self.instances.push(idx);
+        Action::Continue
+    }
+
+    fn end(&mut self, data: &mut VisitorData) {
+        assert!(!self.scoped, "unbalanced scopes");
+
+        if self.to_prove.is_empty()
+            && self.cert_dir.is_none()
+            && self.rebuilt.is_none()
+        {
+            return;
+        }
+        let comp = &data.comp;
+        let facts = std::mem::take(&mut self.to_prove);
+        let emit_cex =
+            self.cex_dir.is_some() && self.entrypoints.contains(&data.idx);
+        if emit_cex {
+            self.model_params = comp
+                .param_args()
+                .iter()
+                .copied()
+                .chain(comp.exist_params())
+                .collect();
+        }
+
+        // Attempt to discharge facts using the built-in solver and fall back
+        // to the external solver for the ones it cannot decide.
+        let mut solver = builtin::Solver::new(comp, self.instance_apps(comp));
+        let external = facts
+            .iter()
+            .filter(|f| !self.check_builtin(&mut solver, f, comp))
+            .cloned()
+            .collect_vec();
+        if self.bv_size.is_some() {
+            self.select_width(&facts, comp);
+        }
+        self.table =
+            bounds::fn_table(comp, external.iter().map(|f| f.prop), MAX_TABLE);
+
+        // The script depends on the facts the built-in solver decides but
+        // not on the answers of the external solver
+        if self.rebuilt.is_some() {
+            if let Some(name) = comp.name.filter(|_| !comp.is_ext) {
+                let script = self.cert_script(&facts, comp);
+                let rebuilt = self.rebuilt.as_mut().unwrap();
+                rebuilt.push((name.to_string(), script));
+            }
+            return;
+        }
+
+        let started = !external.is_empty()
+            && !matches!(self.sol_base, options::Solver::Builtin)
+            && self.encode(comp);
+        if !started {
+            let note = self.unavailable_note();
+            self.undecided
+                .extend(external.iter().map(|f| (f.prop, note.clone())));
+        } else if !data.opts.discharge_separate && self.profile.is_none() {
+            // Attempt to prove all facts
+            let goal = self
+                .sol()
+                .or_many(external.iter().map(|f| self.goal(f.prop, comp)));
+
+            self.annotate_query("all", &external, comp);
+            // If at least one prop could not be proven, roll back to individually checking the props for error reporting
+            if !matches!(self.query(goal, vec![]), Outcome::Unsat) {
+                for fact in &external {
+                    self.check_external(fact, comp);
+                }
+            }
+        } else {
+            // Check each proposition individually. Profiles always do this
+            // so that the time of each query can be attributed to a fact.
+            for fact in &external {
+                self.check_external(fact, comp);
+            }
+        }
+
+        // Facts without counterexamples are only proven if the bitvector
+        // encoding evaluates them exactly.
+        if started {
+            for fact in &external {
+                let refuted =
+                    matches!(self.checked.get(&fact.prop), Some(Some(_)));
+                if !refuted && !self.undecided.contains_key(&fact.prop) {
+                    self.check_wraparound(fact, comp);
+                }
+            }
+        }
+
+        if emit_cex {
+            let dir = self.cex_dir.clone().unwrap();
+            self.emit_counterexamples(&dir, &facts, comp);
+        }
+        for fact in &facts {
+            self.report(fact.clone(), comp);
+        }
+
+        // Emit a certificate for source components if all the facts were
+        // proven
+        if let (Some(dir), Some(name)) = (self.cert_dir.clone(), comp.name) {
+            if !comp.is_ext && self.diagnostics.is_empty() {
+                match self.emit_cert(&dir, name.as_ref(), &facts, comp) {
+                    Ok(path) => {
+                        log::info!("Wrote certificate for `{name}' to {}", path.display())
+                    }
+                    Err(reason) => self.diagnostics.push(
+                        Diagnostic::error()
+                            .with_message(format!(
+                                "cannot produce proof certificate for component `{name}'"
+                            ))
+                            .with_notes(vec![reason]),
+                    ),
+                }
+            }
+        }
+
+        // Report all the errors
+        let is_tty = atty::is(atty::Stream::Stderr);
+        let writer = StandardStream::stderr(if is_tty {
+            ColorChoice::Always
+        } else {
+            ColorChoice::Never
+        });
+        let table = GlobalPositionTable::as_ref();
+        for diag in &self.diagnostics {
+            if diag.severity == cr::Severity::Error {
+                Reported::record(diag.code.as_deref(), &diag.message);
+            }
+            term::emit(
+                &mut writer.lock(),
+                &term::Config::default(),
+                table.files(),
+                diag,
+            )
+            .unwrap();
+            self.error_count += 1;
+        }
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        if let Some(profile) = &self.profile {
+            if let Err(e) = profile.write() {
+                log::warn!("Failed to write discharge profile: {e}");
+            }
+            profile.summary();
+        }
+        if self.error_count > 0 {
+            Some(self.error_count)
+        } else {
+            None
+        }
+    }
+}
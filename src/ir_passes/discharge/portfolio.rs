@@ -0,0 +1,241 @@
+//! Run several external solvers in parallel on the same query and use the
+//! first definitive answer.
+//!
+//! Unlike the incremental solver used by [super::Discharge], every query is
+//! sent to fresh solver processes as a complete SMT-LIB script so that the
+//! processes that lose the race can simply be killed.
+use super::solver_cmd;
+use crate::options;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Answer produced by a portfolio of solvers.
+pub enum Answer {
+    /// The query is satisfiable with the values returned by `get-value`.
+    Sat(Vec<(String, String)>),
+    Unsat,
+    /// No solver produced a definitive answer. Contains the reason why.
+    Unknown(String),
+}
+
+pub struct Portfolio {
+    solvers: Vec<options::Solver>,
+    timeout: Option<u64>,
+}
+
+impl Portfolio {
+    pub fn new(solvers: Vec<options::Solver>, timeout: Option<u64>) -> Self {
+        Self { solvers, timeout }
+    }
+
+    /// The solvers in the portfolio
+    pub fn solvers(&self) -> &[options::Solver] {
+        &self.solvers
+    }
+
+    /// Run the script on all solvers and return the first definitive answer.
+    /// The script must end with a `check-sat` command optionally followed by
+    /// a `get-value` command.
+    pub fn run(&self, script: &str) -> Answer {
+        self.race(script).0
+    }
+
+    /// Like [Self::run] but also returns the solver that produced the
+    /// definitive answer.
+    pub fn race(&self, script: &str) -> (Answer, Option<options::Solver>) {
+        let (tx, rx) = mpsc::channel();
+        let mut children = vec![];
+        let mut reasons = vec![];
+        for solver in &self.solvers {
+            let (name, args) = solver_cmd(*solver, self.timeout);
+            let child = Command::new(name)
+                .args(args)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn();
+            let mut child = match child {
+                Ok(child) => child,
+                Err(e) => {
+                    log::warn!("Failed to start solver `{name}': {e}");
+                    reasons.push(format!("`{name}' could not be started: {e}"));
+                    continue;
+                }
+            };
+            let mut stdin = child.stdin.take().unwrap();
+            let mut stdout = child.stdout.take().unwrap();
+            let script = format!("{script}(exit)\n");
+            let tx = tx.clone();
+            let solver = *solver;
+            thread::spawn(move || {
+                // If writing fails, the solver exited early and its output
+                // explains why.
+                let _ = stdin.write_all(script.as_bytes());
+                drop(stdin);
+                let mut out = String::new();
+                let res = stdout.read_to_string(&mut out).map(|_| out);
+                // The receiver is gone if another solver already answered
+                let _ = tx.send((solver, name, res));
+            });
+            children.push(child);
+        }
+        drop(tx);
+
+        let deadline = self
+            .timeout
+            .map(|ms| Instant::now() + Duration::from_millis(ms));
+        let mut answer = None;
+        while answer.is_none() {
+            let msg = match deadline {
+                Some(d) => {
+                    rx.recv_timeout(d.saturating_duration_since(Instant::now()))
+                }
+                None => {
+                    rx.recv().map_err(|_| mpsc::RecvTimeoutError::Disconnected)
+                }
+            };
+            match msg {
+                Ok((solver, name, Ok(out))) => match parse(&out) {
+                    Some(Answer::Unknown(_)) => {
+                        reasons.push(format!("`{name}' returned unknown"))
+                    }
+                    Some(ans) => {
+                        log::debug!("Solver `{name}' answered first");
+                        answer = Some((ans, solver))
+                    }
+                    None => reasons.push(format!(
+                        "`{name}' produced unexpected output: {}",
+                        out.trim()
+                    )),
+                },
+                Ok((_, name, Err(e))) => {
+                    reasons.push(format!("`{name}' failed: {e}"))
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    reasons.push(format!(
+                        "the solvers timed out after {}ms",
+                        self.timeout.unwrap()
+                    ));
+                    break;
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        for mut child in children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        match answer {
+            Some((ans, solver)) => (ans, Some(solver)),
+            None => (Answer::Unknown(reasons.join("; ")), None),
+        }
+    }
+}
+
+/// A parsed s-expression
+pub enum SExp {
+    Atom(String),
+    List(Vec<SExp>),
+}
+
+impl std::fmt::Display for SExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SExp::Atom(a) => write!(f, "{a}"),
+            // Negative numbers are printed as `(- n)`
+            SExp::List(l) => match &l[..] {
+                [SExp::Atom(op), SExp::Atom(n)] if op == "-" => {
+                    write!(f, "-{n}")
+                }
+                _ => {
+                    write!(f, "(")?;
+                    for (i, e) in l.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, " ")?;
+                        }
+                        write!(f, "{e}")?;
+                    }
+                    write!(f, ")")
+                }
+            },
+        }
+    }
+}
+
+/// Parse a sequence of s-expressions. Returns `None` if the parentheses are
+/// unbalanced.
+pub fn parse_sexps(s: &str) -> Option<Vec<SExp>> {
+    let mut stack: Vec<Vec<SExp>> = vec![vec![]];
+    let mut atom = String::new();
+    let mut quoted = false;
+    for c in s.chars() {
+        if quoted {
+            atom.push(c);
+            quoted = c != '|';
+            continue;
+        }
+        match c {
+            '(' | ')' | ' ' | '\t' | '\n' | '\r' => {
+                if !atom.is_empty() {
+                    let a = std::mem::take(&mut atom);
+                    stack.last_mut()?.push(SExp::Atom(a));
+                }
+                if c == '(' {
+                    stack.push(vec![]);
+                } else if c == ')' {
+                    let l = stack.pop()?;
+                    stack.last_mut()?.push(SExp::List(l));
+                }
+            }
+            '|' => {
+                atom.push(c);
+                quoted = true;
+            }
+            _ => atom.push(c),
+        }
+    }
+    if !atom.is_empty() {
+        stack.last_mut()?.push(SExp::Atom(atom));
+    }
+    if stack.len() != 1 {
+        return None;
+    }
+    stack.pop()
+}
+
+/// Parse the output of a solver running a script. Acknowledgements of
+/// commands are ignored.
+fn parse(out: &str) -> Option<Answer> {
+    let mut sexps = parse_sexps(out)?
+        .into_iter()
+        .filter(|e| !matches!(e, SExp::Atom(a) if a == "success"));
+    let SExp::Atom(res) = sexps.next()? else {
+        return None;
+    };
+    match res.as_str() {
+        "sat" => {
+            let values = match sexps.next() {
+                Some(SExp::List(vs)) => vs
+                    .into_iter()
+                    .filter_map(|v| match v {
+                        SExp::List(mut kv) if kv.len() == 2 => {
+                            let v = kv.pop()?;
+                            let k = kv.pop()?;
+                            Some((k.to_string(), v.to_string()))
+                        }
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            Some(Answer::Sat(values))
+        }
+        "unsat" => Some(Answer::Unsat),
+        "unknown" => Some(Answer::Unknown(res)),
+        _ => None,
+    }
+}
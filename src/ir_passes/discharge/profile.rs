@@ -0,0 +1,237 @@
+//! Statistics for the proof obligations checked by [super::Discharge].
+//!
+//! With `--profile-discharge`, every query made for a fact is recorded along
+//! with the reason the fact was generated, the size of the proposition, the
+//! time taken to answer it, and the answer. The records are written to a JSON
+//! file and the slowest queries are summarized on stderr.
+use fil_ir::{self as ir, Ctx, DisplayCtx};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::{fmt, fs, io};
+
+/// Number of queries shown in the summary
+const TOP: usize = 10;
+
+/// The solver that answered a query
+#[derive(Clone, Copy)]
+pub enum Query {
+    /// The built-in solver
+    Builtin,
+    /// The external solver
+    External,
+    /// The external solver checking whether a fact holds modulo wraparound
+    Wraparound,
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Builtin => f.pad("builtin"),
+            Query::External => f.pad("external"),
+            Query::Wraparound => f.pad("wraparound"),
+        }
+    }
+}
+
+/// The answer to a query
+#[derive(Clone, Copy)]
+pub enum Status {
+    /// The fact holds
+    Valid,
+    /// The solver found a counterexample
+    Invalid,
+    /// The solver could not decide the fact
+    Unknown,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Status::Valid => f.pad("valid"),
+            Status::Invalid => f.pad("invalid"),
+            Status::Unknown => f.pad("unknown"),
+        }
+    }
+}
+
+struct Record {
+    comp: String,
+    kind: &'static str,
+    code: &'static str,
+    loc: String,
+    constraint: String,
+    query: Query,
+    size: usize,
+    time: Duration,
+    status: Status,
+}
+
+impl Record {
+    fn json(&self) -> String {
+        format!(
+            "{{\"component\": \"{}\", \"kind\": \"{}\", \"code\": \"{}\", \"location\": \"{}\", \"constraint\": \"{}\", \"solver\": \"{}\", \"size\": {}, \"time_ms\": {:.3}, \"outcome\": \"{}\"}}",
+            escape(&self.comp),
+            self.kind,
+            self.code,
+            escape(&self.loc),
+            escape(&self.constraint),
+            self.query,
+            self.size,
+            self.time.as_secs_f64() * 1000.0,
+            self.status
+        )
+    }
+}
+
+/// Queries recorded for all components
+pub struct Profile {
+    path: PathBuf,
+    records: Vec<Record>,
+}
+
+impl Profile {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            records: vec![],
+        }
+    }
+
+    /// Record a query made to prove the fact
+    pub fn add(
+        &mut self,
+        fact: &ir::Fact,
+        comp: &ir::Component,
+        query: Query,
+        time: Duration,
+        status: Status,
+    ) {
+        let reason = comp.get(fact.reason).as_assert().map(|a| &a.0);
+        let loc = reason
+            .and_then(|r| super::location(&r.diag(comp)))
+            .unwrap_or_default();
+        self.records.push(Record {
+            comp: comp
+                .name
+                .as_ref()
+                .map_or_else(|| "<unknown>".to_string(), |n| n.to_string()),
+            kind: reason.map_or("unknown", |r| r.kind()),
+            code: reason.map_or("", |r| r.code()),
+            loc,
+            constraint: comp.display(fact.prop.consequent(comp)),
+            query,
+            size: size(comp, fact.prop),
+            time,
+            status,
+        })
+    }
+
+    /// Write all the records to the profile file.
+    pub fn write(&self) -> io::Result<()> {
+        let recs = self
+            .records
+            .iter()
+            .map(|r| format!("  {}", r.json()))
+            .join(",\n");
+        fs::write(&self.path, format!("[\n{recs}\n]\n"))
+    }
+
+    /// Print the slowest queries to stderr
+    pub fn summary(&self) {
+        let total: Duration = self.records.iter().map(|r| r.time).sum();
+        eprintln!(
+            "Discharged {} queries in {}ms (profile written to {})",
+            self.records.len(),
+            total.as_millis(),
+            self.path.display()
+        );
+        let slowest = self
+            .records
+            .iter()
+            .sorted_by(|a, b| b.time.cmp(&a.time))
+            .take(TOP);
+        for r in slowest {
+            eprintln!(
+                "{:>8}ms {:<10} {:<7} {:>5} {} [{}] {}: {}",
+                r.time.as_millis(),
+                r.query,
+                r.status,
+                r.size,
+                r.comp,
+                r.kind,
+                if r.loc.is_empty() {
+                    "<unknown>"
+                } else {
+                    &r.loc
+                },
+                r.constraint
+            );
+        }
+    }
+}
+
+/// Number of distinct terms in the proposition. Terms are shared in the
+/// encoding so this is the size of the formula the solver has to check.
+fn size(comp: &ir::Component, prop: ir::PropIdx) -> usize {
+    let mut props = HashSet::new();
+    let mut times = HashSet::new();
+    let mut exprs = HashSet::new();
+    let mut todo_props = vec![prop];
+    let mut todo_times = vec![];
+    let mut todo_exprs = vec![];
+    while let Some(p) = todo_props.pop() {
+        if !props.insert(p) {
+            continue;
+        }
+        match comp.get(p) {
+            ir::Prop::True | ir::Prop::False => (),
+            ir::Prop::Cmp(c) => todo_exprs.extend([c.lhs, c.rhs]),
+            ir::Prop::TimeCmp(c) => todo_times.extend([c.lhs, c.rhs]),
+            ir::Prop::TimeSubCmp(c) => {
+                for ts in [&c.lhs, &c.rhs] {
+                    match ts {
+                        ir::TimeSub::Unit(e) => todo_exprs.push(*e),
+                        ir::TimeSub::Sym { l, r } => {
+                            todo_times.extend([*l, *r])
+                        }
+                    }
+                }
+            }
+            ir::Prop::Not(p) => todo_props.push(*p),
+            ir::Prop::And(l, r)
+            | ir::Prop::Or(l, r)
+            | ir::Prop::Implies(l, r) => todo_props.extend([*l, *r]),
+        }
+    }
+    for t in todo_times {
+        if times.insert(t) {
+            todo_exprs.push(comp.get(t).offset);
+        }
+    }
+    while let Some(e) = todo_exprs.pop() {
+        if !exprs.insert(e) {
+            continue;
+        }
+        match comp.get(e) {
+            ir::Expr::Param(_) | ir::Expr::Concrete(_) => (),
+            ir::Expr::Bin { lhs, rhs, .. } => todo_exprs.extend([*lhs, *rhs]),
+            ir::Expr::Fn { args, .. } => todo_exprs.extend(args.iter()),
+        }
+    }
+    props.len() + times.len() + exprs.len()
+}
+
+/// Escape a string for use in a JSON string literal
+fn escape(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            '"' => "\\\"".to_string(),
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            c if c.is_control() => format!("\\u{:04x}", c as u32),
+            c => c.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,553 @@
+//! Annotated solver logs and their replay.
+//!
+//! With `--dump-solver-log`, every command sent to the external solver is
+//! written to a log along with comments describing the encoding: the source
+//! name and location of every declared constant, and before every
+//! `check-sat`, the facts checked by the query along with the diagnostic
+//! reported if they do not hold. Comments of the form `; key: value` are read
+//! back by `filament replay` which runs the log on a solver and reports the
+//! facts that do not hold using the recorded diagnostics.
+use super::portfolio::{parse_sexps, SExp};
+use super::solver_cmd;
+use crate::cmdline;
+use codespan_reporting::diagnostic as cr;
+use codespan_reporting::files::Files;
+use codespan_reporting::term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+};
+use fil_utils::{codes, GPosIdx, GlobalPositionTable};
+use itertools::Itertools;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::{fs, io, thread};
+
+/// Source location of the byte offset in the file as `file:line:column`
+pub fn span_location(file_id: usize, start: usize) -> Option<String> {
+    let files = GlobalPositionTable::as_ref().files();
+    let loc = files.location(file_id, start).ok()?;
+    Some(format!(
+        "{}:{}:{}",
+        files.name(file_id).ok()?,
+        loc.line_number,
+        loc.column_number
+    ))
+}
+
+/// Source location of the position as `file:line:column`
+pub fn pos_location(pos: GPosIdx) -> Option<String> {
+    let pos = pos.into_option()?;
+    let data = GlobalPositionTable::as_ref().get_pos(pos.0);
+    span_location(data.file.get(), data.start)
+}
+
+/// Escape newlines so that the text fits in a single comment
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                out.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                out.push('\\');
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// A solver log shared between the solver context, which writes the commands,
+/// and [super::Discharge], which writes the annotations.
+#[derive(Clone)]
+pub struct Log(Arc<Mutex<fs::File>>);
+
+impl Log {
+    /// Create the log and write its header
+    pub fn create(path: &str, solver: cmdline::Solver) -> io::Result<Self> {
+        let mut file = fs::File::create(path)?;
+        let (name, _) = solver_cmd(solver, None);
+        writeln!(file, "; filament solver log\n; solver: {name}")?;
+        Ok(Self(Arc::new(Mutex::new(file))))
+    }
+
+    /// Write each line of the text as a comment
+    pub fn comment(&self, text: &str) -> io::Result<()> {
+        let mut file = self.0.lock().unwrap();
+        for line in text.lines() {
+            writeln!(file, "; {line}")?;
+        }
+        Ok(())
+    }
+
+    /// Write commands to the log
+    pub fn commands(&self, text: &str) -> io::Result<()> {
+        self.0.lock().unwrap().write_all(text.as_bytes())
+    }
+}
+
+impl Write for Log {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.lock().unwrap().flush()
+    }
+}
+
+/// The comments describing a fact checked by the next query and the
+/// diagnostic reported if it does not hold
+pub fn describe_fact(
+    id: &str,
+    constraint: &str,
+    diag: &cr::Diagnostic<usize>,
+) -> String {
+    let mut lines = vec![format!("fact: {id} `{constraint}'")];
+    lines.push(match &diag.code {
+        Some(code) => format!("error: [{code}] {}", escape(&diag.message)),
+        None => format!("error: {}", escape(&diag.message)),
+    });
+    for label in &diag.labels {
+        let style = match label.style {
+            cr::LabelStyle::Primary => "primary",
+            cr::LabelStyle::Secondary => "secondary",
+        };
+        let loc =
+            span_location(label.file_id, label.range.start).unwrap_or_default();
+        lines.push(format!(
+            "label: {style} {} {} `{loc}' {}",
+            label.range.start,
+            label.range.end,
+            escape(&label.message)
+        ));
+    }
+    for note in &diag.notes {
+        lines.push(format!("note: {}", escape(note)));
+    }
+    lines.join("\n")
+}
+
+/// A label of a recorded diagnostic
+struct Label {
+    primary: bool,
+    start: usize,
+    end: usize,
+    /// Location of the label as `file:line:column`
+    loc: String,
+    message: String,
+}
+
+impl Label {
+    fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.splitn(4, ' ');
+        let primary = parts.next()? == "primary";
+        let start = parts.next()?.parse().ok()?;
+        let end = parts.next()?.parse().ok()?;
+        let (loc, message) =
+            parts.next()?.strip_prefix('`')?.split_once('\'')?;
+        Some(Self {
+            primary,
+            start,
+            end,
+            loc: loc.to_string(),
+            message: unescape(message.trim_start()),
+        })
+    }
+
+    /// The file containing the label
+    fn file(&self) -> Option<&str> {
+        let mut parts = self.loc.rsplitn(3, ':');
+        parts.next()?;
+        parts.next()?;
+        parts.next()
+    }
+}
+
+/// A fact checked by a query along with the diagnostic reported if it does not
+/// hold
+struct Fact {
+    /// Component and name of the proposition in the encoding
+    id: (String, String),
+    constraint: String,
+    code: Option<String>,
+    message: String,
+    labels: Vec<Label>,
+    notes: Vec<String>,
+}
+
+impl Fact {
+    /// Parse the name and constraint of the fact. The component is set once
+    /// the query is complete.
+    fn parse(s: &str) -> Self {
+        let (id, constraint) = s.split_once(' ').unwrap_or((s, ""));
+        Self {
+            id: (String::new(), id.to_string()),
+            constraint: constraint
+                .trim_start_matches('`')
+                .trim_end_matches('\'')
+                .to_string(),
+            code: None,
+            message: String::new(),
+            labels: vec![],
+            notes: vec![],
+        }
+    }
+
+    fn set_error(&mut self, s: &str) {
+        let (code, msg) =
+            match s.strip_prefix('[').and_then(|s| s.split_once("] ")) {
+                Some((code, msg)) => (Some(code.to_string()), msg),
+                None => (None, s),
+            };
+        self.code = code;
+        self.message = unescape(msg);
+    }
+}
+
+/// What a query checks
+enum Kind {
+    /// Whether a fact can be violated
+    Goal,
+    /// Whether any of the facts can be violated. Facts that do not hold are
+    /// checked again individually.
+    All,
+    /// Whether a fact can be violated when terms wrap around at the given
+    /// width
+    Wraparound(String),
+    /// A query without annotations
+    Unlabeled,
+}
+
+struct Query {
+    kind: Kind,
+    facts: Vec<Fact>,
+    /// The answer recorded when the log was written
+    recorded: Option<String>,
+}
+
+/// Queries and the solver recorded in the log
+struct Parsed {
+    solver: Option<String>,
+    queries: Vec<Query>,
+}
+
+fn parse(log: &str) -> Parsed {
+    let mut solver = None;
+    let mut comp = String::new();
+    let mut queries: Vec<Query> = vec![];
+    let mut pending: Option<Query> = None;
+    for line in log.lines() {
+        let line = line.trim();
+        if line.starts_with("(check-sat") {
+            let mut q = pending.take().unwrap_or(Query {
+                kind: Kind::Unlabeled,
+                facts: vec![],
+                recorded: None,
+            });
+            for f in &mut q.facts {
+                f.id.0 = comp.clone();
+            }
+            queries.push(q);
+            continue;
+        }
+        let Some((key, val)) =
+            line.strip_prefix("; ").and_then(|l| l.split_once(": "))
+        else {
+            continue;
+        };
+        let fact = pending.as_mut().and_then(|q| q.facts.last_mut());
+        match (key, fact) {
+            ("solver", _) => solver = Some(val.to_string()),
+            ("component", _) => comp = val.to_string(),
+            ("query", _) => {
+                let (kind, arg) = val.split_once(' ').unwrap_or((val, ""));
+                let kind = match kind {
+                    "goal" => Kind::Goal,
+                    "all" => Kind::All,
+                    "wraparound" => Kind::Wraparound(arg.to_string()),
+                    _ => Kind::Unlabeled,
+                };
+                pending = Some(Query {
+                    kind,
+                    facts: vec![],
+                    recorded: None,
+                });
+            }
+            ("fact", _) => {
+                if let Some(q) = &mut pending {
+                    q.facts.push(Fact::parse(val));
+                }
+            }
+            ("error", Some(f)) => f.set_error(val),
+            ("label", Some(f)) => f.labels.extend(Label::parse(val)),
+            ("note", Some(f)) => f.notes.push(unescape(val)),
+            ("answer", _) => {
+                if let Some(q) = queries.last_mut() {
+                    q.recorded = Some(val.to_string());
+                }
+            }
+            _ => (),
+        }
+    }
+    Parsed { solver, queries }
+}
+
+/// Run the log on the solver. Returns the answers to the queries in order and
+/// the errors reported by the solver.
+fn run(
+    log: String,
+    solver: cmdline::Solver,
+    timeout: Option<u64>,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let (name, args) = solver_cmd(solver, timeout);
+    let mut child = Command::new(name)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("`{name}' could not be started: {e}"))?;
+    let mut stdin = child.stdin.take().unwrap();
+    // Write from another thread so that the solver never blocks on its output
+    let writer = thread::spawn(move || {
+        let _ = stdin.write_all(log.as_bytes());
+        let _ = stdin.write_all(b"(exit)\n");
+    });
+    let mut out = String::new();
+    let res = child.stdout.take().unwrap().read_to_string(&mut out);
+    let _ = writer.join();
+    let _ = child.wait();
+    res.map_err(|e| format!("`{name}' failed: {e}"))?;
+
+    let sexps = parse_sexps(&out)
+        .ok_or_else(|| format!("`{name}' produced unexpected output"))?;
+    let mut answers = vec![];
+    let mut errors = vec![];
+    for e in sexps {
+        match e {
+            SExp::Atom(a)
+                if matches!(a.as_str(), "sat" | "unsat" | "unknown") =>
+            {
+                answers.push(a)
+            }
+            SExp::List(l) if matches!(l.first(), Some(SExp::Atom(a)) if a == "error") => {
+                errors.push(l.iter().skip(1).join(" "))
+            }
+            _ => (),
+        }
+    }
+    Ok((answers, errors))
+}
+
+/// Converts recorded diagnostics into diagnostics that refer to the files in
+/// the global position table
+#[derive(Default)]
+struct Sources {
+    /// Index of each file in the position table if it could be read
+    files: HashMap<String, Option<usize>>,
+}
+
+impl Sources {
+    fn file(&mut self, name: &str) -> Option<usize> {
+        *self.files.entry(name.to_string()).or_insert_with(|| {
+            let source = fs::read_to_string(name).ok()?;
+            let table = GlobalPositionTable::as_mut();
+            Some(table.add_file(name.to_string(), source).get())
+        })
+    }
+
+    fn diag(&mut self, fact: &Fact) -> cr::Diagnostic<usize> {
+        let mut diag = cr::Diagnostic::error().with_message(&fact.message);
+        if let Some(code) = &fact.code {
+            diag = diag.with_code(code);
+        }
+        let mut notes = vec![];
+        for label in &fact.labels {
+            let file = label.file().and_then(|f| self.file(f));
+            let in_bounds = file.map_or(false, |f| {
+                let files = GlobalPositionTable::as_ref().files();
+                files.source(f).map_or(false, |s| {
+                    label.start <= label.end && label.end <= s.len()
+                })
+            });
+            match file {
+                Some(file) if in_bounds => {
+                    let l = if label.primary {
+                        cr::Label::primary(file, label.start..label.end)
+                    } else {
+                        cr::Label::secondary(file, label.start..label.end)
+                    };
+                    diag.labels.push(l.with_message(&label.message));
+                }
+                _ => notes.push(format!("{}: {}", label.loc, label.message)),
+            }
+        }
+        diag.notes = notes;
+        diag.notes.extend(fact.notes.iter().cloned());
+        diag.notes
+            .push(format!("Cannot prove constraint: {}", fact.constraint));
+        diag
+    }
+}
+
+/// Re-run the queries in a solver log written by `--dump-solver-log` and
+/// report the facts that do not hold. Returns the number of errors.
+pub fn replay(opts: &cmdline::Replay) -> Result<(), u64> {
+    let path = opts.log.display();
+    let log = match fs::read_to_string(&opts.log) {
+        Ok(log) => log,
+        Err(e) => {
+            eprintln!("error: {path}: {e}");
+            return Err(1);
+        }
+    };
+    let Parsed { solver, queries } = parse(&log);
+    let solver = match (opts.solver, solver) {
+        (Some(s), _) => Ok(s),
+        (None, Some(s)) => s.parse(),
+        (None, None) => {
+            Err("log does not name a solver. Use `--solver`.".to_string())
+        }
+    };
+    let solver = match solver {
+        Ok(cmdline::Solver::Builtin) => {
+            Err("logs must be replayed by an external solver".to_string())
+        }
+        s => s,
+    };
+    let res = solver.and_then(|s| run(log, s, opts.solver_timeout));
+    let (answers, solver_errors) = match res {
+        Ok(res) => res,
+        Err(e) => {
+            eprintln!("error: {path}: {e}");
+            return Err(1);
+        }
+    };
+    for e in &solver_errors {
+        log::warn!("Solver reported an error: {e}");
+    }
+    if answers.len() < queries.len() {
+        eprintln!(
+            "error: {path}: the solver answered {} of {} queries",
+            answers.len(),
+            queries.len()
+        );
+        return Err(1);
+    }
+
+    let mut sources = Sources::default();
+    let mut diags = vec![];
+    let mut differ = 0;
+    for (i, (q, answer)) in queries.iter().zip(&answers).enumerate() {
+        let mut note = format!("Query {} of {path} answered `{answer}'", i + 1);
+        match &q.recorded {
+            Some(rec) if rec != answer => {
+                differ += 1;
+                note += &format!(" but `{rec}' when the log was written");
+            }
+            _ => (),
+        }
+        if answer == "unsat" && !matches!(q.kind, Kind::Wraparound(_)) {
+            continue;
+        }
+        match &q.kind {
+            Kind::Goal => {
+                for fact in &q.facts {
+                    let mut d = sources.diag(fact);
+                    if answer == "unknown" {
+                        d.notes.push(
+                            "The solver could not decide this constraint."
+                                .to_string(),
+                        );
+                    }
+                    d.notes.push(note.clone());
+                    diags.push(d);
+                }
+            }
+            Kind::All => {
+                // Facts checked again by a later query are reported by it
+                let later = queries[i + 1..]
+                    .iter()
+                    .filter(|q| matches!(q.kind, Kind::Goal))
+                    .flat_map(|q| q.facts.iter().map(|f| &f.id))
+                    .collect_vec();
+                for fact in q.facts.iter().filter(|f| !later.contains(&&f.id)) {
+                    let mut d = sources.diag(fact);
+                    d.notes.push(format!(
+                        "The constraint was checked together with {} others and at least one of them may not hold.",
+                        q.facts.len() - 1
+                    ));
+                    d.notes.push(note.clone());
+                    diags.push(d);
+                }
+            }
+            Kind::Wraparound(width) => {
+                for fact in &q.facts {
+                    let mut d = sources.diag(fact);
+                    let msg = if answer == "unsat" {
+                        format!("constraint only holds modulo {width}-bit wraparound")
+                    } else {
+                        format!("constraint could not be verified using {width}-bit bitvectors")
+                    };
+                    let orig = std::mem::replace(&mut d.message, msg);
+                    d.code = Some(codes::WRAPAROUND.to_string());
+                    d.notes.insert(0, format!("Required because: {orig}"));
+                    d.notes.push(note.clone());
+                    diags.push(d);
+                }
+            }
+            Kind::Unlabeled => {
+                log::warn!(
+                    "{note}. The query does not describe the facts it checks."
+                );
+            }
+        }
+    }
+
+    let is_tty = atty::is(atty::Stream::Stderr);
+    let writer = StandardStream::stderr(if is_tty {
+        ColorChoice::Always
+    } else {
+        ColorChoice::Never
+    });
+    let table = GlobalPositionTable::as_ref();
+    for diag in &diags {
+        term::emit(
+            &mut writer.lock(),
+            &term::Config::default(),
+            table.files(),
+            diag,
+        )
+        .unwrap();
+    }
+
+    let count = |a: &str| answers.iter().filter(|b| *b == a).count();
+    let mut summary = format!(
+        "Replayed {} queries from {path}: {} unsat, {} sat, {} unknown",
+        queries.len(),
+        count("unsat"),
+        count("sat"),
+        count("unknown")
+    );
+    if differ > 0 {
+        summary +=
+            &format!(" ({differ} differ from the answers recorded in the log)");
+    }
+    println!("{summary}.");
+    if diags.is_empty() {
+        Ok(())
+    } else {
+        Err(diags.len() as u64)
+    }
+}
@@ -5,11 +5,29 @@ use itertools::Itertools;
 pub struct DumpInterface;
 
 impl DumpInterface {
-    /// Print out the interface of the main component in JSON format
+    /// Print out the interface of the main component in JSON format. With
+    /// several main components, prints an object mapping the name of each
+    /// component to its interface.
     pub fn print(ctx: &ir::Context) {
-        let entrypoint = ctx
-            .entrypoint
-            .unwrap_or_else(|| panic!("No entrypoint found."));
+        match &ctx.entrypoints[..] {
+            [] => panic!("No entrypoint found."),
+            [entrypoint] => println!("{}", Self::interface(ctx, *entrypoint)),
+            entrypoints => {
+                let interfaces = entrypoints
+                    .iter()
+                    .map(|idx| {
+                        let name =
+                            ctx.get(*idx).src_info.as_ref().unwrap().name;
+                        format!("\"{name}\": {}", Self::interface(ctx, *idx))
+                    })
+                    .join(",\n");
+                println!("{{\n{interfaces}\n}}");
+            }
+        }
+    }
+
+    /// The interface of the component in JSON format
+    fn interface(ctx: &ir::Context, entrypoint: ir::CompIdx) -> String {
         let main = ctx.get(entrypoint);
         let src_info = main
             .src_info
@@ -88,8 +106,8 @@ impl DumpInterface {
         let outputs = main.outputs().map(pd_to_info).collect_vec().join(",\n");
 
         // Look ma, a JSON serializer!
-        println!(
+        format!(
             "{{\n\"interfaces\": [\n{interfaces}\n],\n\"inputs\": [\n{inputs}\n],\n\"outputs\": [\n{outputs}\n]\n}}",
-        );
+        )
     }
 }
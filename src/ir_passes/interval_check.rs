@@ -360,4 +360,45 @@ impl Visitor for IntervalCheck {
             Action::Continue
         }
     }
+    /// The ports read by an assertion must be available during the interval
+    /// of the assertion.
+    fn value_assert(
+        &mut self,
+        a: &mut ir::ValueAssert,
+        data: &mut VisitorData,
+    ) -> Action {
+        let comp = &mut data.comp;
+        let &ir::info::ValueAssert {
+            lhs_loc,
+            rhs_loc,
+            range_loc,
+            ..
+        } = comp.get(a.info).into();
+        let mut cons = vec![];
+        let terms = [(&a.cons.lhs, lhs_loc), (&a.cons.rhs, rhs_loc)];
+        for (term, loc) in terms {
+            let ir::ValueTerm::Port(access) = term else {
+                continue;
+            };
+            let typ = access.bundle_typ(comp);
+            let in_range = Self::in_range(&typ, comp);
+            let contains = typ
+                .range
+                .start
+                .lte(a.range.start, comp)
+                .and(typ.range.end.gte(a.range.end, comp), comp);
+            let reason = comp.add(
+                ir::info::Reason::liveness(
+                    range_loc,
+                    loc,
+                    a.range.clone(),
+                    typ.range,
+                )
+                .into(),
+            );
+            let prop = in_range.implies(contains, comp);
+            cons.extend(comp.assert(prop, reason));
+        }
+        Action::AddBefore(cons)
+    }
 }
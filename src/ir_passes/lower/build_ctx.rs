@@ -75,7 +75,7 @@ impl<'a> BuildCtx<'a> {
         let inst = self.comp.get(idx);
         // generate a unique name for this instance
         let inst_name = self.ng.instance_name(idx, self.comp);
-        let comp_name = self.ng.comp_name(inst.comp, self.ctx);
+        let comp_name = self.ng.comp_name(inst.comp);
 
         let cell = if let Some(sig) = self.binding.get(&inst.comp) {
             // this component has is in the binding signature (it has been compiled and is non-primitive)
@@ -7,6 +7,7 @@ use calyx_frontend as frontend;
 use calyx_ir as calyx;
 use calyx_utils::CalyxResult;
 use fil_ir::{self as ir, Ctx, Traversal};
+use itertools::Itertools;
 use std::{collections::HashSet, convert::identity, path::PathBuf, rc::Rc};
 
 #[derive(Default)]
@@ -160,7 +161,7 @@ impl Compile {
         );
 
         calyx::Primitive {
-            name: name_gen.comp_name(idx, ctx).into(),
+            name: name_gen.comp_name(idx).into(),
             params: comp
                 .params()
                 .iter()
@@ -208,7 +209,7 @@ impl Compile {
             name_gen,
         );
         let mut component = calyx::Component::new(
-            name_gen.comp_name(idx, ctx),
+            name_gen.comp_name(idx),
             ports,
             false,
             false,
@@ -216,8 +217,8 @@ impl Compile {
         );
         component.attributes.insert(calyx::BoolAttr::NoInterface, 1);
 
-        // If this is the main component, give it a `@top_level` attribute
-        if Some(idx) == ctx.entrypoint {
+        // If this is a main component, give it a `@top_level` attribute
+        if ctx.is_main(idx) {
             log::debug!("Defining main component {idx}");
             component.attributes.insert(calyx::BoolAttr::TopLevel, 1);
         }
@@ -263,7 +264,8 @@ impl Compile {
                 }
                 ir::Command::Instance(_) // ignore instances and invokes as these are compiled first
                 | ir::Command::Invoke(_)
-                | ir::Command::Fact(_) => (),
+                | ir::Command::Fact(_)
+                | ir::Command::ValueAssert(_) => (),
             }
         }
 
@@ -295,6 +297,41 @@ impl Compile {
         Ok(ctx)
     }
 
+    /// A JSON object mapping the name of each generated module to the source
+    /// components and the values of their parameters that it implements
+    pub fn name_map(ctx: &ir::Context) -> String {
+        let name_gen = NameGenerator::new(false, ctx);
+        let entries = ctx
+            .comps
+            .iter()
+            .filter(|(_, comp)| {
+                !comp.is_ext && !comp.specializations.is_empty()
+            })
+            .map(|(idx, comp)| {
+                let specs = comp
+                    .specializations
+                    .iter()
+                    .sorted_by_key(|spec| NameGenerator::spec_key(spec))
+                    .map(|spec| {
+                        let params = spec
+                            .params
+                            .iter()
+                            .map(|(name, v)| format!("\"{name}\": {v}"))
+                            .join(", ");
+                        format!(
+                            "{{\"component\": \"{}\", \"params\": {{{params}}}}}",
+                            spec.name
+                        )
+                    })
+                    .join(", ");
+                (name_gen.comp_name(idx), specs)
+            })
+            .sorted()
+            .map(|(name, specs)| format!("  \"{name}\": [{specs}]"))
+            .join(",\n");
+        format!("{{\n{entries}\n}}\n")
+    }
+
     /// Compiles filament into calyx
     pub fn compile(
         ctx: ir::Context,
@@ -305,7 +342,7 @@ impl Compile {
         let externals =
             ctx.externals.iter().map(|(k, v)| (k, v.clone())).collect();
 
-        let name_gen = NameGenerator::new(debug);
+        let name_gen = NameGenerator::new(debug, &ctx);
 
         let mut calyx_ctx = Compile::init(&ctx, externals, &name_gen)
             .unwrap_or_else(|e| {
@@ -314,6 +351,8 @@ impl Compile {
 
         let mut bindings = Binding::default();
 
+        let entrypoint =
+            ctx.entrypoints.first().map(|idx| name_gen.comp_name(*idx));
         let po = Traversal::from(ctx);
 
         // Compile the components in post-order.
@@ -333,6 +372,12 @@ impl Compile {
         // add the fsm components to the calyx context
         calyx_ctx.components.extend(bindings.fsm_comps.take());
 
+        // Calyx requires a single entrypoint. The other main components are
+        // marked with `@top_level`.
+        if let Some(name) = entrypoint {
+            calyx_ctx.entrypoint = calyx::Id::from(name);
+        }
+
         calyx_ctx
     }
 }
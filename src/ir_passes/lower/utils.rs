@@ -4,6 +4,7 @@ use fil_ir::{
     InfoIdx, InstIdx, Instance, ParamIdx, PortIdx,
 };
 use linked_hash_map::LinkedHashMap;
+use std::collections::{HashMap, HashSet};
 
 type AttrPair = (calyx::Attribute, u64);
 /// A set of interface ports that are required for all components.
@@ -22,11 +23,72 @@ pub(super) const INTERFACE_PORTS: [(AttrPair, (&str, u64, calyx::Direction));
 /// Helper struct that generates names for [crate::utils::Idx]s given their [Ctx].
 pub(super) struct NameGenerator {
     use_info: bool,
+    /// Names of the components in the context. Distinct components always
+    /// have distinct names.
+    comp_names: HashMap<CompIdx, String>,
 }
 
 impl NameGenerator {
-    pub fn new(use_info: bool) -> Self {
-        Self { use_info }
+    pub fn new(use_info: bool, ctx: &Context) -> Self {
+        Self {
+            use_info,
+            comp_names: Self::comp_names(ctx),
+        }
+    }
+
+    /// Name every component in the context. Externals, toplevel components,
+    /// and components without parameters keep their source names.
+    /// Monomorphized components are named after their source component and
+    /// the values of its parameters, and a suffix is added if that name is
+    /// already taken by another component. Names only depend on the
+    /// specializations of the components and not on their order.
+    fn comp_names(ctx: &Context) -> HashMap<CompIdx, String> {
+        let mut names = HashMap::new();
+        let mut taken = HashSet::new();
+        // The smallest specialization of each monomorphized component
+        let mut specs = vec![];
+        for (idx, comp) in ctx.comps.iter() {
+            let spec = comp
+                .specializations
+                .iter()
+                .min_by_key(|spec| Self::spec_key(spec));
+            let src = match (&comp.src_info, spec) {
+                (Some(src), _) => src.name.to_string(),
+                (None, Some(spec)) if spec.params.is_empty() => {
+                    spec.name.to_string()
+                }
+                (None, Some(spec)) => {
+                    specs.push((
+                        Self::spec_key(spec),
+                        Self::spec_name(spec),
+                        idx,
+                    ));
+                    continue;
+                }
+                (None, None) => format!("comp{}", idx.get()),
+            };
+            taken.insert(src.clone());
+            names.insert(idx, src);
+        }
+        specs.sort();
+        for (_, base, idx) in specs {
+            let mut name = base.clone();
+            let mut suffix = 1;
+            while !taken.insert(name.clone()) {
+                name = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            names.insert(idx, name);
+        }
+        names
+    }
+
+    /// Orders specializations by their source component and parameter values
+    pub fn spec_key(spec: &ir::Specialization) -> (String, Vec<u64>) {
+        (
+            spec.name.to_string(),
+            spec.params.iter().map(|(_, v)| *v).collect(),
+        )
     }
 
     /// Helper function to generate the name of an [crate::utils::Idx] using its info if the debug flag is enabled.
@@ -111,12 +173,34 @@ impl NameGenerator {
     }
 
     /// Returns the name of a [Component]
-    pub fn comp_name(&self, idx: CompIdx, ctx: &impl Ctx<Component>) -> String {
-        ctx.get(idx)
-            .src_info
-            .as_ref()
-            .map(|src| src.name.to_string())
-            .unwrap_or_else(|| format!("comp{}", idx.get()))
+    pub fn comp_name(&self, idx: CompIdx) -> String {
+        self.comp_names[&idx].clone()
+    }
+
+    /// Name of a specialization like `Shift_W32_N4`. Long parameter lists are
+    /// replaced by a hash of the values so that names stay manageable. The
+    /// name may be shared with other components so [Self::comp_names]
+    /// disambiguates it.
+    fn spec_name(spec: &ir::Specialization) -> String {
+        const MAX_PARAMS_LEN: usize = 32;
+        let params = spec
+            .params
+            .iter()
+            .map(|(name, v)| format!("_{name}{v}"))
+            .collect::<String>();
+        if params.len() <= MAX_PARAMS_LEN {
+            return format!("{}{params}", spec.name);
+        }
+        // 32-bit FNV-1a which, unlike the hashers of the standard library, is
+        // stable across compiler versions.
+        let hash = spec
+            .params
+            .iter()
+            .flat_map(|(name, v)| format!("{name}={v};").into_bytes())
+            .fold(0x811c9dc5_u32, |h, b| {
+                (h ^ u32::from(b)).wrapping_mul(0x01000193)
+            });
+        format!("{}_{hash:08x}", spec.name)
     }
 }
 
@@ -0,0 +1,78 @@
+use super::ShareInstances;
+use crate::ir_passes::lower::max_states;
+use fil_ir::{self as ir, Ctx};
+use std::collections::BTreeMap;
+
+/// Metrics of a lowered design used to compare configurations of a
+/// component. The context must have been monomorphized and bundles must have
+/// been eliminated.
+pub struct Metrics {
+    /// Cycles from the start of a transaction until the last output is
+    /// available
+    pub latency: u64,
+    /// Largest delay of the events of the component
+    pub ii: u64,
+    /// Largest number of states of the FSMs of the component
+    pub states: u64,
+    /// Number of instances of each primitive in the whole design
+    pub counts: BTreeMap<String, u64>,
+    /// Estimated area in bits of datapath of the primitives
+    pub area: u64,
+}
+
+impl Metrics {
+    /// Compute the metrics of the design rooted at the first entrypoint
+    pub fn new(ctx: &ir::Context) -> Self {
+        let idx = *ctx.entrypoints.first().expect("No entrypoint found.");
+        let comp = ctx.get(idx);
+        let latency = comp
+            .outputs()
+            .filter_map(|(_, p)| {
+                comp.get(p.live.range.start).offset.as_concrete(comp)
+            })
+            .max()
+            .unwrap_or(0);
+        let ii = comp
+            .events()
+            .iter()
+            .filter_map(|(_, ev)| match &ev.delay {
+                ir::TimeSub::Unit(d) => d.as_concrete(comp),
+                ir::TimeSub::Sym { .. } => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let states = max_states(comp).values().copied().max().unwrap_or(0);
+
+        let mut metrics = Metrics {
+            latency,
+            ii,
+            states,
+            counts: BTreeMap::new(),
+            area: 0,
+        };
+        metrics.count(ctx, idx, 1);
+        metrics
+    }
+
+    /// Count the primitives used by `mult` instances of the component
+    fn count(&mut self, ctx: &ir::Context, idx: ir::CompIdx, mult: u64) {
+        let comp = ctx.get(idx);
+        for (_, inst) in comp.instances().iter() {
+            let sub = ctx.get(inst.comp);
+            if sub.is_ext {
+                let name = sub
+                    .src_info
+                    .as_ref()
+                    .map(|src| src.name)
+                    .or(sub.name)
+                    .map(|n| n.to_string())
+                    .unwrap_or_default();
+                *self.counts.entry(name).or_default() += mult;
+                let (ins, outs) = ShareInstances::port_bits(inst, comp, ctx);
+                self.area += (ins + outs) * mult;
+            } else {
+                self.count(ctx, inst.comp, mult);
+            }
+        }
+    }
+}
@@ -1,27 +1,37 @@
 mod assignment_check;
 mod assume;
+mod bmc;
 mod build_domination;
 mod bundle_elim;
+mod comb_loop_check;
 mod discharge;
 mod dump_interface;
 mod hoist_facts;
 mod interval_check;
 mod lower;
+mod metrics;
 mod mono;
 mod phantom_check;
 mod prop_simplify;
+mod share_instances;
 mod type_check;
+mod unused_check;
 
 pub use assignment_check::AssignCheck;
 pub use assume::Assume;
+pub use bmc::{check_equiv, Bmc};
 pub use build_domination::BuildDomination;
 pub use bundle_elim::BundleElim;
-pub use discharge::Discharge;
+pub use comb_loop_check::CombLoopCheck;
+pub use discharge::{replay, verify_certs, Discharge};
 pub use dump_interface::DumpInterface;
 pub use hoist_facts::HoistFacts;
 pub use interval_check::IntervalCheck;
 pub use lower::Compile;
-pub use mono::Monomorphize;
+pub use metrics::Metrics;
+pub use mono::{Dedup, Monomorphize};
 pub use phantom_check::PhantomCheck;
 pub use prop_simplify::Simplify;
+pub use share_instances::ShareInstances;
 pub use type_check::TypeCheck;
+pub use unused_check::UnusedCheck;
@@ -0,0 +1,362 @@
+use fil_ir::{self as ir, AddCtx, Ctx, MutCtx};
+use itertools::Itertools;
+use std::collections::{HashMap, HashSet};
+
+/// Canonical textual form of a component. Entities defined by the component
+/// are named by their index while interned values (expressions, times, and
+/// propositions) are expanded because the same value may be interned at
+/// different indices in different components.
+struct Canon<'a> {
+    comp: &'a ir::Component,
+}
+
+impl Canon<'_> {
+    fn expr(&self, e: ir::ExprIdx) -> String {
+        match self.comp.get(e) {
+            ir::Expr::Param(p) => p.to_string(),
+            ir::Expr::Concrete(n) => n.to_string(),
+            ir::Expr::Bin { op, lhs, rhs } => {
+                format!("({} {op} {})", self.expr(*lhs), self.expr(*rhs))
+            }
+            ir::Expr::Fn { op, args } => {
+                format!(
+                    "{op}({})",
+                    args.iter().map(|a| self.expr(*a)).join(",")
+                )
+            }
+        }
+    }
+
+    fn time(&self, t: ir::TimeIdx) -> String {
+        let ir::Time { event, offset } = self.comp.get(t);
+        format!("{event}+{}", self.expr(*offset))
+    }
+
+    fn time_sub(&self, ts: &ir::TimeSub) -> String {
+        match ts {
+            ir::TimeSub::Unit(e) => self.expr(*e),
+            ir::TimeSub::Sym { l, r } => {
+                format!("|{} - {}|", self.time(*l), self.time(*r))
+            }
+        }
+    }
+
+    fn cmp<T>(&self, c: &ir::CmpOp<T>, f: impl Fn(&T) -> String) -> String {
+        format!("{} {} {}", f(&c.lhs), c.op, f(&c.rhs))
+    }
+
+    fn prop(&self, p: ir::PropIdx) -> String {
+        match self.comp.get(p) {
+            ir::Prop::True => "true".to_string(),
+            ir::Prop::False => "false".to_string(),
+            ir::Prop::Cmp(c) => self.cmp(c, |e| self.expr(*e)),
+            ir::Prop::TimeCmp(c) => self.cmp(c, |t| self.time(*t)),
+            ir::Prop::TimeSubCmp(c) => self.cmp(c, |ts| self.time_sub(ts)),
+            ir::Prop::Not(p) => format!("!({})", self.prop(*p)),
+            ir::Prop::And(l, r) => {
+                format!("({} & {})", self.prop(*l), self.prop(*r))
+            }
+            ir::Prop::Or(l, r) => {
+                format!("({} | {})", self.prop(*l), self.prop(*r))
+            }
+            ir::Prop::Implies(l, r) => {
+                format!("({} => {})", self.prop(*l), self.prop(*r))
+            }
+        }
+    }
+
+    fn range(&self, r: &ir::Range) -> String {
+        format!("[{}, {}]", self.time(r.start), self.time(r.end))
+    }
+
+    fn access(&self, a: &ir::Access) -> String {
+        let ranges = a
+            .ranges
+            .iter()
+            .map(|(s, e)| format!("{}:{}", self.expr(*s), self.expr(*e)))
+            .join(",");
+        format!("{}{{{ranges}}}", a.port)
+    }
+
+    fn cmds(&self, cmds: &[ir::Command]) -> String {
+        cmds.iter().map(|c| self.cmd(c)).join("; ")
+    }
+
+    fn cmd(&self, cmd: &ir::Command) -> String {
+        match cmd {
+            ir::Command::Instance(inst) => format!("inst {inst}"),
+            ir::Command::Invoke(inv) => format!("invoke {inv}"),
+            ir::Command::BundleDef(p) => format!("bundle {p}"),
+            ir::Command::Connect(ir::Connect { src, dst, .. }) => {
+                format!("{} = {}", self.access(dst), self.access(src))
+            }
+            ir::Command::ForLoop(ir::Loop {
+                index,
+                start,
+                end,
+                body,
+            }) => format!(
+                "for {index} in {}..{} {{{}}}",
+                self.expr(*start),
+                self.expr(*end),
+                self.cmds(body)
+            ),
+            ir::Command::If(ir::If { cond, then, alt }) => format!(
+                "if {} {{{}}} else {{{}}}",
+                self.prop(*cond),
+                self.cmds(then),
+                self.cmds(alt)
+            ),
+            ir::Command::Fact(f) => format!(
+                "{} {}",
+                if f.is_assert() { "assert" } else { "assume" },
+                self.prop(f.prop)
+            ),
+            ir::Command::Exists(ir::Exists { param, expr }) => {
+                format!("exists {param} = {}", self.expr(*expr))
+            }
+            ir::Command::ValueAssert(ir::ValueAssert {
+                cons, range, ..
+            }) => {
+                let term = |t: &ir::ValueTerm| match t {
+                    ir::ValueTerm::Port(a) => self.access(a),
+                    ir::ValueTerm::Expr(e) => self.expr(*e),
+                };
+                format!(
+                    "value {} in {}",
+                    self.cmp(cons, term),
+                    self.range(range)
+                )
+            }
+        }
+    }
+
+    /// The canonical form of the whole component. Only specializations of
+    /// the same source component have the same form.
+    fn component(&self) -> String {
+        let comp = self.comp;
+        let source = comp.specializations.first().map(|s| s.name);
+        let mut lines = vec![
+            format!(
+                "source {}",
+                source.map(|n| n.to_string()).unwrap_or_default()
+            ),
+            format!("params {}", comp.param_args().iter().join(",")),
+            format!("events {}", comp.event_args().iter().join(",")),
+        ];
+        lines.extend(comp.exist_params().map(|p| {
+            let assumes = comp.get_exist_assumes(p).unwrap_or_default();
+            format!(
+                "exists {p}: {}",
+                assumes.iter().map(|a| self.prop(*a)).join(", ")
+            )
+        }));
+        lines.extend(
+            comp.get_param_asserts()
+                .iter()
+                .chain(comp.get_event_asserts())
+                .map(|p| format!("where {}", self.prop(*p))),
+        );
+        lines.extend(comp.params().iter().map(|(idx, p)| {
+            let owner = match &p.owner {
+                ir::ParamOwner::Sig => "sig".to_string(),
+                ir::ParamOwner::Exists { opaque } => {
+                    format!("exists(opaque={opaque})")
+                }
+                ir::ParamOwner::Instance { inst, base } => {
+                    format!("{inst}.{}@{}", base.key(), base.owner())
+                }
+                ir::ParamOwner::Bundle(p) => format!("bundle {p}"),
+                ir::ParamOwner::Loop => "loop".to_string(),
+            };
+            format!("{idx}: {owner}")
+        }));
+        lines.extend(comp.events().iter().map(|(idx, ev)| {
+            format!(
+                "{idx}: delay {} interface {}",
+                self.time_sub(&ev.delay),
+                ev.has_interface
+            )
+        }));
+        lines.extend(comp.ports().iter().map(|(idx, p)| {
+            let owner = match &p.owner {
+                ir::PortOwner::Sig { dir } => format!("sig {dir}"),
+                ir::PortOwner::Inv { inv, dir, base } => {
+                    format!("{inv} {dir} {}@{}", base.key(), base.owner())
+                }
+                ir::PortOwner::Local => "local".to_string(),
+            };
+            let live = &p.live;
+            format!(
+                "{idx}: {owner} [{}; {}] @ {}: {}",
+                live.idxs.iter().join(","),
+                live.lens.iter().map(|l| self.expr(*l)).join(","),
+                self.range(&live.range),
+                self.expr(p.width)
+            )
+        }));
+        lines.extend(comp.instances().iter().map(|(idx, inst)| {
+            format!(
+                "{idx}: {}[{}] lives {} params {}",
+                inst.comp,
+                inst.args.iter().map(|a| self.expr(*a)).join(","),
+                inst.lives.iter().map(|r| self.range(r)).join(","),
+                inst.params.iter().join(",")
+            )
+        }));
+        lines.extend(comp.invocations().iter().map(|(idx, inv)| {
+            let events = inv
+                .events
+                .iter()
+                .map(|eb| {
+                    format!(
+                        "{}@{}: {} delay {}",
+                        eb.base.key(),
+                        eb.base.owner(),
+                        self.time(eb.arg),
+                        self.time_sub(&eb.delay)
+                    )
+                })
+                .join(",");
+            format!(
+                "{idx}: {}<{events}> ports {}",
+                inv.inst,
+                inv.ports.iter().join(",")
+            )
+        }));
+        lines.extend(
+            comp.unannotated_ports
+                .iter()
+                .map(|(name, width)| format!("unannotated {name}: {width}")),
+        );
+        lines.push(self.cmds(&comp.cmds));
+        lines.join("\n")
+    }
+}
+
+/// Shares a single component between all the monomorphized components of the
+/// same source component with the same structure. Different
+/// parameterizations of a component often produce identical components, for
+/// example, when a parameter is only used by assertions that have been
+/// simplified away.
+pub struct Dedup;
+
+impl Dedup {
+    /// Add the component and the components it instantiates to `order` such
+    /// that every component comes after the components it instantiates
+    fn order(
+        ctx: &ir::Context,
+        idx: ir::CompIdx,
+        visited: &mut HashSet<ir::CompIdx>,
+        order: &mut Vec<ir::CompIdx>,
+    ) {
+        if !visited.insert(idx) {
+            return;
+        }
+        for (_, inst) in ctx.get(idx).instances().iter() {
+            if !ctx.is_ext(inst.comp) {
+                Self::order(ctx, inst.comp, visited, order);
+            }
+        }
+        order.push(idx);
+    }
+
+    /// Rename the components used by the instances of the component and by
+    /// the foreign keys that refer to the instantiated components.
+    pub(super) fn redirect(
+        comp: &mut ir::Component,
+        rep: &HashMap<ir::CompIdx, ir::CompIdx>,
+    ) {
+        let get = |idx: ir::CompIdx| rep.get(&idx).copied().unwrap_or(idx);
+
+        for idx in comp.instances().idx_iter().collect_vec() {
+            let inst = comp.get_mut(idx);
+            inst.comp = get(inst.comp);
+        }
+        for idx in comp.ports().idx_iter().collect_vec() {
+            if let ir::PortOwner::Inv { base, .. } =
+                &mut comp.get_mut(idx).owner
+            {
+                *base = ir::Foreign::new(base.key(), get(base.owner()));
+            }
+        }
+        for idx in comp.params().idx_iter().collect_vec() {
+            if let ir::ParamOwner::Instance { base, .. } =
+                &mut comp.get_mut(idx).owner
+            {
+                *base = ir::Foreign::new(base.key(), get(base.owner()));
+            }
+        }
+        for idx in comp.invocations().idx_iter().collect_vec() {
+            for eb in &mut comp.get_mut(idx).events {
+                eb.base = ir::Foreign::new(eb.base.key(), get(eb.base.owner()));
+            }
+        }
+    }
+
+    /// Remove the components that are structurally identical to another
+    /// component. Entrypoints and external components are never removed.
+    /// The remaining components are renumbered so that the indices of the
+    /// new context are contiguous.
+    pub fn transform(mut ctx: ir::Context) -> ir::Context {
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        for idx in ctx.comps.idx_iter().collect_vec() {
+            if !ctx.is_ext(idx) {
+                Self::order(&ctx, idx, &mut visited, &mut order);
+            }
+        }
+
+        // Components are processed after the components they instantiate so
+        // that their instances already refer to the representatives.
+        let mut rep = HashMap::new();
+        let mut canon: HashMap<String, ir::CompIdx> = HashMap::new();
+        for idx in order {
+            Self::redirect(ctx.get_mut(idx), &rep);
+            if ctx.is_main(idx) {
+                continue;
+            }
+            let key = Canon { comp: ctx.get(idx) }.component();
+            if let Some(&first) = canon.get(&key) {
+                log::debug!("{idx} is identical to {first}");
+                let specs =
+                    std::mem::take(&mut ctx.get_mut(idx).specializations);
+                ctx.get_mut(first).specializations.extend(specs);
+                rep.insert(idx, first);
+            } else {
+                canon.insert(key, idx);
+            }
+        }
+        if rep.is_empty() {
+            return ctx;
+        }
+        log::info!("Removed {} duplicate components", rep.len());
+
+        let mut out = ir::Context::default();
+        let mut rename = HashMap::new();
+        for idx in ctx.comps.idx_iter().collect_vec() {
+            if !rep.contains_key(&idx) {
+                let comp = std::mem::take(ctx.get_mut(idx));
+                rename.insert(idx, out.add(comp));
+            }
+        }
+        for idx in out.comps.idx_iter().collect_vec() {
+            Self::redirect(out.get_mut(idx), &rename);
+        }
+        out.entrypoints =
+            ctx.entrypoints.iter().map(|idx| rename[idx]).collect();
+        out.externals = ctx
+            .externals
+            .into_iter()
+            .map(|(file, comps)| {
+                (file, comps.iter().map(|idx| rename[idx]).collect())
+            })
+            .collect();
+        out.entry_args = ctx
+            .entry_args
+            .into_iter()
+            .map(|(idx, args)| (rename[&idx], args))
+            .collect();
+        out
+    }
+}
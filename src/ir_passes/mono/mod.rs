@@ -1,3 +1,4 @@
+mod dedup;
 mod global;
 mod monodeferred;
 mod monomorphize;
@@ -11,4 +12,5 @@ pub(super) use utils::{
     Base, BaseComp, IntoBase, IntoUdl, Underlying, UnderlyingComp,
 };
 
+pub use dedup::Dedup;
 pub use monomorphize::Monomorphize;
@@ -103,6 +103,8 @@ impl MonoDeferred<'_, '_> {
 
         let unannotated_ports = ul.unannotated_ports().clone();
         monosig.base.set_unannotated_ports(unannotated_ports);
+        let allowed_lints = ul.allowed_lints().clone();
+        monosig.base.set_allowed_lints(allowed_lints);
 
         // Monomorphize the component's body
         for cmd in self.underlying.cmds().clone() {
@@ -113,6 +115,7 @@ impl MonoDeferred<'_, '_> {
         if !is_ext {
             // Extend the binding with existential parameters and monomorphize signature port data
             let info = self.pass.inst_info(&comp_k);
+            let mut exists = vec![];
             for param in self.underlying.exist_params() {
                 let param = param.ul();
                 let Some(v) = info.get_exist_val(param) else {
@@ -122,7 +125,17 @@ impl MonoDeferred<'_, '_> {
                     )
                 };
                 self.monosig.binding.push(param, v);
+                if let Some(info) = self
+                    .underlying
+                    .get(self.underlying.get(param).info.ul())
+                    .as_param()
+                {
+                    exists.push((info.name, v));
+                }
             }
+            // Recorded so that precompiled libraries can provide the values
+            // without the body
+            self.monosig.base.set_exists(exists);
 
             for (idx, _) in
                 self.underlying.ports().iter().filter(|(_, p)| p.is_sig())
@@ -252,6 +265,29 @@ impl MonoDeferred<'_, '_> {
         }
     }
 
+    fn value_assert(&mut self, assert: &ir::ValueAssert) -> ir::ValueAssert {
+        let ir::ValueAssert { cons, range, info } = assert;
+        let mut term = |t: &ir::ValueTerm| match t {
+            ir::ValueTerm::Port(acc) => ir::ValueTerm::Port(self.access(acc)),
+            ir::ValueTerm::Expr(e) => ir::ValueTerm::Expr(
+                self.monosig.expr(&self.underlying, e.ul()).get(),
+            ),
+        };
+        let cons = ir::CmpOp {
+            op: cons.op.clone(),
+            lhs: term(&cons.lhs),
+            rhs: term(&cons.rhs),
+        };
+        ir::ValueAssert {
+            cons,
+            range: self.monosig.range(&self.underlying, self.pass, range),
+            info: self
+                .monosig
+                .info(&self.underlying, self.pass, info.ul())
+                .get(),
+        }
+    }
+
     fn forloop(&mut self, lp: &ir::Loop) {
         let ir::Loop {
             index,
@@ -364,6 +400,9 @@ impl MonoDeferred<'_, '_> {
             // If we want to do this long term, this should be done in a
             // separate pass and monomorphization should fail on facts.
             ir::Command::Fact(_) => None,
+            ir::Command::ValueAssert(assert) => {
+                Some(self.value_assert(assert).into())
+            }
         }
     }
 }
@@ -1,10 +1,13 @@
 use super::{
-    Base, CompKey, InstanceInfo, IntoBase, IntoUdl, MonoDeferred, MonoSig,
-    UnderlyingComp,
+    Base, CompKey, Dedup, InstanceInfo, IntoBase, IntoUdl, MonoDeferred,
+    MonoSig, UnderlyingComp,
 };
+use crate::library::Library;
 use fil_ir::{self as ir, Ctx, IndexStore};
+use fil_utils::{codes, Diagnostics, Error};
 use ir::AddCtx;
-use std::collections::HashMap;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
 
 /// The Monomorphize pass.
 ///
@@ -32,6 +35,10 @@ use std::collections::HashMap;
 /// Primitive uses are not mormorphized because their parameterization happens
 /// outside of Filament.
 ///
+/// The monomorphized variants of components defined by precompiled libraries
+/// are copied from the library instead. Variants that the library does not
+/// contain are monomorphized as usual.
+///
 /// ## Existential Parameters
 ///
 /// Existential parameters get their bindings from evaluating a component's body.
@@ -68,22 +75,31 @@ pub struct Monomorphize<'a> {
     inst_info: HashMap<CompKey, InstanceInfo>,
 
     /// Tracks which components are defined in which files
-    pub ext_map: HashMap<String, Vec<ir::CompIdx>>,
+    pub ext_map: BTreeMap<String, Vec<ir::CompIdx>>,
+
+    /// Precompiled libraries providing the bodies of precompiled components
+    libs: &'a [Library],
+    /// Components copied from a library indexed by the library and their
+    /// index in it
+    imported: HashMap<(usize, ir::CompIdx), ir::CompIdx>,
 }
 
 impl<'a> Monomorphize<'a> {
-    fn new(old: &'a ir::Context) -> Self {
+    fn new(old: &'a ir::Context, libs: &'a [Library]) -> Self {
         Monomorphize {
             ctx: ir::Context {
                 comps: IndexStore::default(),
-                entrypoint: None,
-                externals: HashMap::new(),
+                entrypoints: vec![],
+                entry_args: BTreeMap::new(),
+                externals: BTreeMap::new(),
             },
             old,
             externals: vec![],
             processed: HashMap::new(),
             inst_info: HashMap::new(),
-            ext_map: HashMap::new(),
+            ext_map: BTreeMap::new(),
+            libs,
+            imported: HashMap::new(),
         }
     }
 }
@@ -118,6 +134,13 @@ impl<'ctx> Monomorphize<'ctx> {
             return name;
         }
 
+        if underlying.precompiled {
+            if let Some(new_comp) = self.precompiled(&key) {
+                self.processed.insert(key, new_comp);
+                return new_comp;
+            }
+        }
+
         // make a MonoSig
         let monosig = MonoSig::new(underlying, comp, underlying.is_ext, params);
 
@@ -147,28 +170,170 @@ impl<'ctx> Monomorphize<'ctx> {
         // return the `base` index so we can update the instance
         new_comp
     }
+
+    /// Copy the body of a component defined by a precompiled library and
+    /// record how its signature maps to the signature of the underlying
+    /// component. Returns `None` if no library provides the specialization.
+    fn precompiled(&mut self, key: &CompKey) -> Option<Base<ir::Component>> {
+        let underlying = self.old.get(key.comp.idx());
+        let name = underlying.name.expect("component without a name");
+        let Some((lib, spec)) =
+            self.libs.iter().enumerate().find_map(|(i, lib)| {
+                lib.specialization(name, &key.params).map(|s| (i, s))
+            })
+        else {
+            log::info!(
+                "Precompiled libraries do not contain `{name}[{}]'; monomorphizing it from its source",
+                key.params.iter().join(", ")
+            );
+            return None;
+        };
+        let new_comp = self.import(lib, spec.comp);
+
+        let comp = self.ctx.get(new_comp);
+        let info = self.inst_info.entry(key.clone()).or_default();
+        for (idx, port) in underlying.ports().iter().filter(|(_, p)| p.is_sig())
+        {
+            let name = underlying.get(port.info).as_port().map(|p| p.name);
+            let Some((new, _)) = comp.ports().iter().find(|(_, p)| {
+                p.is_sig() && comp.get(p.info).as_port().map(|p| p.name) == name
+            }) else {
+                underlying.internal_error(format!(
+                    "precompiled `{name:?}' has no port `{idx}'"
+                ))
+            };
+            info.add_port(idx.ul(), new.base());
+        }
+        for (idx, event) in underlying.events().iter() {
+            let name = underlying.get(event.info).as_event().map(|e| e.name);
+            let Some((new, _)) = comp.events().iter().find(|(_, e)| {
+                comp.get(e.info).as_event().map(|e| e.name) == name
+            }) else {
+                underlying.internal_error(format!(
+                    "precompiled `{name:?}' has no event `{idx}'"
+                ))
+            };
+            info.add_event(idx.ul(), new.base());
+        }
+        for param in underlying.exist_params() {
+            let name = underlying.get(underlying.get(param).info).as_param();
+            let val = spec
+                .exists
+                .iter()
+                .find(|(n, _)| name.map(|p| p.name) == Some(*n))
+                .map(|(_, v)| *v)
+                .unwrap_or_else(|| {
+                    underlying.internal_error(format!(
+                        "no value for existential parameter `{param}'"
+                    ))
+                });
+            info.add_exist_val(param.ul(), val);
+        }
+        Some(new_comp.base())
+    }
+
+    /// Copy a monomorphized component and the components it instantiates
+    /// from the library into the new context
+    fn import(&mut self, lib: usize, idx: ir::CompIdx) -> ir::CompIdx {
+        if let Some(&new) = self.imported.get(&(lib, idx)) {
+            return new;
+        }
+        let bodies = self.libs[lib].bodies();
+        let mut comp = bodies.get(idx).clone();
+        let mut rename = HashMap::new();
+        for inst in comp.instances().iter().map(|(_, i)| i.comp).unique() {
+            let new = if bodies.is_ext(inst) {
+                self.external(bodies.get(inst))
+            } else {
+                self.import(lib, inst)
+            };
+            rename.insert(inst, new);
+        }
+        Dedup::redirect(&mut comp, &rename);
+        comp.precompiled = true;
+        let new = self.ctx.add(comp);
+        self.imported.insert((lib, idx), new);
+        new
+    }
+
+    /// The component in the new context for the external component of a
+    /// library
+    fn external(&mut self, ext: &ir::Component) -> ir::CompIdx {
+        let name = ext.src_info.as_ref().map(|src| src.name);
+        let Some((idx, _)) = self.old.iter().find(|(_, c)| {
+            c.is_ext && c.src_info.as_ref().map(|src| src.name) == name
+        }) else {
+            ext.internal_error(format!("unknown external component {name:?}"))
+        };
+        self.monomorphize(CompKey::new(idx.ul(), vec![])).get()
+    }
 }
 
 impl Monomorphize<'_> {
-    /// Monomorphize the context by tracing starting from the top-level component.
+    /// Top-level components with signature parameters that have no values.
+    /// These are verified for every value of their parameters but cannot be
+    /// monomorphized.
+    pub fn unbound_entrypoints(ctx: &ir::Context) -> Vec<ir::CompIdx> {
+        ctx.entrypoints
+            .iter()
+            .filter(|ep| {
+                !ctx.entry_args.contains_key(ep)
+                    && ctx.get(**ep).sig_params().next().is_some()
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Report the top-level components whose signature parameters have no
+    /// values. Returns the number of errors.
+    pub fn check_entry_args(ctx: &ir::Context) -> Result<(), u64> {
+        let mut diag = Diagnostics::default();
+        for ep in Self::unbound_entrypoints(ctx) {
+            let comp = ctx.get(ep);
+            let name = comp.name.map(|n| n.to_string()).unwrap_or_default();
+            let mut err = Error::malformed(format!(
+                "parameters of top-level component `{name}' have no values"
+            ))
+            .with_code(codes::ARGUMENT_COUNT);
+            for param in comp.sig_params() {
+                let Some(info) = comp.get(comp.get(param).info).as_param()
+                else {
+                    continue;
+                };
+                err = err.add_note(diag.add_info(
+                    format!("provide a value with `-P {}=<value>'", info.name),
+                    info.bind_loc,
+                ));
+            }
+            diag.add_error(err);
+        }
+        diag.report_all().map_or(Ok(()), Err)
+    }
+
+    /// Monomorphize the context by tracing starting from the top-level
+    /// components. Components used by several top-level components are only
+    /// generated once. The bodies of precompiled components are provided by
+    /// the libraries.
     /// Returns an empty context if there is no top-level component.
-    pub fn transform(ctx: &ir::Context) -> ir::Context {
-        let Some(entrypoint) = ctx.entrypoint else {
+    pub fn transform(ctx: &ir::Context, libs: &[Library]) -> ir::Context {
+        if ctx.entrypoints.is_empty() {
             log::warn!("Program has no entrypoint. Result will be empty.");
             return ir::Context {
                 comps: IndexStore::default(),
-                entrypoint: None,
-                externals: HashMap::new(),
+                entrypoints: vec![],
+                entry_args: BTreeMap::new(),
+                externals: BTreeMap::new(),
             };
-        };
-        let entrypoint = entrypoint.ul();
-        // Monomorphize the entrypoint
-        let mut mono = Monomorphize::new(ctx);
-        let ck = CompKey::new(entrypoint, vec![]);
-        mono.monomorphize(ck.clone());
-
-        let new_entrypoint = mono.processed.get(&ck).unwrap();
-        mono.ctx.entrypoint = Some(new_entrypoint.get());
+        }
+        let mut mono = Monomorphize::new(ctx, libs);
+        for entrypoint in &ctx.entrypoints {
+            // Monomorphize the entrypoint
+            let args =
+                ctx.entry_args.get(entrypoint).cloned().unwrap_or_default();
+            let ck = CompKey::new(entrypoint.ul(), args);
+            let new_entrypoint = mono.monomorphize(ck);
+            mono.ctx.entrypoints.push(new_entrypoint.get());
+        }
         mono.ctx.externals = mono.ext_map;
         ir::Validate::context(&mono.ctx);
         mono.ctx
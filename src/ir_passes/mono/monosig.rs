@@ -49,6 +49,30 @@ impl MonoSig {
         is_ext: bool,
         params: Vec<u64>,
     ) -> Self {
+        let mut comp = ir::Component::default();
+        comp.is_ext = is_ext;
+        // Record the source of generated components to name them
+        let name = underlying
+            .src_info
+            .as_ref()
+            .map(|src| src.name)
+            .or(underlying.name);
+        if let (false, Some(name)) = (is_ext, name) {
+            let params = underlying
+                .sig_params()
+                .zip(&params)
+                .filter_map(|(p, v)| {
+                    let info = underlying.get(underlying.get(p).info);
+                    info.as_param().map(|info| (info.name, *v))
+                })
+                .collect();
+            comp.specializations.push(ir::Specialization {
+                name,
+                params,
+                exists: vec![],
+            });
+        }
+
         let binding = ir::Bind::new(
             underlying
                 .sig_params()
@@ -56,8 +80,6 @@ impl MonoSig {
                 .zip(params)
                 .collect_vec(),
         );
-        let mut comp = ir::Component::default();
-        comp.is_ext = is_ext;
 
         Self {
             base: BaseComp::new(comp),
@@ -172,6 +194,7 @@ impl MonoSig {
             | ir::Info::Port(_)
             | ir::Info::EventBind(_)
             | ir::Info::Event(_)
+            | ir::Info::ValueAssert(_)
             | ir::Info::Empty(_) => info.clone(),
         };
 
@@ -37,6 +37,9 @@ impl<'a> UnderlyingComp<'a> {
     pub fn unannotated_ports(&self) -> &Vec<(ast::Id, u64)> {
         &self.0.unannotated_ports
     }
+    pub fn allowed_lints(&self) -> &Vec<ast::Id> {
+        &self.0.allowed_lints
+    }
     pub fn exist_params(&self) -> impl Iterator<Item = ir::ParamIdx> + '_ {
         self.0.exist_params()
     }
@@ -83,9 +86,17 @@ impl BaseComp {
     pub fn set_unannotated_ports(&mut self, other: Vec<(ast::Id, u64)>) {
         self.0.unannotated_ports = Box::new(other);
     }
+    pub fn set_allowed_lints(&mut self, other: Vec<ast::Id>) {
+        *self.0.allowed_lints = other;
+    }
     pub fn set_src_info(&mut self, other: Option<InterfaceSrc>) {
         self.0.src_info = other;
     }
+    pub fn set_exists(&mut self, exists: Vec<(ast::Id, u64)>) {
+        if let Some(spec) = self.0.specializations.last_mut() {
+            spec.exists = exists;
+        }
+    }
 
     pub fn extend_cmds(
         &mut self,
@@ -1,18 +1,27 @@
 use crate::{
-    cmdline,
     ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
 };
 use fil_ir::{self as ir, Ctx, DisplayCtx};
-use fil_utils::{Diagnostics, Error, GPosIdx};
+use fil_utils::{
+    codes, Diagnostics, Error, GPosIdx, Lint, LintConfig, LintLevel,
+};
 use itertools::Itertools;
+use std::collections::HashSet;
 
 /// Checks if a user-level phantom events are valid.
 /// Phantom events are valid iff:
 /// 1. The component doesn't share any instances
 /// 2. The component doesn't use an subcomponents that need to use the
 ///    corresponding event in their interface, i.e., the uses of the event are all phantom
+///
+/// Conversely, the pass advises when an event with an interface port satisfies
+/// the conditions above and could be a phantom event.
 pub struct PhantomCheck {
     phantom_events: Vec<ir::EventIdx>,
+    /// Non-phantom events that need their interface port
+    required: HashSet<ir::EventIdx>,
+    lints: LintConfig,
     /// Instances defined in each scope
     defined_insts: Vec<Vec<ir::InstIdx>>,
     /// Mapping from instance to the first invoke seen
@@ -29,12 +38,20 @@ impl PhantomCheck {
     fn in_loop(&self) -> bool {
         self.defined_insts.len() != 1
     }
+
+    /// Mark all events used by the invocation as requiring an interface port
+    fn require_all(&mut self, inv: ir::InvIdx, comp: &ir::Component) {
+        self.required
+            .extend(inv.times(comp).map(|(time, _)| time.event(comp)));
+    }
 }
 
 impl Construct for PhantomCheck {
-    fn from(_: &cmdline::Opts, _: &mut ir::Context) -> Self {
+    fn from(opts: &options::Options, _: &mut ir::Context) -> Self {
         PhantomCheck {
             phantom_events: Vec::new(),
+            required: HashSet::new(),
+            lints: opts.lints.clone(),
             defined_insts: vec![Vec::new()],
             diag: Diagnostics::default(),
         }
@@ -42,6 +59,7 @@ impl Construct for PhantomCheck {
 
     fn clear_data(&mut self) {
         self.phantom_events.clear();
+        self.required.clear();
         self.defined_insts = vec![Vec::new()];
         /* Diagnostics struct is shared */
     }
@@ -55,18 +73,26 @@ impl Visitor for PhantomCheck {
     fn start(&mut self, data: &mut VisitorData) -> Action {
         let comp = &data.comp;
         self.phantom_events = comp.phantom_events().collect();
-        if self.phantom_events.is_empty() {
+        let advise = !comp.is_ext
+            && self
+                .lints
+                .level(&Lint::COULD_BE_PHANTOM, &comp.allowed_lints)
+                != LintLevel::Allow;
+        if self.phantom_events.is_empty() && !advise {
             return Action::Stop;
         }
 
-        let diag = &mut self.diag;
-
         // For each instance, check to see if any shared invocation uses a
         // phantom event.
         for (inst, invs) in comp.inst_invoke_map() {
             if invs.len() < 2 {
                 continue;
             }
+            // Shared instances require the events to schedule invocations
+            for inv in &invs {
+                self.require_all(*inv, comp);
+            }
+            let diag = &mut self.diag;
 
             let shared_inv = invs.iter().find_map(|inv| {
                 let bind_loc = inv.times(comp).find_map(|(time, eb)| {
@@ -99,6 +125,7 @@ impl Visitor for PhantomCheck {
                 let err = Error::malformed(
                     "cannot reuse instance using a phantom event",
                 )
+                .with_code(codes::PHANTOM_SHARED)
                 .add_note(diag.add_info(
                     format!("instance is invoked {} times", invs.len()),
                     inst_bind,
@@ -148,6 +175,7 @@ impl Visitor for PhantomCheck {
         // If an invocation is within a loop, we need to ensure that its
         // corresponding instance is in the same loop nest.
         if self.in_loop() && !self.inst_def_in_scope(inst) {
+            self.require_all(inv, comp);
             // If it is not, then ensure that there are no phantom events used
             if let Some(bind_loc) = inv.times(comp).find_map(|(time, eb)| {
                 if self.phantom_events.contains(&time.event(comp)) {
@@ -170,6 +198,7 @@ impl Visitor for PhantomCheck {
                 Error::malformed(
                     "invocation is within a loop but instance is not",
                 )
+                .with_code(codes::PHANTOM_LOOP)
                 .add_note(
                     self.diag.add_info("invocation uses phantom event", bind_loc),
                 )
@@ -198,6 +227,7 @@ impl Visitor for PhantomCheck {
             // If this event is non-phantom, ensure all provided events are non-phantom as well.
             if !inst_phantoms.contains(&event) {
                 let ev = &bind.event(comp);
+                self.required.insert(*ev);
                 if self.phantom_events.contains(ev) {
                     let eb_info =
                         comp.get(info).as_event_bind().unwrap().bind_loc;
@@ -213,6 +243,7 @@ impl Visitor for PhantomCheck {
                         .unwrap_or(GPosIdx::UNKNOWN);
 
                     let err = Error::malformed("component provided phantom event binding to non-phantom event argument")
+                    .with_code(codes::PHANTOM_BINDING)
                     .add_note(self.diag.add_info("invoke provides phantom event", eb_info))
                     .add_note(self.diag.add_info("event is a phantom event", phantom_info))
                     .add_note(self.diag.add_info("instance's event is not phantom", inst_ev_info))
@@ -224,6 +255,33 @@ impl Visitor for PhantomCheck {
         Action::Continue
     }
 
+    fn end(&mut self, data: &mut VisitorData) {
+        let comp = &data.comp;
+        if comp.is_ext {
+            return;
+        }
+        let level = self
+            .lints
+            .level(&Lint::COULD_BE_PHANTOM, &comp.allowed_lints);
+        for ev in comp.events().idx_iter() {
+            if self.phantom_events.contains(&ev) || self.required.contains(&ev)
+            {
+                continue;
+            }
+            let Some(info) = comp.get(comp.get(ev).info).as_event() else {
+                continue;
+            };
+            let loc = info.interface_bind_loc.unwrap_or(info.bind_loc);
+            let err = Error::malformed(format!(
+                "event {} could be a phantom event",
+                comp.display(ev)
+            ))
+            .add_note(self.diag.add_info("interface port is not needed", loc))
+            .add_note(self.diag.add_message("the event is not used by shared instances or by subcomponents that need an interface port"));
+            self.diag.add_lint(&Lint::COULD_BE_PHANTOM, level, err);
+        }
+    }
+
     fn after_traversal(&mut self) -> Option<u64> {
         self.diag.report_all()
     }
@@ -0,0 +1,470 @@
+use super::discharge::builtin;
+use crate::{
+    ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
+};
+use fil_ir::{self as ir, AddCtx, Ctx, DisplayCtx, MutCtx};
+use fil_utils::GPosIdx;
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+use std::collections::BTreeMap;
+
+/// Cycles during which an instance is busy, relative to a single event.
+struct Busy {
+    /// The event all the invocations of the instance are scheduled against
+    event: ir::EventIdx,
+    /// Busy interval `[start, end)` for each invocation
+    intervals: Vec<(u64, u64)>,
+}
+
+impl Busy {
+    fn start(&self) -> u64 {
+        self.intervals.iter().map(|(s, _)| *s).min().unwrap()
+    }
+
+    /// Returns true if none of the intervals in `self` and `other` overlap.
+    fn disjoint(&self, other: &Busy) -> bool {
+        self.intervals.iter().all(|(s1, e1)| {
+            other.intervals.iter().all(|(s2, e2)| e1 <= s2 || e2 <= s1)
+        })
+    }
+}
+
+/// A set of instances that will be merged into the first one.
+struct Bin {
+    insts: Vec<ir::InstIdx>,
+    busy: Busy,
+}
+
+/// Statistics about the instances removed for a specific primitive.
+#[derive(Default)]
+struct Saved {
+    /// Number of instances removed
+    count: u64,
+    /// Bits in the signature of the removed instances
+    bits: u64,
+    /// Bits of input multiplexing added to the shared instances
+    mux_bits: u64,
+}
+
+/// Infers resource sharing for instances of external components.
+///
+/// Two instances of the same external component with the same parameters can
+/// be merged into one if the busy intervals of their invocations never
+/// overlap, i.e., for each invocation `i := I<'G+n>(...)` of a component whose
+/// event has delay `d`, the instance is busy in `['G+n, 'G+n+d)`.
+/// Merging is only valid if all busy intervals are disjoint and they all fit
+/// within the delay of the event they are scheduled against so that the next
+/// iteration of the pipeline does not conflict with the current one.
+///
+/// The merged instance is given a liveness that covers all of its uses and the
+/// pass generates the same `EventLive` and `EventLiveDelay` assertions as
+/// [super::IntervalCheck]. Candidate merges whose assertions the built-in
+/// solver cannot prove are rejected. Run [super::Discharge] after this pass to
+/// re-verify the component.
+/// Must occur after monomorphization.
+pub struct ShareInstances {
+    /// Instances removed for each primitive
+    saved: LinkedHashMap<String, Saved>,
+}
+
+impl Construct for ShareInstances {
+    fn from(_: &options::Options, _: &mut ir::Context) -> Self {
+        Self {
+            saved: LinkedHashMap::new(),
+        }
+    }
+
+    fn clear_data(&mut self) {
+        /* Statistics are tracked across components */
+    }
+}
+
+impl ShareInstances {
+    /// Returns the number of bits in the (input, output) ports of an instance.
+    pub(super) fn port_bits(
+        inst: &ir::Instance,
+        comp: &ir::Component,
+        ctx: &ir::Context,
+    ) -> (u64, u64) {
+        // Widths are computed by substituting the arguments into a copy of
+        // the external component's signature
+        let mut ext = ctx.get(inst.comp).clone();
+        let params = ext.sig_params().collect_vec();
+        let args = inst
+            .args
+            .iter()
+            .map(|a| a.as_concrete(comp))
+            .collect::<Option<Vec<_>>>()
+            .unwrap_or_default();
+        let binding = ir::Bind::new(
+            params
+                .into_iter()
+                .zip(args)
+                .map(|(p, v)| (p, ext.num(v)))
+                .collect_vec(),
+        );
+        let ins = ext.inputs().map(|(_, p)| p.width).collect_vec();
+        let outs = ext.outputs().map(|(_, p)| p.width).collect_vec();
+        let mut width = |ws: Vec<ir::ExprIdx>| -> u64 {
+            ws.into_iter()
+                .map(|w| {
+                    ir::Subst::new(w, &binding)
+                        .apply(&mut ext)
+                        .as_concrete(&ext)
+                        .unwrap_or(0)
+                })
+                .sum()
+        };
+        (width(ins), width(outs))
+    }
+
+    /// Concrete delay between two times or a unit delay.
+    fn concrete_delay(ts: &ir::TimeSub, comp: &ir::Component) -> Option<u64> {
+        match ts {
+            ir::TimeSub::Unit(e) => e.as_concrete(comp),
+            ir::TimeSub::Sym { l, r } => {
+                let l = comp.get(*l);
+                let r = comp.get(*r);
+                if l.event != r.event {
+                    return None;
+                }
+                l.offset
+                    .as_concrete(comp)?
+                    .checked_sub(r.offset.as_concrete(comp)?)
+            }
+        }
+    }
+
+    /// Busy interval for each event binding of an invocation.
+    /// Returns `None` if the bindings are not concrete or are scheduled
+    /// against different events.
+    fn invoke_busy(
+        inv: ir::InvIdx,
+        comp: &ir::Component,
+    ) -> Option<(ir::EventIdx, Vec<(u64, u64)>)> {
+        let mut event = None;
+        let mut ranges = Vec::new();
+        for eb in &comp.get(inv).events {
+            let time = comp.get(eb.arg);
+            if *event.get_or_insert(time.event) != time.event {
+                return None;
+            }
+            let start = time.offset.as_concrete(comp)?;
+            let delay = Self::concrete_delay(&eb.delay, comp)?;
+            ranges.push((start, start + delay));
+        }
+        event.map(|ev| (ev, ranges))
+    }
+
+    /// Compute the busy intervals of an instance that is a candidate for
+    /// sharing.
+    fn inst_busy(
+        inst: ir::InstIdx,
+        invs: &[ir::InvIdx],
+        comp: &ir::Component,
+        ctx: &ir::Context,
+    ) -> Option<Busy> {
+        let instance = comp.get(inst);
+        // Only instances of external components without explicit liveness
+        // are candidates.
+        if !ctx.is_ext(instance.comp)
+            || !instance.lives.is_empty()
+            || !instance.params.is_empty()
+            || instance.args.iter().any(|a| a.as_concrete(comp).is_none())
+        {
+            return None;
+        }
+
+        let mut event = None;
+        let mut intervals = Vec::with_capacity(invs.len());
+        for inv in invs {
+            let (ev, ranges) = Self::invoke_busy(*inv, comp)?;
+            if *event.get_or_insert(ev) != ev {
+                return None;
+            }
+            let start = ranges.iter().map(|(s, _)| *s).min()?;
+            let end = ranges.iter().map(|(_, e)| *e).max()?;
+            intervals.push((start, end));
+        }
+        let event = event?;
+
+        // Phantom events do not have an FSM and cannot be used to multiplex
+        // between invocations.
+        if !comp.get(event).has_interface {
+            return None;
+        }
+
+        let busy = Busy { event, intervals };
+        // Make sure the existing uses of the instance are valid
+        let single = busy.intervals.iter().enumerate().all(|(i, (s1, e1))| {
+            busy.intervals
+                .iter()
+                .skip(i + 1)
+                .all(|(s2, e2)| e1 <= s2 || e2 <= s1)
+        });
+        single.then_some(busy)
+    }
+
+    /// Partition instances into bins that can share a single instance. An
+    /// instance joins a bin if its busy intervals do not overlap with those
+    /// of the bin and the assertions of the merged instance hold.
+    fn bins(
+        mut cands: Vec<(ir::InstIdx, Busy)>,
+        inst_invs: &BTreeMap<ir::InstIdx, Vec<ir::InvIdx>>,
+        comp: &mut ir::Component,
+    ) -> Vec<Bin> {
+        cands.sort_by_key(|(_, b)| b.start());
+        let mut bins: Vec<Bin> = Vec::new();
+        for (inst, busy) in cands {
+            let fits = (0..bins.len()).find(|&b| {
+                let bin = &bins[b];
+                if bin.busy.event != busy.event || !bin.busy.disjoint(&busy) {
+                    return false;
+                }
+                let insts = bin.insts.iter().copied().chain([inst]).collect_vec();
+                let invs = Self::invocations(&insts, inst_invs);
+                let (_, facts) =
+                    Self::shared(&invs, busy.event, insts[0], comp);
+                let mut solver = builtin::Solver::new(comp, vec![]);
+                let valid = facts.iter().all(|(prop, _)| {
+                    matches!(solver.check(*prop), builtin::Res::Valid)
+                });
+                if !valid {
+                    log::info!(
+                        "share-instances: cannot share `{}' with `{}' because the merged instance does not verify",
+                        comp.display(inst),
+                        insts[..insts.len() - 1]
+                            .iter()
+                            .map(|i| comp.display(*i))
+                            .join("', `")
+                    );
+                }
+                valid
+            });
+            if let Some(b) = fits {
+                bins[b].insts.push(inst);
+                bins[b].busy.intervals.extend(busy.intervals);
+            } else {
+                bins.push(Bin {
+                    insts: vec![inst],
+                    busy,
+                });
+            }
+        }
+        bins
+    }
+
+    /// The invocations of the instances
+    fn invocations(
+        insts: &[ir::InstIdx],
+        inst_invs: &BTreeMap<ir::InstIdx, Vec<ir::InvIdx>>,
+    ) -> Vec<ir::InvIdx> {
+        insts
+            .iter()
+            .flat_map(|inst| inst_invs[inst].iter().copied())
+            .collect_vec()
+    }
+
+    /// Liveness of the instance `rep` shared by the invocations, i.e., the
+    /// hull of all uses of each event, and the assertions that ensure the
+    /// sharing is valid with the reasons for them.
+    fn shared(
+        invs: &[ir::InvIdx],
+        event: ir::EventIdx,
+        rep: ir::InstIdx,
+        comp: &mut ir::Component,
+    ) -> (Vec<ir::Range>, Vec<(ir::PropIdx, ir::info::Reason)>) {
+        let num_events = comp.get(invs[0]).events.len();
+        let mut lives = Vec::with_capacity(num_events);
+        for i in 0..num_events {
+            let (start, end) = invs
+                .iter()
+                .map(|inv| {
+                    let eb = &comp.get(*inv).events[i];
+                    let st = comp.get(eb.arg).offset.concrete(comp);
+                    let d = Self::concrete_delay(&eb.delay, comp).unwrap();
+                    (st, st + d)
+                })
+                .fold((u64::MAX, 0), |(lo, hi), (s, e)| (lo.min(s), hi.max(e)));
+            let start = comp.num(start);
+            let end = comp.num(end);
+            let start = comp.add(ir::Time {
+                event,
+                offset: start,
+            });
+            let end = comp.add(ir::Time { event, offset: end });
+            lives.push(ir::Range { start, end });
+        }
+
+        let live_loc = comp
+            .get(comp.get(rep).info)
+            .as_instance()
+            .map(|i| i.bind_loc)
+            .unwrap_or(GPosIdx::UNKNOWN);
+
+        // The instance must not be borrowed for longer than the delay of the event
+        let mut facts = Vec::new();
+        let ev = comp.get(event);
+        let delay = ev.delay.clone();
+        let delay_loc = comp
+            .get(ev.info)
+            .as_event()
+            .map(|e| e.delay_loc)
+            .unwrap_or(GPosIdx::UNKNOWN);
+        for ir::Range { start, end } in &lives {
+            let len = end.sub(*start, comp);
+            let prop = delay.clone().gte(len.clone(), comp);
+            let reason = ir::info::Reason::event_live_delay(
+                live_loc,
+                len,
+                delay_loc,
+                delay.clone(),
+            );
+            facts.push((prop, reason));
+        }
+
+        // Each use of the instance must be within its liveness
+        for inv in invs {
+            let inv_info = comp.get(comp.get(*inv).info).as_invoke().cloned();
+            for (i, eb) in comp.get(*inv).events.clone().into_iter().enumerate()
+            {
+                let ir::Range { start, end } = lives[i];
+                let use_end = eb.arg.add(&eb.delay, comp);
+                let prop =
+                    eb.arg.gte(start, comp).and(use_end.lte(end, comp), comp);
+                let bind_loc = inv_info
+                    .as_ref()
+                    .and_then(|info| info.event_bind_locs.get(i).copied())
+                    .unwrap_or(GPosIdx::UNKNOWN);
+                let reason = ir::info::Reason::event_live(
+                    live_loc,
+                    (start, end),
+                    (eb.arg, use_end),
+                    bind_loc,
+                );
+                facts.push((prop, reason));
+            }
+        }
+        (lives, facts)
+    }
+
+    /// Merge all the instances in the bin into the first one and return the
+    /// assertions that ensure the merge is valid.
+    fn merge(
+        &mut self,
+        bin: &Bin,
+        inst_invs: &BTreeMap<ir::InstIdx, Vec<ir::InvIdx>>,
+        comp: &mut ir::Component,
+        ctx: &ir::Context,
+    ) -> Vec<ir::Command> {
+        let (rep, rest) = bin.insts.split_first().unwrap();
+        let invs = Self::invocations(&bin.insts, inst_invs);
+        let (lives, facts) = Self::shared(&invs, bin.busy.event, *rep, comp);
+
+        // Move all invocations to the representative instance
+        for inv in &invs {
+            comp.get_mut(*inv).inst = *rep;
+        }
+        comp.get_mut(*rep).lives = lives;
+
+        // Assertions are re-verified by the discharge pass
+        let mut cmds = Vec::new();
+        for (prop, reason) in facts {
+            let reason = comp.add(ir::Info::assert(reason));
+            cmds.extend(comp.assert(prop, reason));
+        }
+
+        // Remove the merged instances
+        let inst = comp.get(*rep);
+        let (ins, outs) = Self::port_bits(inst, comp, ctx);
+        let name = format!(
+            "{}[{}]",
+            ctx.get(inst.comp)
+                .src_info
+                .as_ref()
+                .map(|s| s.name.to_string())
+                .unwrap_or_else(|| "<unknown>".to_string()),
+            inst.args.iter().map(|a| comp.display(*a)).join(", ")
+        );
+        for inst in rest {
+            comp.delete(*inst);
+        }
+        comp.cmds.retain(|cmd| {
+            !matches!(cmd, ir::Command::Instance(idx) if rest.contains(idx))
+        });
+
+        let saved = self.saved.entry(name).or_default();
+        saved.count += rest.len() as u64;
+        saved.bits += (ins + outs) * rest.len() as u64;
+        saved.mux_bits += ins * rest.len() as u64;
+
+        cmds
+    }
+}
+
+impl Visitor for ShareInstances {
+    fn name() -> &'static str {
+        "share-instances"
+    }
+
+    fn start(&mut self, data: &mut VisitorData) -> Action {
+        if data.comp.is_ext {
+            return Action::Stop;
+        }
+
+        let comp = &mut data.comp;
+        let ctx = &*data.mut_ctx;
+        // Visit instances and groups in index order so that the merged
+        // instances and the report do not depend on hashing
+        let inst_invs: BTreeMap<_, _> = comp.inst_invoke_map().collect();
+        let mut cands: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for (inst, invs) in &inst_invs {
+            let Some(busy) = Self::inst_busy(*inst, invs, comp, ctx) else {
+                continue;
+            };
+            // Group instances by the component and the parameters
+            let i = comp.get(*inst);
+            let key = (
+                i.comp,
+                i.args.iter().map(|a| a.concrete(comp)).collect_vec(),
+            );
+            cands.entry(key).or_default().push((*inst, busy));
+        }
+
+        let bins = cands
+            .into_values()
+            .flat_map(|cands| Self::bins(cands, &inst_invs, comp))
+            .filter(|bin| bin.insts.len() > 1)
+            .collect_vec();
+
+        let mut facts = Vec::new();
+        for bin in bins {
+            facts.extend(self.merge(&bin, &inst_invs, comp, ctx));
+        }
+        comp.cmds.extend(facts);
+
+        // All the work is done in the start method
+        Action::Stop
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        if self.saved.is_empty() {
+            return None;
+        }
+        let (mut total, mut mux) = (0, 0);
+        for (name, saved) in &self.saved {
+            log::info!(
+                "share-instances: removed {} instance(s) of {name}",
+                saved.count
+            );
+            total += saved.bits;
+            mux += saved.mux_bits;
+        }
+        log::info!(
+            "share-instances: estimated area saved: {} bits of datapath ({total} removed, {mux} of input multiplexing added)",
+            total.saturating_sub(mux)
+        );
+        None
+    }
+}
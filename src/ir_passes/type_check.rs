@@ -10,6 +10,7 @@ use itertools::Itertools;
 /// * Port accesses are in-bounds
 /// * Connections are between ports of same size
 /// * Connected ports have the same bitwidths
+/// * Assertions on values read exactly one port
 /// * Add constraints on existentially quantified parameters
 pub struct TypeCheck;
 
@@ -140,4 +141,37 @@ impl Visitor for TypeCheck {
 
         Action::AddBefore(cons)
     }
+    fn value_assert(
+        &mut self,
+        a: &mut ir::ValueAssert,
+        data: &mut VisitorData,
+    ) -> Action {
+        let comp = &mut data.comp;
+        let &ir::info::ValueAssert {
+            lhs_loc, rhs_loc, ..
+        } = comp.get(a.info).into();
+        let mut cons = vec![];
+        let terms = [(&a.cons.lhs, lhs_loc), (&a.cons.rhs, rhs_loc)];
+        for (term, loc) in terms {
+            let ir::ValueTerm::Port(access) = term else {
+                continue;
+            };
+            cons.extend(self.port_access(access, loc, comp));
+            let one = comp.num(1);
+            let len = access
+                .ranges
+                .iter()
+                .fold(one, |acc, (s, e)| acc.mul(e.sub(*s, comp), comp));
+            let reason = comp.add(
+                ir::info::Reason::misc(
+                    "assertion must read the value of exactly one port",
+                    loc,
+                )
+                .into(),
+            );
+            let prop = len.equal(one, comp);
+            cons.extend(comp.assert(prop, reason));
+        }
+        Action::AddBefore(cons)
+    }
 }
@@ -0,0 +1,194 @@
+use crate::{
+    ir_visitor::{Action, Construct, Visitor, VisitorData},
+    options,
+};
+use fil_ir::{self as ir, Connect, Context, Ctx, DisplayCtx, PortIdx};
+use fil_utils::{self as utils, Diagnostics, Error, GPosIdx, Lint, LintConfig};
+use itertools::Itertools;
+use linked_hash_map::LinkedHashMap;
+
+/// Reports instances that are never invoked, invocation outputs that are never
+/// read, and input ports and bundles whose elements are (partly) never read.
+/// Must occur after monomorphization and before bundle elimination so that
+/// accesses are concrete and bundles are still intact.
+pub struct UnusedCheck {
+    /// For each port that should be read, whether each of its elements is read
+    reads: LinkedHashMap<PortIdx, Vec<bool>>,
+    lints: LintConfig,
+    diag: Diagnostics,
+}
+
+impl Construct for UnusedCheck {
+    fn from(opts: &options::Options, _: &mut Context) -> Self {
+        Self {
+            reads: LinkedHashMap::new(),
+            lints: opts.lints.clone(),
+            diag: Diagnostics::default(),
+        }
+    }
+
+    fn clear_data(&mut self) {
+        self.reads = LinkedHashMap::new();
+    }
+}
+
+impl UnusedCheck {
+    /// Report the lint at the level configured for the component.
+    fn report(&mut self, lint: &Lint, err: Error, comp: &ir::Component) {
+        let level = self.lints.level(lint, &comp.allowed_lints);
+        self.diag.add_lint(lint, level, err);
+    }
+
+    /// Location and description of a port that is never read.
+    fn port_loc(port: PortIdx, comp: &ir::Component) -> (String, GPosIdx) {
+        let p = comp.get(port);
+        let desc = if p.is_sig_in() {
+            "input port"
+        } else if p.is_inv_out() {
+            "invocation output"
+        } else {
+            "bundle"
+        };
+        let pos = match &p.owner {
+            ir::PortOwner::Inv { inv, .. } => comp
+                .get(comp.get(*inv).info)
+                .as_invoke()
+                .map(|i| i.bind_loc),
+            _ => comp.get(p.info).as_port().map(|i| i.bind_loc),
+        };
+        (desc.to_string(), pos.unwrap_or(GPosIdx::UNKNOWN))
+    }
+}
+
+impl Visitor for UnusedCheck {
+    fn name() -> &'static str {
+        "unused-check"
+    }
+
+    fn start(&mut self, data: &mut VisitorData) -> Action {
+        // skip externals
+        if data.comp.is_ext {
+            return Action::Stop;
+        }
+        let comp = &data.comp;
+
+        for (idx, port) in comp.ports().iter() {
+            // input ports, invoke output ports, and local bundles are the only
+            // ports that are read from
+            if !(port.is_sig_in() || port.is_inv_out() || port.is_local()) {
+                continue;
+            }
+
+            let len = port
+                .live
+                .lens
+                .iter()
+                .map(|l| l.concrete(comp) as usize)
+                .product();
+            self.reads.insert(idx, vec![false; len]);
+        }
+
+        let ctx = &*data.mut_ctx;
+        let invoked = comp.inst_invoke_map().map(|(i, _)| i).collect_vec();
+        let unused = comp
+            .instances()
+            .iter()
+            .filter(|(idx, _)| !invoked.contains(idx))
+            // Instances of components without ports are only used for their
+            // existential parameters
+            .filter(|(_, inst)| {
+                ctx.get(inst.comp).ports().iter().any(|(_, p)| p.is_sig())
+            })
+            .filter_map(|(_, inst)| comp.get(inst.info).as_instance().cloned())
+            .collect_vec();
+        for ir::info::Instance { name, bind_loc, .. } in unused {
+            let err =
+                Error::malformed(format!("instance `{name}' is never invoked"));
+            let err = err
+                .add_note(self.diag.add_info("instance is unused", bind_loc));
+            self.report(&Lint::UNUSED_INSTANCE, err, comp);
+        }
+
+        Action::Continue
+    }
+
+    fn connect(&mut self, con: &mut Connect, data: &mut VisitorData) -> Action {
+        let ir::Access { port, ranges } = &con.src;
+        let comp = &data.comp;
+        let Some(reads) = self.reads.get_mut(port) else {
+            return Action::Continue;
+        };
+        let ranges_c = ranges
+            .iter()
+            .map(|(s, e)| {
+                (s.concrete(comp) as usize, e.concrete(comp) as usize)
+            })
+            .collect();
+        let len_c = comp
+            .get(*port)
+            .live
+            .lens
+            .iter()
+            .map(|l| l.concrete(comp) as usize)
+            .collect_vec();
+
+        for i in utils::all_indices(ranges_c) {
+            reads[utils::flat_idx(&i, &len_c)] = true;
+        }
+        Action::Continue
+    }
+
+    fn end(&mut self, data: &mut VisitorData) {
+        let comp = &data.comp;
+        for (port, reads) in std::mem::take(&mut self.reads) {
+            let unread = reads
+                .iter()
+                .enumerate()
+                .filter_map(|(i, r)| (!r).then_some(i))
+                .collect_vec();
+            if unread.is_empty() {
+                continue;
+            }
+
+            let (desc, pos) = Self::port_loc(port, comp);
+            let (lint, err) = if unread.len() == reads.len() {
+                let err = Error::malformed(format!(
+                    "{desc} `{}' is never read",
+                    comp.display(port)
+                ));
+                let lint = if comp.get(port).is_inv_out() {
+                    &Lint::UNUSED_OUTPUT
+                } else {
+                    &Lint::UNUSED_PORT
+                };
+                (lint, err.add_note(self.diag.add_info("never read", pos)))
+            } else {
+                let err = Error::malformed(format!(
+                    "{desc} `{}' has {} unread locations",
+                    comp.display(port),
+                    unread.len()
+                ));
+                // List the first few unread indices and summarize the rest
+                let mut msg = format!(
+                    "bundle indices are never read: {}",
+                    unread.iter().take(5).map(|i| i.to_string()).join(", ")
+                );
+                if unread.len() > 5 {
+                    msg.push_str(
+                        format!(", ... and {} others", unread.len() - 5)
+                            .as_str(),
+                    );
+                }
+                (
+                    &Lint::UNUSED_BUNDLE_LOCATION,
+                    err.add_note(self.diag.add_info(msg, pos)),
+                )
+            };
+            self.report(lint, err, comp);
+        }
+    }
+
+    fn after_traversal(&mut self) -> Option<u64> {
+        self.diag.report_all()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::cmdline;
+use crate::options;
 use fil_ir::{self as ir, MutCtx};
 
 #[must_use]
@@ -38,7 +38,7 @@ pub struct VisitorData<'comp> {
     /// The idx of the current component.
     pub idx: ir::CompIdx,
     /// The command line options for this pass
-    pub opts: &'comp cmdline::Opts,
+    pub opts: &'comp options::Options,
     /// mutable context reference, held to prevent another
     /// function from mutating the context as it is currently invalid.
     pub mut_ctx: &'comp mut ir::Context,
@@ -51,13 +51,13 @@ impl<'comp> VisitorData<'comp> {
     }
 }
 
-impl<'comp> From<(ir::CompIdx, &'comp cmdline::Opts, &'comp mut ir::Context)>
+impl<'comp> From<(ir::CompIdx, &'comp options::Options, &'comp mut ir::Context)>
     for VisitorData<'comp>
 {
     fn from(
         (idx, opts, ctx): (
             ir::CompIdx,
-            &'comp cmdline::Opts,
+            &'comp options::Options,
             &'comp mut ir::Context,
         ),
     ) -> Self {
@@ -80,14 +80,14 @@ impl Drop for VisitorData<'_> {
 
 /// Construct a visitor
 pub trait Construct {
-    fn from(opts: &cmdline::Opts, ctx: &mut ir::Context) -> Self;
+    fn from(opts: &options::Options, ctx: &mut ir::Context) -> Self;
 
     /// Clear data before the next component has been visited
     fn clear_data(&mut self);
 }
 
 impl<T: Default> Construct for T {
-    fn from(_: &cmdline::Opts, _: &mut ir::Context) -> Self {
+    fn from(_: &options::Options, _: &mut ir::Context) -> Self {
         Self::default()
     }
 
@@ -198,6 +198,14 @@ where
         Action::Continue
     }
 
+    fn value_assert(
+        &mut self,
+        _: &mut ir::ValueAssert,
+        _data: &mut VisitorData,
+    ) -> Action {
+        Action::Continue
+    }
+
     fn visit_cmd(
         &mut self,
         cmd: &mut ir::Command,
@@ -212,6 +220,7 @@ where
             ir::Command::If(i) => self.do_if(i, data),
             ir::Command::Fact(f) => self.fact(f, data),
             ir::Command::Exists(e) => self.exists(e, data),
+            ir::Command::ValueAssert(a) => self.value_assert(a, data),
         }
     }
 
@@ -295,7 +304,10 @@ where
     }
 
     /// Apply the pass to all components in the context
-    fn do_pass(opts: &cmdline::Opts, ctx: &mut ir::Context) -> Result<(), u64> {
+    fn do_pass(
+        opts: &options::Options,
+        ctx: &mut ir::Context,
+    ) -> Result<(), u64> {
         let mut visitor = Self::from(opts, ctx);
         for idx in ctx.comps.idx_iter() {
             visitor.clear_data();
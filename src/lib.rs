@@ -1,6 +1,11 @@
 pub mod cmdline;
 pub mod ir_passes;
 pub mod ir_visitor;
+pub mod library;
+pub mod options;
+pub mod pass_manager;
+pub mod pipeline;
 pub mod resolver;
+pub mod sweep;
 
 mod macros;
@@ -0,0 +1,289 @@
+//! Precompiled libraries.
+//!
+//! Checking a program parses, verifies, and monomorphizes every file it
+//! imports. A precompiled library stores the result of doing this once:
+//! - the checked components and external components defined in each file,
+//!   so that the file is neither parsed nor verified again, and
+//! - the monomorphized bodies of its components for every set of parameters
+//!   they were instantiated with, so that they are not monomorphized again.
+//!   Specializations missing from the library are monomorphized from the
+//!   checked components.
+//!
+//! The library is a JSON document that uses the serialized form of the IR
+//! for the bodies:
+//! ```text
+//! { "version": 2, "options": "..", "positions": { .. }, "modules": { "/path/to/file.fil": { "hash": "..", .. } }, "bodies": { .. } }
+//! ```
+//! A file of the library is only used if neither its contents nor the
+//! contents of the files it imports have changed, and if the program is
+//! checked with the same solver options the library was checked with. Other
+//! files are parsed and checked again. Libraries are created by the
+//! `precompile` command and used with `--precompiled`.
+use crate::options;
+use fil_ast as ast;
+use fil_ir::{self as ir, Ctx};
+use fil_utils::{GlobalPositionTable, PosIdx, PositionData};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Version of the format of libraries
+const VERSION: u32 = 2;
+
+/// Options that change which programs are accepted by the checker. A library
+/// checked under some options is not trusted under others, for example, when
+/// it was checked with the built-in solver only or with a bitvector encoding.
+pub fn options(opts: &options::Options) -> String {
+    let solver = |s: &options::Solver| format!("{s:?}").to_lowercase();
+    format!(
+        "solver={} portfolio={} encoding={}",
+        solver(&opts.solver),
+        opts.portfolio.iter().map(solver).join(","),
+        opts.solver_bv
+            .map_or_else(|| "int".to_string(), |w| format!("bv{w}"))
+    )
+}
+
+#[derive(Serialize, Deserialize)]
+/// The checked definitions of a file
+pub struct Module {
+    /// Hash of the contents of the file
+    hash: String,
+    /// Canonical paths of the files imported by the file
+    imports: Vec<PathBuf>,
+    /// External components with the absolute path of their implementation
+    externs: Vec<(String, Vec<ast::Signature>)>,
+    /// Components defined in the file
+    components: Vec<ast::Component>,
+}
+
+impl Module {
+    /// The definitions of the parsed file. The paths of the imports and
+    /// externs must already have been resolved.
+    pub fn new(
+        path: &Path,
+        imports: &[PathBuf],
+        ns: &ast::Namespace,
+    ) -> Result<Self, String> {
+        let imports = imports
+            .iter()
+            .map(|p| {
+                fs::canonicalize(p)
+                    .map_err(|e| format!("cannot find `{}': {e}", p.display()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            hash: Self::hash(path)?,
+            imports,
+            // Externs are written with their canonical path so that they
+            // do not depend on where the program is checked from
+            externs: ns
+                .externs
+                .iter()
+                .map(|(p, sigs)| {
+                    let p = fs::canonicalize(p).map_or_else(
+                        |_| p.clone(),
+                        |p| p.display().to_string(),
+                    );
+                    (p, sigs.clone())
+                })
+                .collect(),
+            components: ns.components.clone(),
+        })
+    }
+
+    /// SHA-256 hash of the contents of the file
+    fn hash(path: &Path) -> Result<String, String> {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("cannot read `{}': {e}", path.display()))?;
+        Ok(Sha256::digest(bytes)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect())
+    }
+
+    /// The namespace of the file. Components are marked as precompiled.
+    pub fn namespace(&self) -> ast::Namespace {
+        let components = self.components.clone();
+        ast::Namespace {
+            imports: self
+                .imports
+                .iter()
+                .map(|p| p.to_string_lossy().to_string())
+                .collect(),
+            externs: self.externs.clone(),
+            precompiled: components.iter().map(|c| c.sig.name.copy()).collect(),
+            components,
+            toplevels: vec!["main".to_string()],
+            bindings: vec![],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DocumentRef<'a> {
+    version: u32,
+    options: &'a str,
+    positions: PositionData,
+    modules: &'a BTreeMap<PathBuf, Module>,
+    bodies: &'a ir::Context,
+}
+
+#[derive(Deserialize)]
+struct Document {
+    version: u32,
+    options: String,
+    positions: PositionData,
+    /// Read once the positions have been imported
+    modules: serde_json::Value,
+    bodies: serde_json::Value,
+}
+
+/// A specialization of a component provided by a library
+pub struct Specialization<'a> {
+    /// The monomorphized component in [Library::bodies]
+    pub comp: ir::CompIdx,
+    /// Values of the existentially quantified parameters of the component
+    pub exists: &'a [(ast::Id, u64)],
+}
+
+/// A precompiled library
+pub struct Library {
+    /// Files that can be used indexed by their canonical path
+    modules: BTreeMap<PathBuf, Module>,
+    /// Monomorphized components of the library
+    bodies: ir::Context,
+    /// Monomorphized component implementing each specialization
+    specs: HashMap<(ast::Id, Vec<u64>), (ir::CompIdx, usize)>,
+}
+
+impl Library {
+    /// Serialize a library made up of the checked files and the monomorphized
+    /// components generated from them.
+    pub fn serialize(
+        options: &str,
+        modules: &BTreeMap<PathBuf, Module>,
+        bodies: &ir::Context,
+    ) -> String {
+        let doc = DocumentRef {
+            version: VERSION,
+            options,
+            positions: GlobalPositionTable::as_ref().export(),
+            modules,
+            bodies,
+        };
+        serde_json::to_string(&doc).expect("failed to serialize the library")
+    }
+
+    /// Load a library written by [Library::serialize] to be used under the
+    /// given options. Files that changed since the library was written are
+    /// returned separately and are not part of the library.
+    pub fn load(
+        path: &Path,
+        options: &str,
+    ) -> Result<(Self, Vec<PathBuf>), String> {
+        let malformed = |e| format!("`{}' is malformed: {e}", path.display());
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("cannot read `{}': {e}", path.display()))?;
+        let doc: Document = serde_json::from_str(&text).map_err(malformed)?;
+        if doc.version != VERSION {
+            return Err(format!(
+                "`{}' was written by an incompatible version",
+                path.display()
+            ));
+        }
+        if doc.options != options {
+            return Err(format!(
+                "`{}' was checked with `{}' but the program is checked with `{options}'",
+                path.display(),
+                doc.options,
+            ));
+        }
+        let offset = GlobalPositionTable::as_mut().import(doc.positions)?;
+        let (modules, bodies): (BTreeMap<PathBuf, Module>, ir::Context) =
+            PosIdx::relocated(offset, || {
+                Ok((
+                    serde_json::from_value(doc.modules)?,
+                    serde_json::from_value(doc.bodies)?,
+                ))
+            })
+            .map_err(malformed)?;
+
+        let changed = modules
+            .iter()
+            .filter(|(file, m)| Module::hash(file).ok() != Some(m.hash.clone()))
+            .map(|(file, _)| file.clone())
+            .collect_vec();
+        // Files that import a changed file are checked again as well because
+        // the signatures they rely on may have changed.
+        let mut stale: HashSet<_> = changed.iter().cloned().collect();
+        loop {
+            let importers = modules
+                .iter()
+                .filter(|(file, m)| {
+                    !stale.contains(*file)
+                        && m.imports.iter().any(|imp| {
+                            stale.contains(imp) || !modules.contains_key(imp)
+                        })
+                })
+                .map(|(file, _)| file.clone())
+                .collect_vec();
+            if importers.is_empty() {
+                break;
+            }
+            stale.extend(importers);
+        }
+        let modules = modules
+            .into_iter()
+            .filter(|(file, _)| !stale.contains(file))
+            .collect();
+
+        let specs = bodies
+            .iter()
+            .filter(|(_, c)| !c.is_ext)
+            .flat_map(|(idx, c)| {
+                c.specializations.iter().enumerate().map(move |(i, spec)| {
+                    let params = spec.params.iter().map(|(_, v)| *v).collect();
+                    ((spec.name, params), (idx, i))
+                })
+            })
+            .collect();
+        Ok((
+            Self {
+                modules,
+                bodies,
+                specs,
+            },
+            changed,
+        ))
+    }
+
+    /// The checked definitions of the file if it is part of the library
+    pub fn module(&self, file: &Path) -> Option<&Module> {
+        fs::canonicalize(file)
+            .ok()
+            .and_then(|f| self.modules.get(&f))
+    }
+
+    /// Monomorphized components of the library
+    pub fn bodies(&self) -> &ir::Context {
+        &self.bodies
+    }
+
+    /// The monomorphized component implementing the component with the
+    /// given values for its signature parameters
+    pub fn specialization(
+        &self,
+        name: ast::Id,
+        params: &[u64],
+    ) -> Option<Specialization<'_>> {
+        let &(comp, i) = self.specs.get(&(name, params.to_vec()))?;
+        Some(Specialization {
+            comp,
+            exists: &self.bodies.get(comp).specializations[i].exists,
+        })
+    }
+}
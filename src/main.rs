@@ -1,12 +1,17 @@
-use calyx_backend::Backend;
-use calyx_opt::pass_manager::PassManager;
 use fil_ir as ir;
-use filament::ir_passes::BuildDomination;
-use filament::{cmdline, ir_passes as ip, resolver::Resolver};
-use filament::{log_pass, log_time, pass_pipeline};
+use fil_utils::codes;
+use filament::log_time;
+use filament::options::Options;
+use filament::pipeline::{gen_verilog, Pipeline};
+use filament::sweep::{self, Config};
+use filament::{
+    cmdline, ir_passes as ip,
+    library::{self, Library},
+};
+use itertools::Itertools;
+use std::panic::AssertUnwindSafe;
 
-// Prints out the interface for main component in the input program.
-fn run(opts: &cmdline::Opts) -> Result<(), u64> {
+fn init_logger(opts: &cmdline::Opts) {
     // enable tracing
     env_logger::Builder::from_default_env()
         .format_timestamp(None)
@@ -15,41 +20,37 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
         .filter_level(opts.log_level)
         .target(env_logger::Target::Stderr)
         .init();
+}
 
-    let ns = match Resolver::from(opts).parse_namespace() {
-        Ok(mut ns) => {
-            ns.toplevel = opts.toplevel.clone();
-            ns
-        }
-        Err(e) => {
-            eprintln!("Error: {e:?}");
-            return Err(1);
-        }
-    };
+/// The compilation pipeline configured by the command line
+fn pipeline(opts: &cmdline::Opts) -> Pipeline {
+    Pipeline::new(Options::from(opts))
+}
 
-    // Transform AST to IR
-    let mut ir = log_pass! { opts; ir::transform(ns)?, "astconv" };
-    pass_pipeline! {opts, ir;
-        ip::BuildDomination,
-        ip::TypeCheck,
-        ip::IntervalCheck,
-        ip::PhantomCheck,
-        ip::Assume,
-        ip::HoistFacts
-    }
-    if !opts.unsafe_skip_discharge {
-        pass_pipeline! {opts, ir; ip::Discharge }
-    }
-    pass_pipeline! { opts, ir;
-        BuildDomination
+/// Read the IR written by `--emit-ir` and the pass after which it was written
+fn load_ir(path: &std::path::Path) -> Result<(ir::Context, String), u64> {
+    std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read `{}': {e}", path.display()))
+        .and_then(|text| ir::Context::from_json(&text))
+        .map_err(|e| {
+            eprintln!("error: {e}");
+            1
+        })
+}
+
+// Prints out the interface for main component in the input program.
+fn run(opts: &cmdline::Opts) -> Result<(), u64> {
+    let pipeline = pipeline(opts);
+    let ir = if let Some(path) = &opts.from_ir {
+        let (ir, pass) = load_ir(path)?;
+        pipeline.resume(ir, &pass)?
+    } else {
+        if opts.all_concrete && !opts.toplevel.is_empty() {
+            log::warn!("Ignoring --toplevel because --all-concrete is given");
+        }
+        let toplevels = (!opts.all_concrete).then(|| opts.toplevels());
+        pipeline.lower(toplevels)?
     };
-    ir = log_pass! { opts; ip::Monomorphize::transform(&ir), "monomorphize"};
-    pass_pipeline! { opts, ir;
-        ip::Simplify,
-        ip::AssignCheck,
-        ip::BundleElim,
-        ip::AssignCheck
-    }
 
     // Return early if we're asked to dump the interface
     if opts.dump_interface {
@@ -61,10 +62,13 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
     if opts.check {
         return Ok(());
     }
-    let calyx = log_time!(
-        ip::Compile::compile(ir, opts.disable_slow_fsms, opts.preserve_names),
-        "compile"
-    );
+    if let Some(path) = &opts.name_map {
+        if let Err(e) = std::fs::write(path, ip::Compile::name_map(&ir)) {
+            eprintln!("error: cannot write the name map: {e}");
+            return Err(1);
+        }
+    }
+    let calyx = log_time!(pipeline.compile(ir), "compile");
     match opts.backend {
         cmdline::Backend::Verilog => {
             gen_verilog(calyx).unwrap();
@@ -77,27 +81,274 @@ fn run(opts: &cmdline::Opts) -> Result<(), u64> {
     Ok(())
 }
 
-fn gen_verilog(mut ctx: calyx_ir::Context) -> Result<(), calyx_utils::Error> {
-    let pm = PassManager::default_passes()?;
-    let backend_conf = calyx_ir::BackendConf {
-        synthesis_mode: false,
-        enable_verification: false,
-        flat_assign: true,
+/// Check and compile every configuration of the sweep and write a CSV of the
+/// resulting designs
+fn sweep(opts: &cmdline::Opts, sweep: &cmdline::Sweep) -> Result<(), u64> {
+    let config = match Config::load(&sweep.config) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return Err(1);
+        }
+    };
+    let mut opts = opts.clone();
+    opts.input = Some(config.file.clone());
+    if let Some(lib) = &config.library {
+        opts.library = lib.clone();
+    }
+    opts.check = false;
+    opts.all_concrete = false;
+    opts.toplevel = vec![config.component.clone()];
+
+    let ns = match pipeline(&opts).resolver().parse_namespace() {
+        Ok(ns) => ns,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            return Err(1);
+        }
+    };
+    if let Err(e) = config.validate(&ns) {
+        eprintln!("error: {e}");
+        return Err(1);
+    }
+
+    let mut table = sweep::Table::default();
+    for point in config.points() {
+        log::info!(
+            "sweep: {}",
+            point.iter().map(|(n, v)| format!("{n}={v}")).join(", ")
+        );
+        opts.params = point.clone();
+        let outcome = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            sweep_point(&opts, &config.component)
+        }))
+        .unwrap_or_else(|_| {
+            sweep::Outcome::Invalid("internal compiler error".to_string())
+        });
+        table.add(point, outcome);
+    }
+
+    let written = match &sweep.output {
+        Some(path) => {
+            std::fs::File::create(path).and_then(|mut f| table.write(&mut f))
+        }
+        None => table.write(&mut std::io::stdout()),
+    };
+    if let Err(e) = written {
+        eprintln!("error: cannot write the results: {e}");
+        return Err(1);
+    }
+    eprintln!(
+        "Swept {} configurations of `{}': {} invalid.",
+        table.configs(),
+        config.component,
+        table.invalid()
+    );
+    Ok(())
+}
+
+/// Check and compile the component with the parameter values in `opts`
+fn sweep_point(opts: &cmdline::Opts, comp: &str) -> sweep::Outcome {
+    let pipeline = pipeline(opts);
+    let mut resolver = pipeline.resolver();
+    let ns = match pipeline.parse(&mut resolver, Some(vec![comp.to_string()])) {
+        Ok(ns) => ns,
+        Err(e) => {
+            return sweep::Outcome::Invalid(format!(
+                "cannot parse the program: {}",
+                e.kind
+            ))
+        }
+    };
+    let ir = match ir::try_transform(ns) {
+        Ok(ir) => ir,
+        Err(mut diag) => {
+            // Tell configurations rejected by the `where' clauses apart
+            // from errors in the program using the first error
+            let reason = match diag.first_error() {
+                Some(e) if e.code == Some(codes::PARAM_CONSTRAINT) => format!(
+                    "parameters violate the `where' clauses of `{comp}'"
+                ),
+                Some(e) => match e.code {
+                    Some(code) => format!("error[{code}]: {}", e.kind),
+                    None => format!("error: {}", e.kind),
+                },
+                None => "the program could not be built".to_string(),
+            };
+            diag.report_all();
+            return sweep::Outcome::Invalid(reason);
+        }
+    };
+    let ir = match pipeline.verify(ir) {
+        Ok(ir) => ir,
+        Err(failure) => {
+            return sweep::Outcome::Invalid(failure.first.map_or_else(
+                || {
+                    format!(
+                        "verification failed with {} errors",
+                        failure.errors
+                    )
+                },
+                |e| e.to_string(),
+            ))
+        }
+    };
+    let ir = match pipeline.monomorphize(ir, resolver.libraries()) {
+        Ok(ir) => ir,
+        Err(failure) => {
+            return sweep::Outcome::Invalid(failure.first.map_or_else(
+                || {
+                    format!(
+                        "checks of the monomorphized design failed with {} errors",
+                        failure.errors
+                    )
+                },
+                |e| e.to_string(),
+            ))
+        }
+    };
+    let metrics = ip::Metrics::new(&ir);
+    pipeline.compile(ir);
+    sweep::Outcome::Valid(metrics)
+}
+
+/// Verify every component of the program and its imports, monomorphize the
+/// toplevel components, and write the result as a precompiled library. If no
+/// toplevel components are given, every component without signature
+/// parameters is a toplevel component.
+fn precompile(
+    opts: &cmdline::Opts,
+    precompile: &cmdline::Precompile,
+) -> Result<(), u64> {
+    let pipeline = pipeline(opts);
+    if !pipeline.discharges()? {
+        eprintln!("error: cannot precompile a library without discharging its proof obligations");
+        return Err(1);
+    }
+    let mut resolver = pipeline.resolver();
+    let toplevels = (!opts.toplevel.is_empty()).then(|| opts.toplevels());
+    let ir = pipeline.verify(pipeline.build(&mut resolver, toplevels)?)?;
+    let ir = pipeline.specialize(ir, resolver.libraries())?;
+    // The bodies are stored as they are monomorphized because programs using
+    // the library run the remaining passes on them
+    let options = library::options(pipeline.options());
+    let lib = Library::serialize(&options, resolver.modules(), &ir);
+    let specs = ir
+        .comps
+        .iter()
+        .map(|(_, c)| c.specializations.len())
+        .sum::<usize>();
+    pipeline.check_specialized(ir)?;
+    if let Err(e) = std::fs::write(&precompile.output, lib) {
+        eprintln!("error: cannot write `{}': {e}", precompile.output.display());
+        return Err(1);
+    }
+    eprintln!(
+        "Precompiled {} files with {specs} specializations into `{}'.",
+        resolver.modules().len(),
+        precompile.output.display()
+    );
+    Ok(())
+}
+
+/// Parse the textual IR in the input file, run a single pass on it, and print
+/// the resulting IR
+fn run_pass(opts: &cmdline::Opts, run: &cmdline::RunPass) -> Result<(), u64> {
+    let pipeline = pipeline(opts);
+    let pm = pipeline.pass_manager();
+    if pm.get(&run.pass).is_none() {
+        eprintln!(
+            "error: unknown pass `{}'. Known passes are: {}",
+            run.pass,
+            pm.known()
+        );
+        return Err(1);
+    };
+    let path = opts.input.as_ref().unwrap();
+    let mut ir = match ir::IrParser::parse_file(path) {
+        Ok(ir) => ir,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return Err(1);
+        }
     };
-    ctx.bc = backend_conf;
-    pm.execute_plan(
-        &mut ctx,
-        &["all".to_string()],
-        &["canonicalize".to_string()],
-        false,
-    )?;
-    let backend = calyx_backend::VerilogBackend;
-    backend.run(ctx, calyx_utils::OutputFile::Stdout)
+    let pass = std::slice::from_ref(&run.pass);
+    pm.run(pipeline.options(), &[], &mut ir, pass)?;
+    ir::Printer::context(&ir, &mut std::io::stdout()).unwrap();
+    Ok(())
+}
+
+/// Check that two components of the program are equivalent
+fn equiv(opts: &cmdline::Opts, equiv: &cmdline::Equiv) -> Result<(), u64> {
+    let pipeline = pipeline(opts);
+    let left = pipeline.lower(Some(vec![equiv.left.clone()]))?;
+    let right = pipeline.lower(Some(vec![equiv.right.clone()]))?;
+    ip::check_equiv(
+        pipeline.options(),
+        (&equiv.left, &left),
+        (&equiv.right, &right),
+    )
 }
 
 fn main() {
     let opts: cmdline::Opts = argh::from_env();
-    match run(&opts) {
+    if let Some(cmdline::Command::VerifyCert(verify)) = &opts.command {
+        if let Err(failed) = ip::verify_certs(verify) {
+            eprintln!("{failed} certificates could not be verified.");
+            std::process::exit(1)
+        }
+        return;
+    }
+    if let Some(cmdline::Command::Replay(replay)) = &opts.command {
+        init_logger(&opts);
+        if let Err(failed) = ip::replay(replay) {
+            eprintln!("Replay failed with {failed} errors.");
+            std::process::exit(1)
+        }
+        return;
+    }
+    if let Some(code) = &opts.explain {
+        match codes::explain(code) {
+            Some(explanation) => {
+                print!("{explanation}");
+                return;
+            }
+            None => {
+                eprintln!(
+                    "error: unknown code `{code}'. Known codes are: {}",
+                    codes::all().join(", ")
+                );
+                std::process::exit(1)
+            }
+        }
+    }
+    if let Some(cmdline::Command::Sweep(sw)) = &opts.command {
+        init_logger(&opts);
+        if let Err(failed) = sweep(&opts, sw) {
+            eprintln!("Sweep failed with {failed} errors.");
+            std::process::exit(1)
+        }
+        return;
+    }
+    if opts.print_passes {
+        if let Err(e) = pipeline(&opts).print_passes() {
+            eprintln!("error: {e}");
+            std::process::exit(1)
+        }
+        return;
+    }
+    if opts.input.is_none() && opts.from_ir.is_none() {
+        eprintln!("error: no input file provided");
+        std::process::exit(1)
+    }
+    init_logger(&opts);
+    let res = match &opts.command {
+        Some(cmdline::Command::Equiv(eq)) => equiv(&opts, eq),
+        Some(cmdline::Command::Precompile(pc)) => precompile(&opts, pc),
+        Some(cmdline::Command::RunPass(rp)) => run_pass(&opts, rp),
+        _ => run(&opts),
+    };
+    match res {
         Ok(_) => (),
         Err(err) => {
             eprintln!("Compilation failed with {err} errors.");
@@ -0,0 +1,101 @@
+//! Options of the compiler passes and pipeline. They do not depend on the
+//! command line: the driver converts its arguments into [Options] and
+//! downstream crates can construct them directly.
+use fil_utils::LintConfig;
+use std::{path::PathBuf, str::FromStr};
+
+#[derive(Debug, Default, Clone, Copy)]
+/// Solver to use in the pass
+pub enum Solver {
+    CVC5,
+    #[default]
+    Z3,
+    Boolector,
+    Bitwuzla,
+    /// Only use the built-in decision procedure
+    Builtin,
+}
+
+impl FromStr for Solver {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "z3" => Ok(Solver::Z3),
+            "cvc5" => Ok(Solver::CVC5),
+            "boolector" => Ok(Solver::Boolector),
+            "bitwuzla" => Ok(Solver::Bitwuzla),
+            "builtin" => Ok(Solver::Builtin),
+            _ => {
+                Err(format!("unknown solver: {s}. Known solvers are: z3, cvc5, boolector, bitwuzla, builtin"))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+/// Configuration of the passes and the pipeline running them
+pub struct Options {
+    /// The input program
+    pub input: Option<PathBuf>,
+    /// Path to search for imports
+    pub library: PathBuf,
+    /// Precompiled libraries whose files are not verified and whose
+    /// specializations are not monomorphized again
+    pub precompiled: Vec<PathBuf>,
+    /// Print the IR after these passes
+    pub dump_after: Vec<String>,
+    /// Write the IR to the file after the last run of the pass, or after the
+    /// last pass of the pipeline if no pass is given
+    pub emit_ir: Option<(Option<String>, PathBuf)>,
+    /// Print assignments that falsify the constraints
+    pub show_models: bool,
+    /// Only check the program. Parametric toplevel components without
+    /// values for their parameters are verified but not monomorphized.
+    pub check: bool,
+    /// Values of the signature parameters of the toplevel components
+    pub params: Vec<(String, u64)>,
+    /// Lint levels
+    pub lints: LintConfig,
+
+    // Pipeline configuration
+    /// Passes to run instead of the default pipeline
+    pub passes: Option<Vec<String>>,
+    /// Passes removed from the pipeline
+    pub disable_pass: Vec<String>,
+    /// Skip the discharge and bmc passes (unsafe)
+    pub unsafe_skip_discharge: bool,
+    /// Share instances of external components whose invocations never
+    /// overlap
+    pub share_instances: bool,
+    /// Keep structurally identical specializations of a component as
+    /// separate components
+    pub no_dedup: bool,
+
+    // Backend configuration
+    /// Disable generation of slow FSMs in the backend
+    pub disable_slow_fsms: bool,
+    /// Preserve original port names during compilation
+    pub preserve_names: bool,
+
+    // Solver configuration
+    /// External solver used for constraints the built-in solver cannot decide
+    pub solver: Solver,
+    /// Timeout in milliseconds for each query to an external solver
+    pub solver_timeout: Option<u64>,
+    /// External solvers raced instead of `solver`
+    pub portfolio: Vec<Solver>,
+    /// Solve assertions separately rather than all at once
+    pub discharge_separate: bool,
+    /// Dump interactions with the solver in the given file
+    pub solver_replay_file: Option<String>,
+    /// Use bitvector encoding for proofs with at most the given width
+    pub solver_bv: Option<u8>,
+    /// Emit a proof certificate for each verified component into the
+    /// directory
+    pub emit_certs: Option<PathBuf>,
+    /// Write designs and stimuli reproducing counterexamples into the
+    /// directory
+    pub emit_counterexample: Option<PathBuf>,
+    /// Record statistics about each proof obligation in the given JSON file
+    pub profile_discharge: Option<PathBuf>,
+}
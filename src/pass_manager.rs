@@ -0,0 +1,374 @@
+//! Registry of the passes of the compiler and the pipelines that run them.
+use crate::{
+    ir_passes as ip, ir_visitor::Visitor, library::Library, log_time, options,
+};
+use fil_ir as ir;
+use itertools::Itertools;
+
+/// Runs a pass on the context using the precompiled libraries. Returns the
+/// number of errors on failure.
+pub type PassFn =
+    fn(&options::Options, &[Library], &mut ir::Context) -> Result<(), u64>;
+
+/// Name of the pass that separates the verification of the parametric
+/// program from the checks of the monomorphized program
+pub const MONOMORPHIZE: &str = "monomorphize";
+
+/// A pass known to the [PassManager]
+#[derive(Clone)]
+pub struct Pass {
+    /// The user visible name of the pass
+    pub name: &'static str,
+    /// One-line description printed by `--print-passes`
+    pub description: &'static str,
+    /// Passes that must run earlier in any pipeline containing this pass
+    pub after: &'static [&'static str],
+    /// Passes that must not run earlier than this pass
+    pub before: &'static [&'static str],
+    /// Passes that must run later in any pipeline containing this pass
+    pub then: &'static [&'static str],
+    run: PassFn,
+}
+
+/// Registry of passes keyed by their names
+pub struct PassManager {
+    /// Passes in the order they were registered
+    passes: Vec<Pass>,
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        let mut pm = PassManager { passes: vec![] };
+        pm.register_visitor::<ip::BuildDomination>(
+            "hoist instances and invocations so that definitions dominate their uses",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::TypeCheck>(
+            "check port accesses, bitwidths, and event arguments",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::IntervalCheck>(
+            "check the availability and liveness of ports and instances",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::PhantomCheck>(
+            "check the uses of phantom events",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::Assume>(
+            "add default assumptions for uses of custom functions",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::HoistFacts>(
+            "hoist facts to the top level by adding their path conditions",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::Discharge>(
+            "discharge top-level assertions with the solver",
+            &["hoist-facts"],
+            &[],
+            &[],
+        );
+        pm.register(
+            MONOMORPHIZE,
+            "specialize the components used by the top-level components",
+            &[],
+            &[],
+            &[],
+            |_, libs, ir| {
+                ip::Monomorphize::check_entry_args(ir)?;
+                *ir = ip::Monomorphize::transform(ir, libs);
+                Ok(())
+            },
+        );
+        pm.register_visitor::<ip::Simplify>(
+            "simplify propositions and expressions",
+            &[],
+            &[],
+            &[],
+        );
+        pm.register(
+            "dedup",
+            "merge structurally identical specializations",
+            &[MONOMORPHIZE],
+            &[],
+            &[],
+            |_, _, ir| {
+                *ir = ip::Dedup::transform(std::mem::take(ir));
+                Ok(())
+            },
+        );
+        pm.register_visitor::<ip::AssignCheck>(
+            "check that each port index is written at most once",
+            &[MONOMORPHIZE],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::UnusedCheck>(
+            "report unused instances, invocations, and ports",
+            &[MONOMORPHIZE],
+            &["bundle-elim"],
+            &[],
+        );
+        pm.register_visitor::<ip::BundleElim>(
+            "replace bundles with individual ports",
+            &[MONOMORPHIZE],
+            &[],
+            &[],
+        );
+        pm.register_visitor::<ip::CombLoopCheck>(
+            "detect combinational loops through invocations",
+            &[MONOMORPHIZE, "bundle-elim"],
+            &[],
+            &[],
+        );
+        // Merged instances add assertions that must be discharged again
+        pm.register_visitor::<ip::ShareInstances>(
+            "share instances whose invocations never overlap",
+            &[MONOMORPHIZE, "bundle-elim"],
+            &[],
+            &["discharge"],
+        );
+        pm.register_visitor::<ip::Bmc>(
+            "check assertions on port values with bounded model checking",
+            &[MONOMORPHIZE, "bundle-elim"],
+            &[],
+            &[],
+        );
+        pm
+    }
+}
+
+impl PassManager {
+    /// Register a pass under the given name
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        description: &'static str,
+        after: &'static [&'static str],
+        before: &'static [&'static str],
+        then: &'static [&'static str],
+        run: PassFn,
+    ) {
+        assert!(
+            self.get(name).is_none(),
+            "pass `{name}' is registered twice"
+        );
+        self.passes.push(Pass {
+            name,
+            description,
+            after,
+            before,
+            then,
+            run,
+        });
+    }
+
+    /// Register a visitor under its [Visitor::name]
+    pub fn register_visitor<V: Visitor>(
+        &mut self,
+        description: &'static str,
+        after: &'static [&'static str],
+        before: &'static [&'static str],
+        then: &'static [&'static str],
+    ) {
+        self.register(
+            V::name(),
+            description,
+            after,
+            before,
+            then,
+            |opts, _, ir| V::do_pass(opts, ir),
+        )
+    }
+
+    /// The pass with the given name
+    pub fn get(&self, name: &str) -> Option<&Pass> {
+        self.passes.iter().find(|p| p.name == name)
+    }
+
+    /// All passes in the order they were registered
+    pub fn passes(&self) -> impl Iterator<Item = &Pass> {
+        self.passes.iter()
+    }
+
+    /// Comma-separated names of the registered passes
+    pub fn known(&self) -> String {
+        self.passes.iter().map(|p| p.name).join(", ")
+    }
+
+    /// The pipeline run when `--passes` is not given
+    fn default_pipeline(opts: &options::Options) -> Vec<String> {
+        let mut passes = vec![
+            "build-domination",
+            "type-check",
+            "interval-check",
+            "phantom-check",
+            "add-assume",
+            "hoist-facts",
+            "discharge",
+            "build-domination",
+            MONOMORPHIZE,
+            "simplify",
+            // Specializations are only compared after their assertions have
+            // been simplified
+            "dedup",
+            "assign-check",
+            "unused-check",
+            "bundle-elim",
+            "assign-check",
+            "comb-loop-check",
+        ];
+        if opts.no_dedup {
+            passes.retain(|p| *p != "dedup");
+        }
+        // Infer resource sharing and re-verify the merged instances
+        if opts.share_instances {
+            passes.extend(["share-instances", "discharge"]);
+        }
+        passes.push("bmc");
+        passes.into_iter().map(String::from).collect()
+    }
+
+    /// The pipeline selected by `--passes`, `--disable-pass`, and
+    /// `--unsafe-skip-discharge`
+    pub fn pipeline(
+        &self,
+        opts: &options::Options,
+    ) -> Result<Vec<String>, String> {
+        self.check(opts, self.select(opts)?)
+    }
+
+    /// The passes selected by `--passes` and `--disable-pass`
+    pub fn select(
+        &self,
+        opts: &options::Options,
+    ) -> Result<Vec<String>, String> {
+        let mut passes = opts
+            .passes
+            .clone()
+            .unwrap_or_else(|| Self::default_pipeline(opts));
+        for name in passes.iter().chain(&opts.disable_pass) {
+            if self.get(name).is_none() {
+                return Err(format!(
+                    "unknown pass `{name}'. Known passes are: {}",
+                    self.known()
+                ));
+            }
+        }
+        passes.retain(|p| !opts.disable_pass.contains(p));
+        Ok(passes)
+    }
+
+    /// Validate the selected passes and remove the passes skipped by
+    /// `--unsafe-skip-discharge`. Unless the program is only checked, the
+    /// pipeline must produce a program that can be compiled.
+    pub fn check(
+        &self,
+        opts: &options::Options,
+        mut passes: Vec<String>,
+    ) -> Result<Vec<String>, String> {
+        self.validate(&passes)?;
+        if !opts.check {
+            if let Some(req) = [MONOMORPHIZE, "bundle-elim"]
+                .iter()
+                .find(|req| !passes.iter().any(|p| p == *req))
+            {
+                return Err(format!(
+                    "the pipeline must contain `{req}' to compile the program"
+                ));
+            }
+        }
+        if opts.unsafe_skip_discharge {
+            passes.retain(|p| p != "discharge" && p != "bmc");
+        }
+        Ok(passes)
+    }
+
+    /// Check the ordering constraints of the passes in the pipeline
+    pub fn validate(&self, pipeline: &[String]) -> Result<(), String> {
+        for (idx, name) in pipeline.iter().enumerate() {
+            let pass = self.get(name).unwrap();
+            let (earlier, later) = (&pipeline[..idx], &pipeline[idx + 1..]);
+            if let Some(req) = pass
+                .after
+                .iter()
+                .find(|req| !earlier.iter().any(|p| p == *req))
+            {
+                return Err(format!(
+                    "pass `{name}' must run after `{req}' in the pipeline"
+                ));
+            }
+            if let Some(next) =
+                pass.before.iter().find(|b| earlier.iter().any(|p| p == *b))
+            {
+                return Err(format!(
+                    "pass `{name}' must run before `{next}' in the pipeline"
+                ));
+            }
+            if let Some(req) =
+                pass.then.iter().find(|t| !later.iter().any(|p| p == *t))
+            {
+                return Err(format!(
+                    "pass `{name}' must be followed by `{req}' in the pipeline"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the passes in order. Prints the IR after each pass mentioned by
+    /// `--dump-after`.
+    pub fn run(
+        &self,
+        opts: &options::Options,
+        libs: &[Library],
+        ir: &mut ir::Context,
+        pipeline: &[String],
+    ) -> Result<(), u64> {
+        for name in pipeline {
+            let pass = self.get(name).unwrap();
+            log_time!((pass.run)(opts, libs, ir)?, name);
+            if opts.dump_after.contains(name) {
+                ir::Printer::context(ir, &mut std::io::stdout()).unwrap()
+            }
+        }
+        Ok(())
+    }
+
+    /// Print the registered passes and the pipeline
+    pub fn print(&self, pipeline: &[String]) {
+        println!("Passes:");
+        let width = self.passes.iter().map(|p| p.name.len()).max();
+        for pass in &self.passes {
+            print!(
+                "  {:width$}  {}",
+                pass.name,
+                pass.description,
+                width = width.unwrap_or_default()
+            );
+            let after = pass.after.iter().map(|p| format!("after {p}"));
+            let before = pass.before.iter().map(|p| format!("before {p}"));
+            let then = pass.then.iter().map(|p| format!("then {p}"));
+            let order = after.chain(before).chain(then).join(", ");
+            if !order.is_empty() {
+                print!(" ({order})");
+            }
+            println!();
+        }
+        println!();
+        println!("Pipeline: {}", pipeline.join(" -> "));
+    }
+}
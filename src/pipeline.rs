@@ -0,0 +1,428 @@
+//! The compilation pipeline of the driver. Downstream crates can register
+//! their own [Visitor] passes and insert them at named points of the
+//! pipeline, i.e., before or after a built-in pass:
+//!
+//! ```text
+//! let opts = Options {
+//!     input: Some("design.fil".into()),
+//!     ..Options::default()
+//! };
+//! let pipeline = Pipeline::new(opts)
+//!     .register::<PowerEstimate>("estimate the power of the design", &[MONOMORPHIZE], &[], &[])
+//!     .insert_after("bundle-elim", PowerEstimate::name());
+//! let ir = pipeline.lower(Some(vec!["main".to_string()]))?;
+//! let calyx = pipeline.compile(ir);
+//! ```
+use crate::{
+    ir_passes as ip,
+    ir_visitor::Visitor,
+    library::Library,
+    log_pass,
+    options::Options,
+    pass_manager::{PassManager, MONOMORPHIZE},
+    resolver::Resolver,
+};
+use calyx_backend::Backend;
+use calyx_opt::pass_manager::PassManager as CalyxPassManager;
+use fil_ast as ast;
+use fil_ir as ir;
+use fil_utils::{FilamentResult, Reported};
+use std::{collections::HashMap, ops::Range};
+
+/// A named point of the pipeline
+enum Point {
+    /// Before the first run of the pass
+    Before(String),
+    /// After the first run of the pass
+    After(String),
+}
+
+/// Index of the monomorphization pass or the number of passes if the
+/// pipeline does not monomorphize the program
+fn mono_idx(passes: &[String]) -> usize {
+    passes
+        .iter()
+        .position(|p| p == MONOMORPHIZE)
+        .unwrap_or(passes.len())
+}
+
+/// Errors reported by the passes of a stage of the pipeline
+#[derive(Debug)]
+pub struct Failure {
+    /// Number of errors reported
+    pub errors: u64,
+    /// The first error reported, if it was reported as a diagnostic
+    pub first: Option<Reported>,
+}
+
+impl From<u64> for Failure {
+    fn from(errors: u64) -> Self {
+        Self {
+            errors,
+            first: None,
+        }
+    }
+}
+
+impl From<Failure> for u64 {
+    fn from(failure: Failure) -> Self {
+        failure.errors
+    }
+}
+
+/// Parses, checks, and compiles programs with the passes selected by the
+/// [Options] and the passes inserted by the user
+pub struct Pipeline {
+    opts: Options,
+    passes: PassManager,
+    /// Passes inserted into the pipeline selected by the options
+    inserts: Vec<(Point, String)>,
+}
+
+impl Pipeline {
+    /// Pipeline running the passes selected by the options
+    pub fn new(opts: Options) -> Self {
+        Self {
+            opts,
+            passes: PassManager::default(),
+            inserts: vec![],
+        }
+    }
+
+    /// The options of the pipeline
+    pub fn options(&self) -> &Options {
+        &self.opts
+    }
+
+    /// The passes known to the pipeline
+    pub fn pass_manager(&self) -> &PassManager {
+        &self.passes
+    }
+
+    /// Make the visitor known to the pipeline. `after`, `before`, and `then`
+    /// are ordering constraints like those of [crate::pass_manager::Pass].
+    /// Registering a pass does not run it.
+    pub fn register<V: Visitor>(
+        mut self,
+        description: &'static str,
+        after: &'static [&'static str],
+        before: &'static [&'static str],
+        then: &'static [&'static str],
+    ) -> Self {
+        self.passes
+            .register_visitor::<V>(description, after, before, then);
+        self
+    }
+
+    /// Run the pass right before the first run of `point`
+    pub fn insert_before(mut self, point: &str, pass: &str) -> Self {
+        self.inserts
+            .push((Point::Before(point.to_string()), pass.to_string()));
+        self
+    }
+
+    /// Run the pass right after the first run of `point`. Passes inserted
+    /// after the same point run in the order they were inserted.
+    pub fn insert_after(mut self, point: &str, pass: &str) -> Self {
+        self.inserts
+            .push((Point::After(point.to_string()), pass.to_string()));
+        self
+    }
+
+    /// The passes run by the pipeline
+    pub fn passes(&self) -> Result<Vec<String>, String> {
+        let mut passes = self.passes.select(&self.opts)?;
+        // Number of passes inserted after each point so far
+        let mut after: HashMap<&str, usize> = HashMap::new();
+        for (point, pass) in &self.inserts {
+            if self.passes.get(pass).is_none() {
+                return Err(format!(
+                    "cannot insert unknown pass `{pass}'. Known passes are: {}",
+                    self.passes.known()
+                ));
+            }
+            let (Point::Before(name) | Point::After(name)) = point;
+            let Some(mut idx) = passes.iter().position(|p| p == name) else {
+                return Err(format!(
+                    "cannot insert `{pass}' at `{name}' because `{name}' is not in the pipeline"
+                ));
+            };
+            if let Point::After(name) = point {
+                let count = after.entry(name).or_default();
+                idx += 1 + *count;
+                *count += 1;
+            }
+            passes.insert(idx, pass.clone());
+        }
+        self.passes.check(&self.opts, passes)
+    }
+
+    /// Print the known passes and the pipeline
+    pub fn print_passes(&self) -> Result<(), String> {
+        self.passes.print(&self.passes()?);
+        Ok(())
+    }
+
+    /// The passes run by the pipeline
+    fn all(&self) -> Result<Vec<String>, u64> {
+        self.passes().map_err(|e| {
+            eprintln!("error: {e}");
+            1
+        })
+    }
+
+    /// The passes run by the pipeline, split into the passes that verify the
+    /// parametric program and the passes from monomorphization onwards
+    fn split(&self) -> Result<(Vec<String>, Vec<String>), u64> {
+        let mut passes = self.all()?;
+        let rest = passes.split_off(mono_idx(&passes));
+        Ok((passes, rest))
+    }
+
+    /// Index of the pass after which the IR is written. Without a pass name,
+    /// the IR is written after the last pass.
+    fn emit_point(&self, passes: &[String]) -> Result<Option<usize>, u64> {
+        let Some((pass, _)) = &self.opts.emit_ir else {
+            return Ok(None);
+        };
+        let Some(pass) = pass.as_ref().or(passes.last()) else {
+            eprintln!("error: cannot write the IR of an empty pipeline");
+            return Err(1);
+        };
+        match passes.iter().rposition(|p| p == pass) {
+            Some(idx) => Ok(Some(idx)),
+            None => {
+                eprintln!("error: cannot write the IR after `{pass}' because it is not in the pipeline");
+                Err(1)
+            }
+        }
+    }
+
+    /// Write the IR produced by the pass to the file given by the options
+    fn emit(&self, ir: &ir::Context, pass: &str) -> Result<(), u64> {
+        let Some((_, path)) = &self.opts.emit_ir else {
+            return Ok(());
+        };
+        std::fs::write(path, ir.to_json(pass)).map_err(|e| {
+            eprintln!("error: cannot write the IR: {e}");
+            1
+        })
+    }
+
+    /// Warn if the IR will not be written because the passes from
+    /// monomorphization onwards are skipped
+    fn skip_monomorphize(&self) -> Result<(), u64> {
+        let passes = self.all()?;
+        if matches!(self.emit_point(&passes)?, Some(idx) if idx >= mono_idx(&passes))
+        {
+            log::warn!("The IR is not written because the program is only checked and not monomorphized");
+        }
+        Ok(())
+    }
+
+    /// Whether the pipeline discharges the proof obligations of the
+    /// parametric program
+    pub fn discharges(&self) -> Result<bool, u64> {
+        Ok(self.split()?.0.iter().any(|p| p == "discharge"))
+    }
+
+    /// Resolver for the input program and the libraries in the options
+    pub fn resolver(&self) -> Resolver {
+        Resolver::from(&self.opts)
+    }
+
+    /// Parse the program using the resolver with the given toplevel
+    /// components. If no toplevel components are given, every component
+    /// without signature parameters is a toplevel component.
+    pub fn parse(
+        &self,
+        resolver: &mut Resolver,
+        toplevels: Option<Vec<String>>,
+    ) -> FilamentResult<ast::Namespace> {
+        let mut ns = resolver.parse_namespace()?;
+        ns.toplevels = toplevels.unwrap_or_else(|| ns.concrete_components());
+        ns.bindings = self.opts.params.clone();
+        Ok(ns)
+    }
+
+    /// Parse the program using the resolver and convert it to the IR with the
+    /// given toplevel components.
+    pub fn build(
+        &self,
+        resolver: &mut Resolver,
+        toplevels: Option<Vec<String>>,
+    ) -> Result<ir::Context, u64> {
+        let ns = match self.parse(resolver, toplevels) {
+            Ok(ns) => ns,
+            Err(e) => {
+                eprintln!("Error: {e:?}");
+                return Err(1);
+            }
+        };
+
+        // Transform AST to IR
+        Ok(log_pass! { self.opts; ir::transform(ns)?, "astconv" })
+    }
+
+    /// Run the passes of the pipeline in the range, write the IR if the
+    /// options select one of them, and remember the first error reported
+    fn run(
+        &self,
+        ir: &mut ir::Context,
+        libs: &[Library],
+        range: Range<usize>,
+    ) -> Result<(), Failure> {
+        let passes = self.all()?;
+        let emit = self.emit_point(&passes)?;
+        let failure = |errors| Failure {
+            errors,
+            first: Reported::take_first(),
+        };
+        Reported::take_first();
+        match emit {
+            Some(idx) if range.contains(&idx) => {
+                let (before, after) =
+                    (range.start..idx + 1, idx + 1..range.end);
+                self.passes
+                    .run(&self.opts, libs, ir, &passes[before])
+                    .map_err(failure)?;
+                self.emit(ir, &passes[idx])?;
+                self.passes
+                    .run(&self.opts, libs, ir, &passes[after])
+                    .map_err(failure)
+            }
+            _ => self
+                .passes
+                .run(&self.opts, libs, ir, &passes[range])
+                .map_err(failure),
+        }
+    }
+
+    /// Type check the program and discharge its proof obligations
+    pub fn verify(&self, mut ir: ir::Context) -> Result<ir::Context, Failure> {
+        let (passes, _) = self.split()?;
+        self.run(&mut ir, &[], 0..passes.len())?;
+        Ok(ir)
+    }
+
+    /// Monomorphize the verified program using the bodies of the precompiled
+    /// libraries
+    pub fn specialize(
+        &self,
+        mut ir: ir::Context,
+        libs: &[Library],
+    ) -> Result<ir::Context, Failure> {
+        let (verify, passes) = self.split()?;
+        if passes.is_empty() {
+            eprintln!("error: the pipeline must contain `{MONOMORPHIZE}' to compile the program");
+            return Err(1.into());
+        }
+        let mono = verify.len();
+        self.run(&mut ir, libs, mono..mono + 1)?;
+        Ok(ir)
+    }
+
+    /// Check the components of the monomorphized program
+    pub fn check_specialized(
+        &self,
+        mut ir: ir::Context,
+    ) -> Result<ir::Context, Failure> {
+        let (verify, passes) = self.split()?;
+        let end = verify.len() + passes.len();
+        // The range is empty if the pipeline does not monomorphize
+        let start = (verify.len() + 1).min(end);
+        self.run(&mut ir, &[], start..end)?;
+        Ok(ir)
+    }
+
+    /// Monomorphize the verified program using the bodies of the precompiled
+    /// libraries and check the resulting components
+    pub fn monomorphize(
+        &self,
+        ir: ir::Context,
+        libs: &[Library],
+    ) -> Result<ir::Context, Failure> {
+        if self.opts.check && self.split()?.1.is_empty() {
+            self.skip_monomorphize()?;
+            return Ok(ir);
+        }
+        self.check_specialized(self.specialize(ir, libs)?)
+    }
+
+    /// Check and lower the program with the given toplevel components. If no
+    /// toplevel components are given, every component without signature
+    /// parameters is a toplevel component.
+    pub fn lower(
+        &self,
+        toplevels: Option<Vec<String>>,
+    ) -> Result<ir::Context, Failure> {
+        let mut resolver = self.resolver();
+        let ir = self.verify(self.build(&mut resolver, toplevels)?)?;
+        // Parametric toplevel components without values for their parameters
+        // can be verified but not monomorphized
+        if self.opts.check
+            && !ip::Monomorphize::unbound_entrypoints(&ir).is_empty()
+        {
+            self.skip_monomorphize()?;
+            return Ok(ir);
+        }
+        self.monomorphize(ir, resolver.libraries())
+    }
+
+    /// Run the passes that follow `pass` in the pipeline on the IR written
+    /// after it with [Options::emit_ir]. Components of precompiled libraries
+    /// are monomorphized from the IR instead of being copied from the
+    /// libraries.
+    pub fn resume(
+        &self,
+        mut ir: ir::Context,
+        pass: &str,
+    ) -> Result<ir::Context, Failure> {
+        let passes = self.all()?;
+        let Some(idx) = passes.iter().rposition(|p| p == pass) else {
+            eprintln!("error: the IR was written after `{pass}', which is not in the pipeline");
+            return Err(1.into());
+        };
+        if self.emit_point(&passes)? == Some(idx) {
+            self.emit(&ir, pass)?;
+        }
+        let mut end = passes.len();
+        if self.opts.check
+            && !ip::Monomorphize::unbound_entrypoints(&ir).is_empty()
+        {
+            end = mono_idx(&passes).max(idx + 1);
+        }
+        self.run(&mut ir, &[], idx + 1..end)?;
+        Ok(ir)
+    }
+
+    /// Compile the checked and monomorphized program to Calyx
+    pub fn compile(&self, ir: ir::Context) -> calyx_ir::Context {
+        ip::Compile::compile(
+            ir,
+            self.opts.disable_slow_fsms,
+            self.opts.preserve_names,
+        )
+    }
+}
+
+/// Lower the Calyx program and print it as Verilog
+pub fn gen_verilog(
+    mut ctx: calyx_ir::Context,
+) -> Result<(), calyx_utils::Error> {
+    let pm = CalyxPassManager::default_passes()?;
+    let backend_conf = calyx_ir::BackendConf {
+        synthesis_mode: false,
+        enable_verification: false,
+        flat_assign: true,
+    };
+    ctx.bc = backend_conf;
+    pm.execute_plan(
+        &mut ctx,
+        &["all".to_string()],
+        &["canonicalize".to_string()],
+        false,
+    )?;
+    let backend = calyx_backend::VerilogBackend;
+    backend.run(ctx, calyx_utils::OutputFile::Stdout)
+}
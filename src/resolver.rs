@@ -1,8 +1,11 @@
-use crate::cmdline;
+use crate::{
+    library::{self, Library, Module},
+    options,
+};
 use fil_ast as ast;
-use fil_utils::{Error, FilamentResult};
+use fil_utils::{codes, Error, FilamentResult};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     fs,
     path::{Path, PathBuf},
 };
@@ -15,19 +18,57 @@ pub struct Resolver {
     input: PathBuf,
     // Files that have already been imported
     already_imported: HashSet<PathBuf>,
+    // Precompiled libraries
+    precompiled: Vec<Library>,
+    // Definitions of each file indexed by its canonical path
+    modules: BTreeMap<PathBuf, Module>,
 }
 
-impl From<&cmdline::Opts> for Resolver {
-    fn from(opts: &cmdline::Opts) -> Self {
+impl From<&options::Options> for Resolver {
+    fn from(opts: &options::Options) -> Self {
+        let options = library::options(opts);
+        let precompiled = opts
+            .precompiled
+            .iter()
+            .filter_map(|path| match Library::load(path, &options) {
+                Ok((lib, changed)) => {
+                    for file in changed {
+                        log::warn!(
+                            "Checking `{}' again because it changed since `{}' was precompiled",
+                            file.display(),
+                            path.display()
+                        );
+                    }
+                    Some(lib)
+                }
+                Err(e) => {
+                    log::warn!("Ignoring precompiled library: {e}");
+                    None
+                }
+            })
+            .collect();
         Self {
-            lib: opts.library.clone(),
-            input: opts.input.clone(),
-            already_imported: HashSet::new(),
+            precompiled,
+            ..Self::new(
+                opts.library.clone(),
+                opts.input.clone().unwrap_or_default(),
+            )
         }
     }
 }
 
 impl Resolver {
+    /// Resolve the program in `input` using `lib` to search for imports.
+    pub fn new(lib: PathBuf, input: PathBuf) -> Self {
+        Self {
+            lib,
+            input,
+            already_imported: HashSet::new(),
+            precompiled: vec![],
+            modules: BTreeMap::new(),
+        }
+    }
+
     /// Resolve import either using opts.library or relative the parent directory of the input file.
     fn resolve_import(
         &self,
@@ -50,7 +91,8 @@ impl Resolver {
                 imp,
                 fs::canonicalize(lib_base).unwrap().display(),
                 fs::canonicalize(cur_base).unwrap().display(),
-            )))
+            ))
+            .with_code(codes::AMBIGUOUS_IMPORT))
             } else {
                 Ok(cur_base)
             }
@@ -64,7 +106,8 @@ impl Resolver {
                 imp,
                 lib_base.display(),
                 cur_base.display()
-            )))
+            ))
+            .with_code(codes::UNRESOLVED_IMPORT))
         }
     }
 
@@ -100,47 +143,67 @@ impl Resolver {
         }
     }
 
-    pub fn parse_namespace(&mut self) -> FilamentResult<ast::Namespace> {
-        // Parse the top-level file
-        let mut ns = ast::FilamentParser::parse_file(&self.input)?;
+    /// The precompiled libraries used by the program
+    pub fn libraries(&self) -> &[Library] {
+        &self.precompiled
+    }
 
-        // Extern are resolved to thier absolute path relative to the input file.
-        let base = Self::parent(&self.input);
-        let mut imports: Vec<PathBuf> = ns
+    /// The definitions of the input file and every file it imports. Only
+    /// meaningful after [Resolver::parse_namespace] has been called.
+    pub fn modules(&self) -> &BTreeMap<PathBuf, Module> {
+        &self.modules
+    }
+
+    /// Parse the file or load its definitions from a precompiled library.
+    /// Returns the namespace of the file and the files it imports that have
+    /// not been imported yet.
+    fn load(
+        &mut self,
+        path: &Path,
+    ) -> FilamentResult<(ast::Namespace, Vec<PathBuf>)> {
+        let mut ns =
+            match self.precompiled.iter().find_map(|lib| lib.module(path)) {
+                Some(module) => {
+                    log::info!("Using precompiled `{}'", path.display());
+                    module.namespace()
+                }
+                None => ast::FilamentParser::parse_file(path)?,
+            };
+
+        // Imports and externs are resolved relative to the file.
+        let base = Self::parent(path);
+        let imports = ns
             .imports
             .drain(..)
-            .filter_map(|s| match self.resolve_import(&s, &base) {
-                Ok(file) => self.add_import(file).map(Ok),
-                f @ Err(_) => Some(f),
-            })
-            .collect::<FilamentResult<_>>()?;
-
+            .map(|s| self.resolve_import(&s, &base))
+            .collect::<FilamentResult<Vec<_>>>()?;
         ns.externs = ns
             .externs
             .drain(..)
             .map(|(p, imps)| (Self::absolute(p, &base), imps))
             .collect();
 
+        let module = Module::new(path, &imports, &ns).map_err(Error::misc)?;
+        self.modules.insert(fs::canonicalize(path).unwrap(), module);
+        let imports = imports
+            .into_iter()
+            .filter_map(|file| self.add_import(file))
+            .collect();
+        Ok((ns, imports))
+    }
+
+    pub fn parse_namespace(&mut self) -> FilamentResult<ast::Namespace> {
+        // Parse the top-level file
+        let input = self.input.clone();
+        let (mut ns, mut imports) = self.load(&input)?;
+
         while let Some(path) = imports.pop() {
-            let mut imp = ast::FilamentParser::parse_file(&path)?;
-            let base = Self::parent(&path);
+            let (mut imp, new) = self.load(&path)?;
             imp.components.append(&mut ns.components);
             ns.components = imp.components;
-            ns.externs.extend(
-                imp.externs
-                    .into_iter()
-                    .map(|(p, imps)| (Self::absolute(p, &base), imps)),
-            );
-            let base = Self::parent(&path);
-            imports.extend(
-                imp.imports
-                    .into_iter()
-                    .filter_map(|s| match self.resolve_import(&s, &base) {
-                        Ok(file) => self.add_import(file).map(Ok),
-                        f @ Err(_) => Some(f),
-                    })
-                    .collect::<FilamentResult<Vec<_>>>()?,
-            );
+            ns.externs.extend(imp.externs);
+            ns.precompiled.extend(imp.precompiled);
+            imports.extend(new);
         }
 
         log::trace!("Imported: {:#?}", self.already_imported);
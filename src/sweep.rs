@@ -0,0 +1,249 @@
+//! Design-space sweeps over the parameters of a component.
+//!
+//! A sweep is described by a TOML file:
+//! ```toml
+//! # Paths are relative to the sweep file
+//! file = "gen.fil"
+//! component = "Gen"
+//! library = "../.."    # optional
+//!
+//! [params]
+//! W = [8, 16, 32]                  # a list of values
+//! N = { from = 1, to = 4 }         # an inclusive range with an optional `step`
+//! L = 2                            # a single value
+//! ```
+//! Every combination of the values is checked and compiled separately.
+//! Parameters that are not listed use their default value.
+use crate::ir_passes::Metrics;
+use fil_ast as ast;
+use itertools::Itertools;
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// A sweep over the values of the signature parameters of a component
+pub struct Config {
+    /// The file defining the component
+    pub file: PathBuf,
+    /// The component to sweep
+    pub component: String,
+    /// Path to search for imports
+    pub library: Option<PathBuf>,
+    /// Values of each parameter in the order they are given
+    pub params: Vec<(String, Vec<u64>)>,
+}
+
+impl Config {
+    /// Load the sweep description from the TOML file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read `{}': {e}", path.display()))?;
+        let value: toml::Value = text
+            .parse()
+            .map_err(|e| format!("cannot parse `{}': {e}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let string = |key: &str| {
+            value.get(key).map(|v| {
+                v.as_str()
+                    .map(|s| s.to_string())
+                    .ok_or_else(|| format!("`{key}' must be a string"))
+            })
+        };
+
+        let file = string("file").ok_or("missing the `file' to sweep")??.into();
+        let component =
+            string("component").ok_or("missing the `component' to sweep")??;
+        let library = string("library").transpose()?.map(|l| dir.join(l));
+        let params = match value.get("params") {
+            None => vec![],
+            Some(toml::Value::Table(params)) => params
+                .iter()
+                .map(|(name, v)| Ok((name.clone(), Self::values(name, v)?)))
+                .collect::<Result<_, String>>()?,
+            Some(_) => return Err("`params' must be a table".to_string()),
+        };
+        Ok(Config {
+            file: dir.join::<PathBuf>(file),
+            component,
+            library,
+            params,
+        })
+    }
+
+    /// Values of a parameter given as a number, a list, or a range
+    fn values(name: &str, v: &toml::Value) -> Result<Vec<u64>, String> {
+        let num = |v: &toml::Value| {
+            v.as_integer()
+                .and_then(|n| u64::try_from(n).ok())
+                .ok_or_else(|| {
+                    format!("values of `{name}' must be natural numbers")
+                })
+        };
+        let vals = match v {
+            toml::Value::Integer(_) => vec![num(v)?],
+            toml::Value::Array(vs) => {
+                vs.iter().map(num).collect::<Result<_, _>>()?
+            }
+            toml::Value::Table(range) => {
+                let get = |key: &str| range.get(key).map(num).transpose();
+                let (Some(from), Some(to)) = (get("from")?, get("to")?) else {
+                    return Err(format!(
+                        "range of `{name}' must have a `from' and a `to'"
+                    ));
+                };
+                let step = get("step")?.unwrap_or(1);
+                if step == 0 {
+                    return Err(format!("step of `{name}' must be positive"));
+                }
+                (from..=to).step_by(step as usize).collect()
+            }
+            _ => {
+                return Err(format!(
+                    "`{name}' must be a number, a list, or a range"
+                ))
+            }
+        };
+        if vals.is_empty() {
+            return Err(format!("`{name}' does not have any values"));
+        }
+        Ok(vals)
+    }
+
+    /// Check that the component exists and that every parameter without a
+    /// default value is given values by the sweep
+    pub fn validate(&self, ns: &ast::Namespace) -> Result<(), String> {
+        let comp = &self.component;
+        let Some(sig) = ns
+            .components
+            .iter()
+            .map(|c| &c.sig)
+            .find(|sig| sig.name.inner().as_ref() == comp)
+        else {
+            return Err(format!("there is no component named `{comp}'"));
+        };
+        for (name, _) in &self.params {
+            if !sig.params.iter().any(|p| p.name().as_ref() == name) {
+                return Err(format!("`{name}' is not a parameter of `{comp}'"));
+            }
+        }
+        for p in &sig.params {
+            let name = p.name();
+            if p.default.is_none()
+                && !self.params.iter().any(|(n, _)| n == name.as_ref())
+            {
+                return Err(format!(
+                    "parameter `{name}' of `{comp}' does not have a default value and must be given values"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Every combination of the values of the parameters
+    pub fn points(&self) -> Vec<Vec<(String, u64)>> {
+        if self.params.is_empty() {
+            return vec![vec![]];
+        }
+        self.params
+            .iter()
+            .map(|(name, vals)| {
+                vals.iter().map(move |v| (name.clone(), *v)).collect_vec()
+            })
+            .multi_cartesian_product()
+            .collect()
+    }
+}
+
+/// Outcome of checking and compiling one configuration
+pub enum Outcome {
+    Valid(Metrics),
+    /// The configuration is invalid for the given reason
+    Invalid(String),
+}
+
+/// Results of a sweep written as CSV
+#[derive(Default)]
+pub struct Table {
+    rows: Vec<(Vec<(String, u64)>, Outcome)>,
+}
+
+impl Table {
+    /// Number of configurations
+    pub fn configs(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn add(&mut self, point: Vec<(String, u64)>, outcome: Outcome) {
+        self.rows.push((point, outcome))
+    }
+
+    /// Number of invalid configurations
+    pub fn invalid(&self) -> usize {
+        self.rows
+            .iter()
+            .filter(|(_, o)| matches!(o, Outcome::Invalid(_)))
+            .count()
+    }
+
+    /// Quote the field if it contains characters with a meaning in CSV
+    fn field(s: &str) -> String {
+        if s.contains([',', '"', '\n']) {
+            format!("\"{}\"", s.replace('"', "\"\""))
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// Write the table with one column for each parameter, the metrics, and
+    /// the number of instances of each primitive used by any configuration
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let params = self
+            .rows
+            .first()
+            .map(|(p, _)| p.iter().map(|(n, _)| n.clone()).collect_vec())
+            .unwrap_or_default();
+        let prims: BTreeSet<&String> = self
+            .rows
+            .iter()
+            .filter_map(|(_, o)| match o {
+                Outcome::Valid(m) => Some(m.counts.keys()),
+                Outcome::Invalid(_) => None,
+            })
+            .flatten()
+            .collect();
+
+        let header = params
+            .iter()
+            .map(|p| Self::field(p))
+            .chain(
+                ["status", "reason", "latency", "ii", "states", "area"]
+                    .map(String::from),
+            )
+            .chain(prims.iter().map(|p| Self::field(&format!("count:{p}"))))
+            .join(",");
+        writeln!(out, "{header}")?;
+
+        for (point, outcome) in &self.rows {
+            let mut row =
+                point.iter().map(|(_, v)| v.to_string()).collect_vec();
+            match outcome {
+                Outcome::Valid(m) => {
+                    row.extend(["valid".to_string(), String::new()]);
+                    row.extend(
+                        [m.latency, m.ii, m.states, m.area]
+                            .map(|v| v.to_string()),
+                    );
+                    row.extend(prims.iter().map(|p| {
+                        m.counts.get(*p).copied().unwrap_or(0).to_string()
+                    }));
+                }
+                Outcome::Invalid(reason) => {
+                    row.extend(["invalid".to_string(), Self::field(reason)]);
+                    row.extend((0..4 + prims.len()).map(|_| String::new()));
+                }
+            }
+            writeln!(out, "{}", row.join(","))?;
+        }
+        Ok(())
+    }
+}